@@ -1,6 +1,7 @@
 use node_kitties_runtime::{
-	AccountId, AuraConfig, BalancesConfig, GenesisConfig, GrandpaConfig, Signature, SudoConfig,
-	SystemConfig, WASM_BINARY,
+	AccountId, AuraConfig, BalancesConfig, ExistentialDeposit, GenesisConfig, GrandpaConfig,
+	KittiesRecoveryAccount, Signature, StakeForEachKitty, SubstrateKitties, SubstrateKittiesConfig,
+	SudoConfig, SystemConfig, WASM_BINARY,
 };
 use sc_service::ChainType;
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
@@ -122,6 +123,9 @@ pub fn local_testnet_config() -> Result<ChainSpec, String> {
 	))
 }
 
+/// How many stranded kitties the recovery account can re-stake from its genesis balance.
+const RECOVERY_STAKES: u128 = 1_000;
+
 /// Configure initial storage state for FRAME modules.
 fn testnet_genesis(
 	wasm_binary: &[u8],
@@ -138,7 +142,22 @@ fn testnet_genesis(
 		},
 		balances: BalancesConfig {
 			// Configure endowed accounts with initial balance of 1 << 60.
-			balances: endowed_accounts.iter().cloned().map(|k| (k, 1 << 60)).collect(),
+			// The kitties pallet accounts hold sale proceeds and prize pots, so keep them alive.
+			balances: endowed_accounts
+				.iter()
+				.cloned()
+				.map(|k| (k, 1 << 60))
+				.chain(std::iter::once((SubstrateKitties::account_id(), ExistentialDeposit::get())))
+				.chain(std::iter::once((
+					SubstrateKitties::proceeds_account_id(),
+					ExistentialDeposit::get(),
+				)))
+				// The recovery account re-stakes stranded kitties, so it needs funds up front.
+				.chain(std::iter::once((
+					KittiesRecoveryAccount::get(),
+					ExistentialDeposit::get() + StakeForEachKitty::get() * RECOVERY_STAKES,
+				)))
+				.collect(),
 		},
 		aura: AuraConfig {
 			authorities: initial_authorities.iter().map(|x| (x.0.clone())).collect(),
@@ -148,7 +167,11 @@ fn testnet_genesis(
 		},
 		sudo: SudoConfig {
 			// Assign network admin rights.
-			key: root_key,
+			key: root_key.clone(),
+		},
+		substrate_kitties: SubstrateKittiesConfig {
+			// The sudo key also administers the kitties pallet until rotated.
+			admin: Some(root_key),
 		},
 		transaction_payment: Default::default(),
 	}
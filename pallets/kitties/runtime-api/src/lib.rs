@@ -0,0 +1,64 @@
+//! Runtime API definition for the kitties pallet.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+// `decl_runtime_apis!` generates code that trips this lint.
+#![allow(clippy::too_many_arguments, clippy::unnecessary_mut_passed)]
+
+use codec::Codec;
+use pallet_kitties::{BuyQuote, ImageDescriptor, Kitty, OrderBookPage, PriceInfo};
+use sp_std::vec::Vec;
+
+sp_api::decl_runtime_apis! {
+	pub trait KittiesApi<AccountId, KittyIndex, Balance, BlockNumber, AssetId> where
+		AccountId: Codec,
+		KittyIndex: Codec,
+		Balance: Codec,
+		BlockNumber: Codec,
+		AssetId: Codec,
+	{
+		/// The stake currently required for `kitty_id`, `None` if the kitty is unknown. While demand
+		/// is high this includes the raised amount, which the owner may not have reserved yet.
+		fn kitty_stake(kitty_id: KittyIndex) -> Option<Balance>;
+		/// Token metadata and base amounts clients need to format prices consistently.
+		fn price_info() -> PriceInfo<Balance>;
+		/// The full cost breakdown of `buyer` buying `kitty_id` right now, `None` if the purchase
+		/// would be rejected.
+		fn quote_buy(kitty_id: KittyIndex, buyer: AccountId) -> Option<BuyQuote<Balance>>;
+		/// At most `MaxQueryResults` kitties owned by `owner`, and whether the list was truncated.
+		fn kitties_of(owner: AccountId) -> (Vec<KittyIndex>, bool);
+		/// Up to `limit` (capped at `MaxQueryResults`) kitties owned by `owner`, skipping the
+		/// first `start`.
+		fn kitties_of_paged(owner: AccountId, start: u32, limit: u32) -> Vec<KittyIndex>;
+		/// The kitty records for `ids`, aligned with the input and `None` for unknown ids. Only the
+		/// first `MaxQueryResults` ids are looked up.
+		fn kitties_by_ids(ids: Vec<KittyIndex>) -> Vec<Option<Kitty>>;
+		/// A human-readable message for the pallet error with `index`, `None` for unknown indices.
+		fn describe_error(index: u8) -> Option<Vec<u8>>;
+		/// The kitty with the highest rarity score and its score, `None` if no kitty exists.
+		fn rarest_kitty() -> Option<(KittyIndex, u32)>;
+		/// The block of the last successful signed call made by `who`, `None` if the account has
+		/// never made one. Receiving a kitty does not count.
+		fn last_activity(who: AccountId) -> Option<BlockNumber>;
+		/// `(generation, count)` pairs of living kitties, sorted by generation.
+		fn supply_by_generation() -> Vec<(u32, u32)>;
+		/// Whether `who` currently owns `kitty_id`, `false` for unknown kitties.
+		fn owns(who: AccountId, kitty_id: KittyIndex) -> bool;
+		/// Up to `limit` (capped at `MaxQueryResults`) fixed-price listings and live auctions in
+		/// kitty id order from `start`, with the id to resume from. At most `MaxOrderBookScan`
+		/// ids are checked per call, so a page may come back short with `next` still set.
+		/// Auctions past their end block are left out even before they settle.
+		fn order_book(
+			start: KittyIndex,
+			limit: u32,
+		) -> OrderBookPage<AccountId, KittyIndex, Balance, BlockNumber, AssetId>;
+		/// A renderer-independent image description derived from the kitty's DNA, `None` if the
+		/// kitty is unknown. Layers are listed bottom to top: background, body, pattern, belly,
+		/// ears, eyes, mouth, accessory. Layer `i` takes its variant from DNA byte `2i` and its
+		/// palette entry from byte `2i + 1`.
+		fn image_descriptor(kitty_id: KittyIndex) -> Option<ImageDescriptor>;
+		/// The estimated value of at most `MaxQueryResults` kitties owned by `who`, and whether
+		/// the holdings were truncated. Each kitty is valued at its native listing price, else its
+		/// last native sale price, else zero.
+		fn portfolio_value(who: AccountId) -> (Balance, bool);
+	}
+}
@@ -0,0 +1,38 @@
+//! Benchmarking setup for pallet-kitties
+
+use super::*;
+
+use crate::Pallet as Kitties;
+use frame_benchmarking::{account, benchmarks, impl_benchmark_test_suite, whitelisted_caller};
+use frame_support::traits::{Currency, Get, Hooks};
+use frame_system::RawOrigin;
+
+benchmarks! {
+	// 区块中执行n个到期的预定转移
+	on_initialize {
+		let n in 0 .. T::MaxScheduledPerBlock::get();
+		let owner: T::AccountId = whitelisted_caller();
+		let recipient: T::AccountId = account("recipient", 0, 0);
+		let funds = T::StakeForEachKitty::get().saturating_mul(1_000_000u32.into());
+		T::Currency::make_free_balance_be(&owner, funds);
+		T::Currency::make_free_balance_be(&recipient, funds);
+		let at: T::BlockNumber = 2u32.into();
+		for i in 0 .. n {
+			let kitty_id = Kitties::<T>::do_mint(&owner, [i as u8; 16])?;
+			Kitties::<T>::schedule_transfer(
+				RawOrigin::Signed(owner.clone()).into(),
+				recipient.clone(),
+				kitty_id,
+				at,
+			)?;
+		}
+	}: {
+		Kitties::<T>::on_initialize(at);
+	}
+	verify {
+		assert!(Agenda::<T>::iter_prefix(at).next().is_none());
+		assert!(AgendaCursor::<T>::get().is_none());
+	}
+}
+
+impl_benchmark_test_suite!(Kitties, crate::mock::new_test_ext(), crate::mock::Test);
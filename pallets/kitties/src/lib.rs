@@ -1,4 +1,5 @@
 #![cfg_attr(not(feature = "std"), no_std)]
+use frame_support::dispatch::{DispatchError, DispatchResult};
 pub use pallet::*;
 
 #[cfg(test)]
@@ -7,26 +8,292 @@ mod mock;
 #[cfg(test)]
 mod tests;
 
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
+pub mod migrations;
+pub mod weights;
+
+// 供其他模块直接调用的Kitty操作接口，无需构造Origin
+pub trait KittyMutate<AccountId> {
+	type KittyIndex;
+	type Balance;
+
+	// 为owner创建并质押一个新的Kitty
+	fn do_mint(owner: &AccountId, dna: [u8; 16]) -> Result<Self::KittyIndex, DispatchError>;
+	// 将Kitty从from转移给to，to要求确认时改为等待to确认
+	fn do_transfer(from: &AccountId, to: &AccountId, kitty_id: Self::KittyIndex) -> DispatchResult;
+	// 以price结算buyer向seller购买Kitty的交易，Kitty须以该价格挂单出售
+	fn do_settle_sale(
+		buyer: &AccountId,
+		seller: &AccountId,
+		kitty_id: Self::KittyIndex,
+		price: Self::Balance,
+	) -> DispatchResult;
+}
+
+// 将Kitty映射为其他NFT集合（如pallet-uniques）中NFT的桥接接口
+pub trait NftBridge<AccountId, KittyIndex> {
+	// 外部集合中NFT的标识
+	type NftId: frame_support::Parameter;
+
+	// 为owner在外部集合中创建与Kitty对应的NFT
+	fn mint(
+		owner: &AccountId,
+		kitty_id: KittyIndex,
+		dna: [u8; 16],
+	) -> Result<Self::NftId, DispatchError>;
+	// 销毁holder持有的外部NFT，holder不持有该NFT时返回错误
+	fn burn(holder: &AccountId, nft_id: &Self::NftId) -> DispatchResult;
+}
+
+// 没有配置外部集合时拒绝导出
+impl<AccountId, KittyIndex> NftBridge<AccountId, KittyIndex> for () {
+	type NftId = ();
+
+	fn mint(
+		_owner: &AccountId,
+		_kitty_id: KittyIndex,
+		_dna: [u8; 16],
+	) -> Result<(), DispatchError> {
+		Err(DispatchError::Other("NftBridge not configured"))
+	}
+
+	fn burn(_holder: &AccountId, _nft_id: &()) -> DispatchResult {
+		Err(DispatchError::Other("NftBridge not configured"))
+	}
+}
+
 #[frame_support::pallet]
 pub mod pallet {
 	use frame_support::{
 		dispatch::DispatchResult,
 		ensure,
 		pallet_prelude::*,
-		sp_runtime::traits::{AtLeast32BitUnsigned, Bounded},
-		traits::{Currency, ExistenceRequirement, Randomness, ReservableCurrency},
+		sp_runtime::{
+			traits::{
+				AccountIdConversion, AtLeast32BitUnsigned, Bounded, CheckedAdd, IdentifyAccount,
+				One, Saturating, Verify, Zero,
+			},
+			Permill,
+		},
+		traits::{
+			fungibles, Currency, ExistenceRequirement, OnUnbalanced, Randomness,
+			ReservableCurrency, WithdrawReasons,
+		},
+		transactional, PalletId,
 	};
 	use frame_system::{ensure_signed, pallet_prelude::*};
 	use scale_info::TypeInfo;
+	#[cfg(feature = "std")]
+	use serde::{Deserialize, Serialize};
 	use sp_io::hashing::blake2_128;
+	use sp_std::prelude::*;
 
-	#[derive(Encode, Decode, TypeInfo)]
+	pub use crate::weights::WeightInfo;
+	use crate::NftBridge;
+
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
 	pub struct Kitty {
 		pub dna: [u8; 16],
+		// 克隆体与原Kitty的DNA相同，不能繁殖
+		pub clone: bool,
+	}
+
+	// 未售出时转移给备用拥有者的计划
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+	pub struct FallbackTransfer<AccountId, BlockNumber> {
+		pub owner: AccountId,
+		pub fallback_owner: AccountId,
+		pub deadline: BlockNumber,
+	}
+
+	// 写入链下索引的Kitty历史记录
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+	pub enum HistoryRecord<AccountId, Balance> {
+		Mint { owner: AccountId, dna: [u8; 16] },
+		Transfer { from: AccountId, to: AccountId },
+		Sale { seller: AccountId, buyer: AccountId, price: Balance },
+		Burn { owner: AccountId },
+		Abandon { owner: AccountId },
+		Adopt { owner: AccountId },
+	}
+
+	// 供客户端统一格式化价格的代币信息
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+	pub struct PriceInfo<Balance> {
+		pub token_symbol: Vec<u8>,
+		pub decimals: u8,
+		pub existential_deposit: Balance,
+		pub stake_per_kitty: Balance,
+	}
+
+	// 形象的图层，渲染时按声明顺序从下往上叠加
+	#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+	#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+	pub enum LayerKind {
+		Background,
+		Body,
+		Pattern,
+		Belly,
+		Ears,
+		Eyes,
+		Mouth,
+		Accessory,
+	}
+
+	// 图层使用的素材编号和调色板编号
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+	#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+	pub struct ImageLayer {
+		pub kind: LayerKind,
+		pub variant: u8,
+		pub palette: u8,
+	}
+
+	// 由DNA确定的形象描述，相同的DNA总是得到相同的形象
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+	#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+	pub struct ImageDescriptor {
+		// 按从下往上的顺序排列
+		pub layers: Vec<ImageLayer>,
+		// 克隆体的形象与原Kitty相同，由渲染器另外标记
+		pub clone: bool,
+	}
+
+	// 购买Kitty的费用明细，市场手续费和版税从价格中扣除
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+	pub struct BuyQuote<Balance> {
+		pub price: Balance,
+		pub marketplace_fee: Balance,
+		pub royalty: Balance,
+		pub seller_amount: Balance,
+		// 买家需要质押的金额
+		pub stake: Balance,
+		// 买家需要支付的总金额
+		pub total: Balance,
+	}
+
+	// 分期购买计划，已付款项由模块账户托管
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+	pub struct InstallmentPlan<AccountId, Balance, BlockNumber> {
+		pub buyer: AccountId,
+		pub seller: AccountId,
+		pub price: Balance,
+		pub installments: u32,
+		pub paid_installments: u32,
+		pub paid: Balance,
+		// 下一期的截止区块
+		pub next_due: BlockNumber,
+	}
+
+	// 拍卖，最高出价由出价者质押
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+	pub struct Auction<AccountId, Balance, BlockNumber> {
+		pub seller: AccountId,
+		// 保留价，出价不能低于该价格
+		pub reserve: Balance,
+		pub end: BlockNumber,
+		// 最高出价及其出价区块。出价相同时最早的出价获胜，
+		// 之后相同金额的出价记录在TiedBids中，结算时退还
+		pub highest_bid: Option<(AccountId, Balance, BlockNumber)>,
+	}
+
+	// Kitty当前的交易状态
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+	pub enum KittyStatus<Balance> {
+		Idle,
+		Listed(Balance),
+		InAuction,
+		Locked,
+	}
+
+	// 订单簿中一个Kitty的出售方式
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+	pub enum OrderKind<AccountId, Balance, BlockNumber, AssetId> {
+		// 一口价出售，asset为空时使用原生代币定价
+		FixedPrice { price: Balance, asset: Option<AssetId> },
+		// 进行中的拍卖及其当前最高出价
+		Auction { reserve: Balance, end: BlockNumber, highest_bid: Option<(AccountId, Balance)> },
+	}
+
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+	pub struct OrderBookEntry<AccountId, KittyIndex, Balance, BlockNumber, AssetId> {
+		pub kitty_id: KittyIndex,
+		pub seller: AccountId,
+		pub kind: OrderKind<AccountId, Balance, BlockNumber, AssetId>,
+	}
+
+	// 按Kitty ID排序的一页订单簿，next为下一页的起始ID，没有更多时为空
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+	pub struct OrderBookPage<AccountId, KittyIndex, Balance, BlockNumber, AssetId> {
+		pub entries: Vec<OrderBookEntry<AccountId, KittyIndex, Balance, BlockNumber, AssetId>>,
+		pub next: Option<KittyIndex>,
+	}
+
+	pub type RaceId = u32;
+
+	pub type BundleId = u32;
+
+	// 管理员创建的比赛
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+	pub struct Race<Balance, BlockNumber> {
+		pub entry_fee: Balance,
+		pub start: BlockNumber,
+		pub pot: Balance,
+	}
+
+	// Kitty被锁定的原因，锁定期间不能交易
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+	pub enum KittyLock {
+		Race(RaceId),
+		Installment,
+		Auction,
+		Bundle(BundleId),
+		ScheduledTransfer,
+		PendingTransfer,
+		Exported,
+	}
+
+	// 在指定区块执行的任务
+	#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+	pub enum ScheduledTask<KittyIndex> {
+		FallbackTransfer(KittyIndex),
+		ResolveRace(RaceId),
+		InstallmentDue(KittyIndex),
+		SettleAuction(KittyIndex),
+		ScheduledTransfer(KittyIndex),
+		ExpireIncoming(KittyIndex),
 	}
 
 	type BalanceOf<T> =
 		<<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+	type NegativeImbalanceOf<T> = <<T as Config>::Currency as Currency<
+		<T as frame_system::Config>::AccountId,
+	>>::NegativeImbalance;
+
+	// 从下往上的图层顺序，第i层使用DNA的第2i和2i+1个字节
+	const IMAGE_LAYERS: [LayerKind; 8] = [
+		LayerKind::Background,
+		LayerKind::Body,
+		LayerKind::Pattern,
+		LayerKind::Belly,
+		LayerKind::Ears,
+		LayerKind::Eyes,
+		LayerKind::Mouth,
+		LayerKind::Accessory,
+	];
+	// 每个图层的素材数量
+	pub const LAYER_VARIANTS: u8 = 16;
+	// 调色板中的颜色数量
+	pub const PALETTE_SIZE: u8 = 32;
+
+	// 每轮变异可以改变的DNA位
+	const MUTATION_MASK: u8 = 0b0000_1111;
+
+	// 链下索引中Kitty历史记录的键前缀
+	// 完整的键为 `kitties/history/<kitty_id>/<seq>`，其中kitty_id和seq均为SCALE编码
+	pub const HISTORY_KEY_PREFIX: &[u8] = b"kitties/history/";
 
 	#[pallet::storage]
 	#[pallet::getter(fn kitties_count)]
@@ -47,18 +314,568 @@ pub mod pallet {
 	pub type ListForSale<T: Config> =
 		StorageMap<_, Blake2_128Concat, T::KittyIndex, Option<BalanceOf<T>>, ValueQuery>;
 
+	#[pallet::storage]
+	#[pallet::getter(fn kitty_stake)]
+	pub type KittyStake<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::KittyIndex, BalanceOf<T>, OptionQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn listing_deposit)]
+	pub type ListingDeposits<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::KittyIndex, (T::AccountId, BalanceOf<T>), OptionQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn fallback_transfer)]
+	pub type FallbackTransfers<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		T::KittyIndex,
+		FallbackTransfer<T::AccountId, T::BlockNumber>,
+		OptionQuery,
+	>;
+
+	#[pallet::storage]
+	pub type Agenda<T: Config> = StorageDoubleMap<
+		_,
+		Twox64Concat,
+		T::BlockNumber,
+		Blake2_128Concat,
+		ScheduledTask<T::KittyIndex>,
+		(),
+		OptionQuery,
+	>;
+
+	// 每个Kitty已写入链下索引的历史记录数量，仅在开启链下索引时更新
+	#[pallet::storage]
+	#[pallet::getter(fn history_length)]
+	pub type HistoryLength<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::KittyIndex, u32, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn kitty_lock)]
+	pub type KittyLocks<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::KittyIndex, KittyLock, OptionQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn next_race_id)]
+	pub type NextRaceId<T: Config> = StorageValue<_, RaceId, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn races)]
+	pub type Races<T: Config> =
+		StorageMap<_, Twox64Concat, RaceId, Race<BalanceOf<T>, T::BlockNumber>, OptionQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn race_entrants)]
+	pub type RaceEntrants<T: Config> = StorageMap<
+		_,
+		Twox64Concat,
+		RaceId,
+		BoundedVec<(T::AccountId, T::KittyIndex), T::MaxRaceEntrants>,
+		ValueQuery,
+	>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn creator)]
+	pub type KittyCreator<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::KittyIndex, T::AccountId, OptionQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn pending_royalties)]
+	pub type PendingRoyalties<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, BalanceOf<T>, ValueQuery>;
+
+	// 克隆体复制自的Kitty
+	// 克隆体单独记录在这里，DNA唯一性检查应跳过此处记录的Kitty
+	#[pallet::storage]
+	#[pallet::getter(fn cloned_from)]
+	pub type ClonedFrom<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::KittyIndex, T::KittyIndex, OptionQuery>;
+
+	// 卖家等待领取的交易收入
+	#[pallet::storage]
+	#[pallet::getter(fn proceeds)]
+	pub type Proceeds<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, BalanceOf<T>, ValueQuery>;
+
+	// 每个评选周期内每个Kitty获得的票数
+	#[pallet::storage]
+	#[pallet::getter(fn featured_votes)]
+	pub type FeaturedVotes<T: Config> = StorageDoubleMap<
+		_,
+		Twox64Concat,
+		T::BlockNumber,
+		Blake2_128Concat,
+		T::KittyIndex,
+		u32,
+		ValueQuery,
+	>;
+
+	// 每个评选周期内每个账户投票的Kitty
+	#[pallet::storage]
+	#[pallet::getter(fn featured_vote_of)]
+	pub type FeaturedVoters<T: Config> = StorageDoubleMap<
+		_,
+		Twox64Concat,
+		T::BlockNumber,
+		Blake2_128Concat,
+		T::AccountId,
+		T::KittyIndex,
+		OptionQuery,
+	>;
+
+	// 上一个评选周期的获胜Kitty
+	#[pallet::storage]
+	#[pallet::getter(fn featured_kitty)]
+	pub type FeaturedKitty<T: Config> = StorageValue<_, T::KittyIndex, OptionQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn installment_plan)]
+	pub type InstallmentPlans<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		T::KittyIndex,
+		InstallmentPlan<T::AccountId, BalanceOf<T>, T::BlockNumber>,
+		OptionQuery,
+	>;
+
+	// 拥有者的私人笔记，内容由客户端加密
+	#[pallet::storage]
+	#[pallet::getter(fn private_note)]
+	pub type PrivateNotes<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		T::KittyIndex,
+		BoundedVec<u8, T::MaxNoteLength>,
+		OptionQuery,
+	>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn note_deposit)]
+	pub type NoteDeposits<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::KittyIndex, (T::AccountId, BalanceOf<T>), OptionQuery>;
+
+	// 拥有者到其Kitty的索引，与Owner保持一致
+	#[pallet::storage]
+	pub type OwnedKitties<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		Blake2_128Concat,
+		T::KittyIndex,
+		(),
+		OptionQuery,
+	>;
+
+	// 购买后可以再次出售的区块
+	#[pallet::storage]
+	#[pallet::getter(fn resale_unlock_block)]
+	pub type ResaleUnlockAt<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::KittyIndex, T::BlockNumber, OptionQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn auction)]
+	pub type Auctions<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		T::KittyIndex,
+		Auction<T::AccountId, BalanceOf<T>, T::BlockNumber>,
+		OptionQuery,
+	>;
+
+	// 使用其他资产定价的出售，没有记录时使用原生代币
+	#[pallet::storage]
+	#[pallet::getter(fn listing_asset)]
+	pub type ListingAsset<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::KittyIndex, T::AssetId, OptionQuery>;
+
+	// 对Kitty的报价，报价金额由报价者质押
+	#[pallet::storage]
+	#[pallet::getter(fn offer)]
+	pub type Offers<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		T::KittyIndex,
+		Blake2_128Concat,
+		T::AccountId,
+		BalanceOf<T>,
+		OptionQuery,
+	>;
+
+	// 每个Kitty待处理的报价数量
+	#[pallet::storage]
+	#[pallet::getter(fn offer_count)]
+	pub type OfferCount<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::KittyIndex, u32, ValueQuery>;
+
+	// 报价者的报价索引
+	#[pallet::storage]
+	pub type BidderOffers<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		Blake2_128Concat,
+		T::KittyIndex,
+		(),
+		OptionQuery,
+	>;
+
+	// 每个账户待处理的报价数量
+	#[pallet::storage]
+	#[pallet::getter(fn account_offer_count)]
+	pub type AccountOfferCount<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, u32, ValueQuery>;
+
+	// 与最高出价金额相同但出价较晚的出价及其出价区块
+	#[pallet::storage]
+	#[pallet::getter(fn tied_bid)]
+	pub type TiedBids<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		T::KittyIndex,
+		Blake2_128Concat,
+		T::AccountId,
+		(BalanceOf<T>, T::BlockNumber),
+		OptionQuery,
+	>;
+
+	// Kitty的创建区块
+	#[pallet::storage]
+	#[pallet::getter(fn created_at)]
+	pub type CreatedAt<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::KittyIndex, T::BlockNumber, OptionQuery>;
+
+	#[pallet::storage]
+	pub type NextBundleId<T: Config> = StorageValue<_, BundleId, ValueQuery>;
+
+	// 捆绑包的拥有者及其包含的Kitty
+	#[pallet::storage]
+	#[pallet::getter(fn bundle)]
+	pub type Bundles<T: Config> = StorageMap<
+		_,
+		Twox64Concat,
+		BundleId,
+		(T::AccountId, BoundedVec<T::KittyIndex, T::MaxBundleSize>),
+		OptionQuery,
+	>;
+
+	// Kitty所在的捆绑包，一个Kitty只能在一个捆绑包中
+	#[pallet::storage]
+	#[pallet::getter(fn bundle_of)]
+	pub type KittyBundle<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::KittyIndex, BundleId, OptionQuery>;
+
+	// 出售中的捆绑包及其价格
+	#[pallet::storage]
+	#[pallet::getter(fn bundle_price)]
+	pub type BundleListings<T: Config> =
+		StorageMap<_, Twox64Concat, BundleId, BalanceOf<T>, OptionQuery>;
+
+	// 需求旺盛时提高了质押要求但拥有者尚未补足的差额，以及补足的截止区块
+	#[pallet::storage]
+	#[pallet::getter(fn stake_shortfall)]
+	pub type StakeShortfall<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::KittyIndex, (BalanceOf<T>, T::BlockNumber), OptionQuery>;
+
+	// 需求旺盛时提高的质押金额，报价数量回落到阈值以下或拥有者改变时撤销
+	#[pallet::storage]
+	#[pallet::getter(fn demand_stake_raise)]
+	pub type DemandStakeRaise<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::KittyIndex, BalanceOf<T>, OptionQuery>;
+
+	// 模块管理员，与ForceOrigin一样可以调用特权交易
+	#[pallet::storage]
+	#[pallet::getter(fn admin)]
+	pub type Admin<T: Config> = StorageValue<_, T::AccountId, OptionQuery>;
+
+	// 当前稀有度最高的Kitty及其稀有度
+	#[pallet::storage]
+	#[pallet::getter(fn rarest_kitty)]
+	pub type RarestKitty<T: Config> = StorageValue<_, (T::KittyIndex, u32), OptionQuery>;
+
+	// 赞助者已使用的授权数量，防止授权被重复使用
+	#[pallet::storage]
+	#[pallet::getter(fn sponsor_nonce)]
+	pub type SponsorNonces<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, u32, ValueQuery>;
+
+	// 账户最近一次调用交易的区块，用于统计不活跃的持有者
+	#[pallet::storage]
+	#[pallet::getter(fn last_activity)]
+	pub type LastActivity<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, T::BlockNumber, OptionQuery>;
+
+	// 预定的转移：接收者和执行区块
+	#[pallet::storage]
+	#[pallet::getter(fn scheduled_transfer)]
+	pub type ScheduledTransfers<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::KittyIndex, (T::AccountId, T::BlockNumber), OptionQuery>;
+
+	// 灵魂绑定的Kitty，不能转移或出售，只能销毁
+	#[pallet::storage]
+	#[pallet::getter(fn is_soulbound)]
+	pub type Soulbound<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::KittyIndex, bool, ValueQuery>;
+
+	// Kitty的代数，创建的Kitty为第0代，繁殖的Kitty比父母中较高的一代多一代
+	#[pallet::storage]
+	#[pallet::getter(fn generation_of)]
+	pub type KittyGeneration<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::KittyIndex, u32, ValueQuery>;
+
+	// 每一代现存的Kitty数量
+	#[pallet::storage]
+	#[pallet::getter(fn generation_count)]
+	pub type GenerationCounts<T: Config> = StorageMap<_, Twox64Concat, u32, u32, ValueQuery>;
+
+	// 账户最近一次创建Kitty的区块
+	#[pallet::storage]
+	#[pallet::getter(fn last_create)]
+	pub type LastCreate<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, T::BlockNumber, OptionQuery>;
+
+	// 需要确认才能接收转移的账户
+	#[pallet::storage]
+	#[pallet::getter(fn requires_acceptance)]
+	pub type RequireAcceptance<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, bool, ValueQuery>;
+
+	// 等待接收者确认的转移：发送者、接收者和过期区块
+	#[pallet::storage]
+	#[pallet::getter(fn pending_transfer)]
+	pub type PendingTransfers<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		T::KittyIndex,
+		(T::AccountId, T::AccountId, T::BlockNumber),
+		OptionQuery,
+	>;
+
+	// 总量封顶后不能再创建新的Kitty，不能解除
+	#[pallet::storage]
+	#[pallet::getter(fn is_supply_sealed)]
+	pub type SupplySealed<T: Config> = StorageValue<_, bool, ValueQuery>;
+
+	// 账户连续繁殖出非稀有Kitty的次数
+	#[pallet::storage]
+	#[pallet::getter(fn pity_counter)]
+	pub type PityCounter<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, u32, ValueQuery>;
+
+	// 已导出到外部集合的Kitty及其对应的NFT
+	#[pallet::storage]
+	#[pallet::getter(fn exported_nft)]
+	pub type ExportedKitties<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		T::KittyIndex,
+		<T::NftBridge as NftBridge<T::AccountId, T::KittyIndex>>::NftId,
+		OptionQuery,
+	>;
+
+	// Kitty最近的拥有者及其失去Kitty的区块，最早的记录在前
+	#[pallet::storage]
+	#[pallet::getter(fn recent_owners)]
+	pub type RecentOwners<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		T::KittyIndex,
+		BoundedVec<(T::AccountId, T::BlockNumber), T::MaxRecentOwners>,
+		ValueQuery,
+	>;
+
+	// Kitty最近一次以原生代币成交的价格
+	#[pallet::storage]
+	#[pallet::getter(fn last_sale_price)]
+	pub type LastSalePrice<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::KittyIndex, BalanceOf<T>, OptionQuery>;
+
+	// 未完成的recount：下次继续遍历的原始存储键和已遍历到的最大Kitty ID
+	#[pallet::storage]
+	pub type RecountCursor<T: Config> =
+		StorageValue<_, (Vec<u8>, Option<T::KittyIndex>), OptionQuery>;
+
+	// 还有未执行任务的最早区块，下个区块从这里继续执行
+	#[pallet::storage]
+	#[pallet::getter(fn agenda_cursor)]
+	pub type AgendaCursor<T: Config> = StorageValue<_, T::BlockNumber, OptionQuery>;
+
+	// 正在评选的周期和目前票数最多的Kitty，下个区块从这里继续评选
+	#[pallet::storage]
+	#[pallet::getter(fn featured_tally)]
+	pub type FeaturedTally<T: Config> =
+		StorageValue<_, (T::BlockNumber, Option<(T::KittyIndex, u32)>), OptionQuery>;
+
+	// 存储结构改变时递增，并在migrations中添加对应的迁移
+	const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
+
 	#[pallet::pallet]
 	#[pallet::generate_store(pub(super) trait Store)]
+	#[pallet::storage_version(STORAGE_VERSION)]
 	pub struct Pallet<T>(_);
 
 	#[pallet::config]
 	pub trait Config: frame_system::Config {
 		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
 		type Randomness: Randomness<Self::Hash, Self::BlockNumber>;
+		// 第二个随机数来源，与Randomness组合生成DNA，单个来源被操纵时无法完全控制结果
+		// 不需要时配置为与Randomness相同的来源
+		type SecondaryRandomness: Randomness<Self::Hash, Self::BlockNumber>;
 		type Currency: Currency<Self::AccountId> + ReservableCurrency<Self::AccountId>;
 		#[pallet::constant]
 		type StakeForEachKitty: Get<BalanceOf<Self>>;
 		type KittyIndex: Parameter + AtLeast32BitUnsigned + Default + Copy + Bounded;
+		#[pallet::constant]
+		type ListingDeposit: Get<BalanceOf<Self>>;
+		type ForceOrigin: EnsureOrigin<Self::Origin>;
+		#[pallet::constant]
+		type PremiumBreedFee: Get<BalanceOf<Self>>;
+		#[pallet::constant]
+		type MaxPremiumTier: Get<u8>;
+		type FeeDestination: OnUnbalanced<NegativeImbalanceOf<Self>>;
+		// 是否将Kitty历史记录写入链下索引
+		type EnableOffchainIndexing: Get<bool>;
+		#[pallet::constant]
+		type TokenSymbol: Get<Vec<u8>>;
+		#[pallet::constant]
+		type TokenDecimals: Get<u8>;
+		#[pallet::constant]
+		type PalletId: Get<PalletId>;
+		#[pallet::constant]
+		type MaxRaceEntrants: Get<u32>;
+		// 比赛奖池按名次分配的比例，第一名额外获得取整余数
+		#[pallet::constant]
+		type RacePrizeSplit: Get<Vec<Permill>>;
+		// 每笔交易中创建者获得的版税比例
+		#[pallet::constant]
+		type RoyaltyRate: Get<Permill>;
+		#[pallet::constant]
+		type CloneFee: Get<BalanceOf<Self>>;
+		// 从该区块开始才允许交易，创建不受限制
+		#[pallet::constant]
+		type TradingStartBlock: Get<Self::BlockNumber>;
+		// 评选本周Kitty的周期长度
+		#[pallet::constant]
+		type FeaturedPeriod: Get<Self::BlockNumber>;
+		// 每笔交易收取的市场手续费比例，交给FeeDestination处理
+		#[pallet::constant]
+		type MarketplaceFee: Get<Permill>;
+		// 稀有Kitty交易时在市场手续费之外额外收取的比例
+		// 每项为(最低稀有度, 额外比例)，按最低稀有度升序排列，使用稀有度达到的最高一档
+		// 稀有度低于第一档或为克隆体时不额外收取
+		#[pallet::constant]
+		type RarityFeeTiers: Get<Vec<(u32, Permill)>>;
+		#[pallet::constant]
+		type MaxInstallments: Get<u32>;
+		// 每期付款的期限
+		#[pallet::constant]
+		type InstallmentPeriod: Get<Self::BlockNumber>;
+		// 逾期时从已付款项中扣除并补偿给卖家的比例
+		#[pallet::constant]
+		type InstallmentPenalty: Get<Permill>;
+		#[pallet::constant]
+		type MaxNoteLength: Get<u32>;
+		// 私人笔记每字节需要质押的押金
+		#[pallet::constant]
+		type NoteDepositPerByte: Get<BalanceOf<Self>>;
+		// 查询接口单次最多返回的结果数量
+		#[pallet::constant]
+		type MaxQueryResults: Get<u32>;
+		// 购买后需要等待多少区块才能再次出售，为零时不限制
+		#[pallet::constant]
+		type ResaleCooldown: Get<Self::BlockNumber>;
+		// 接收质押丢失的Kitty的账户
+		type RecoveryAccount: Get<Self::AccountId>;
+		// Kitty数量达到KittyIndex最大值的该比例时发出警告
+		#[pallet::constant]
+		type SupplyWarningThreshold: Get<Permill>;
+		type AssetId: Parameter + Copy;
+		// 以其他资产出售时用于结算的资产
+		type Assets: fungibles::Transfer<
+			Self::AccountId,
+			AssetId = Self::AssetId,
+			Balance = BalanceOf<Self>,
+		>;
+		// 允许用于出售的资产
+		#[pallet::constant]
+		type AllowedAssets: Get<Vec<Self::AssetId>>;
+		// 接收以其他资产结算时的市场手续费的账户
+		type FeeAssetDestination: Get<Self::AccountId>;
+		#[pallet::constant]
+		type MaxOffersPerKitty: Get<u32>;
+		// 每个账户最多同时保留的报价数量
+		#[pallet::constant]
+		type MaxOffersPerAccount: Get<u32>;
+		// Kitty创建后需要经过的区块数才能销毁
+		#[pallet::constant]
+		type MinAgeBeforeBurn: Get<Self::BlockNumber>;
+		#[pallet::constant]
+		type MaxBundleSize: Get<u32>;
+		// 报价数量达到该值时提高Kitty的质押要求，为零时不提高
+		#[pallet::constant]
+		type HighDemandOfferThreshold: Get<u32>;
+		// 提高的质押比例
+		#[pallet::constant]
+		type HighDemandStakeIncrease: Get<Permill>;
+		// 拥有者补足质押的宽限期
+		#[pallet::constant]
+		type HighDemandGracePeriod: Get<Self::BlockNumber>;
+		// 赞助者授权繁殖时使用的签名
+		type SponsorSignature: Verify<Signer = Self::SponsorPublic> + Parameter;
+		type SponsorPublic: IdentifyAccount<AccountId = Self::AccountId>;
+		#[pallet::constant]
+		type GambleFee: Get<BalanceOf<Self>>;
+		// 赌博获胜的概率
+		#[pallet::constant]
+		type GambleWinChance: Get<Permill>;
+		// 每个区块最多执行的预定任务数量
+		#[pallet::constant]
+		type MaxScheduledPerBlock: Get<u32>;
+		// 同一账户两次创建Kitty之间需要间隔的区块数
+		#[pallet::constant]
+		type CreateCooldown: Get<Self::BlockNumber>;
+		// 等待确认的转移在多少个区块后过期
+		#[pallet::constant]
+		type IncomingTransferExpiry: Get<Self::BlockNumber>;
+		// 稀有度达到该值的Kitty为稀有Kitty
+		#[pallet::constant]
+		type RareRarity: Get<u32>;
+		// 连续繁殖出该数量的非稀有Kitty后，下一次繁殖保证得到稀有Kitty，为0时关闭
+		#[pallet::constant]
+		type PityThreshold: Get<u32>;
+		// 导出Kitty时使用的外部NFT集合
+		type NftBridge: NftBridge<Self::AccountId, Self::KittyIndex>;
+		// 批量交易中最多包含的Kitty数量
+		#[pallet::constant]
+		type MaxBatchSize: Get<u32>;
+		// 防止对敲：最近的拥有者在失去Kitty后的该区块数内不能买回，为0时关闭
+		// 只能识别直接买回，通过记录之外的新账户或超过MaxRecentOwners次的转手仍然无法识别
+		#[pallet::constant]
+		type WashTradeCooldown: Get<Self::BlockNumber>;
+		// 每个Kitty记录的最近拥有者数量
+		#[pallet::constant]
+		type MaxRecentOwners: Get<u32>;
+		// 繁殖时消耗的材料代币
+		type BreedingMaterial: fungibles::Mutate<
+			Self::AccountId,
+			AssetId = Self::AssetId,
+			Balance = BalanceOf<Self>,
+		>;
+		#[pallet::constant]
+		type BreedingMaterialAsset: Get<Self::AssetId>;
+		// 每次繁殖销毁的材料数量，为0时不需要材料
+		#[pallet::constant]
+		type BreedingMaterialPerBreed: Get<BalanceOf<Self>>;
+		// 拍卖的最长持续区块数
+		#[pallet::constant]
+		type MaxAuctionDuration: Get<Self::BlockNumber>;
+		// 评选本周Kitty时每个区块最多删除的投票记录数量
+		#[pallet::constant]
+		type MaxFeaturedTallyPerBlock: Get<u32>;
+		// 查询订单簿时每次最多检查的Kitty数量
+		#[pallet::constant]
+		type MaxOrderBookScan: Get<u32>;
+		type WeightInfo: WeightInfo;
 	}
 
 	// Errors.
@@ -72,6 +889,64 @@ pub mod pallet {
 		KittyNotForSell,
 		NotEnoughBalanceForBuying,
 		NotEnoughBalanceForStaking,
+		ZeroStakeAmount,
+		NotEnoughBalanceForListingDeposit,
+		FallbackIsOwner,
+		DeadlineInPast,
+		InvalidPremiumTier,
+		NotEnoughBalanceForFee,
+		KittyNotOrphaned,
+		KittyLocked,
+		InvalidRaceId,
+		RaceAlreadyStarted,
+		TooManyRaceEntrants,
+		NotRaceEntrant,
+		NoPendingRoyalties,
+		CloneCannotBreed,
+		TradingNotStarted,
+		NoProceeds,
+		AlreadyVotedThisPeriod,
+		InvalidInstallmentCount,
+		NoInstallmentPlan,
+		NotInstallmentBuyer,
+		NoteTooLong,
+		NotEnoughBalanceForNoteDeposit,
+		ResaleCooldownActive,
+		StakeNotStranded,
+		AlreadyInAuction,
+		InvalidAuctionDuration,
+		NotInAuction,
+		AuctionEnded,
+		BidTooLow,
+		NotEnoughBalanceForBidding,
+		ParentListed,
+		AssetNotAllowed,
+		AssetListingNotSupported,
+		InvalidOfferAmount,
+		NotEnoughBalanceForOffer,
+		TooManyOffers,
+		NoOffer,
+		KittyTooYoungToBurn,
+		InvalidBundleSize,
+		KittyAlreadyBundled,
+		InvalidBundleId,
+		BundleNotForSell,
+		StakeTopUpRequired,
+		InvalidSponsorSignature,
+		SponsorCannotPay,
+		CloneCannotGamble,
+		KittyScheduled,
+		NoScheduledTransfer,
+		KittySoulbound,
+		TooManyActiveOffers,
+		CreateOnCooldown,
+		NoPendingTransfer,
+		SupplySealed,
+		NotExported,
+		WashTradeDetected,
+		NotEnoughBreedingMaterial,
+		InvalidRecountLimit,
+		RecipientRequiresAcceptance,
 	}
 
 	#[pallet::event]
@@ -81,6 +956,121 @@ pub mod pallet {
 		KittyTransfer(T::AccountId, T::AccountId, T::KittyIndex),
 		KittyListed(T::AccountId, T::KittyIndex, Option<BalanceOf<T>>),
 		KittySold(T::AccountId, T::AccountId, T::KittyIndex),
+		KittyStakeToppedUp(T::AccountId, T::KittyIndex, BalanceOf<T>),
+		KittyForceDelisted(T::KittyIndex, BalanceOf<T>),
+		FallbackTransferScheduled(T::AccountId, T::KittyIndex, T::AccountId, T::BlockNumber),
+		FallbackTransferFailed(T::KittyIndex),
+		KittyBredPremium(T::AccountId, T::KittyIndex, u8, BalanceOf<T>),
+		KittyAbandoned(T::AccountId, T::KittyIndex),
+		KittyAdopted(T::AccountId, T::KittyIndex),
+		RaceCreated(RaceId, BalanceOf<T>, T::BlockNumber),
+		RaceEntered(T::AccountId, RaceId, T::KittyIndex),
+		RaceEntryWithdrawn(T::AccountId, RaceId, T::KittyIndex),
+		RaceResolved(RaceId, Vec<(T::AccountId, T::KittyIndex, BalanceOf<T>)>),
+		RoyaltyAccrued(T::AccountId, T::KittyIndex, BalanceOf<T>),
+		RoyaltiesClaimed(T::AccountId, BalanceOf<T>),
+		KittyCloned(T::AccountId, T::KittyIndex, T::KittyIndex, BalanceOf<T>),
+		ProceedsCredited(T::AccountId, T::KittyIndex, BalanceOf<T>),
+		ProceedsWithdrawn(T::AccountId, BalanceOf<T>),
+		FeaturedVoted(T::AccountId, T::BlockNumber, T::KittyIndex),
+		KittyFeatured(T::BlockNumber, T::KittyIndex, u32),
+		InstallmentPlanStarted(T::AccountId, T::KittyIndex, u32, BalanceOf<T>),
+		InstallmentPaid(T::AccountId, T::KittyIndex, u32, BalanceOf<T>),
+		InstallmentPlanCompleted(T::AccountId, T::KittyIndex),
+		InstallmentPlanDefaulted(T::AccountId, T::KittyIndex, BalanceOf<T>, BalanceOf<T>),
+		NoteSet(T::AccountId, T::KittyIndex, BalanceOf<T>),
+		NoteCleared(T::KittyIndex),
+		StrandedStakeRecovered(T::KittyIndex, T::AccountId, T::AccountId),
+		SupplyNearingLimit(T::KittyIndex),
+		AuctionStarted(T::AccountId, T::KittyIndex, BalanceOf<T>, T::BlockNumber),
+		AuctionBid(T::AccountId, T::KittyIndex, BalanceOf<T>),
+		AuctionSettled(T::KittyIndex, T::AccountId, BalanceOf<T>),
+		AuctionEndedWithoutBids(T::KittyIndex),
+		AuctionSettlementFailed(T::KittyIndex, T::AccountId),
+		KittyListedInAsset(T::AccountId, T::KittyIndex, BalanceOf<T>, T::AssetId),
+		KittySoldInAsset(T::AccountId, T::AccountId, T::KittyIndex, T::AssetId, BalanceOf<T>),
+		OfferMade(T::AccountId, T::KittyIndex, BalanceOf<T>),
+		OfferWithdrawn(T::AccountId, T::KittyIndex, BalanceOf<T>),
+		OfferAccepted(T::AccountId, T::AccountId, T::KittyIndex, BalanceOf<T>),
+		OfferCancelled(T::AccountId, T::KittyIndex, BalanceOf<T>),
+		OffersWithdrawn(T::AccountId, u32),
+		KittyBurned(T::AccountId, T::KittyIndex),
+		BundleCreated(T::AccountId, BundleId, Vec<T::KittyIndex>),
+		BundleTransferred(T::AccountId, T::AccountId, BundleId),
+		BundleListed(T::AccountId, BundleId, Option<BalanceOf<T>>),
+		BundleSold(T::AccountId, T::AccountId, BundleId, BalanceOf<T>),
+		Unbundled(T::AccountId, BundleId),
+		StakeIncreaseRequired(T::AccountId, T::KittyIndex, BalanceOf<T>, T::BlockNumber),
+		StakeShortfallCovered(T::KittyIndex),
+		StakeIncreaseLifted(T::KittyIndex, BalanceOf<T>),
+		AdminChanged(Option<T::AccountId>, T::AccountId),
+		SponsoredBreed(T::AccountId, T::AccountId, T::KittyIndex, BalanceOf<T>),
+		GambleWon(T::AccountId, T::KittyIndex, T::KittyIndex),
+		GambleLost(T::AccountId, T::KittyIndex),
+		TransferScheduled(T::AccountId, T::AccountId, T::KittyIndex, T::BlockNumber),
+		ScheduledTransferCancelled(T::AccountId, T::KittyIndex),
+		ScheduledTransferFailed(T::KittyIndex),
+		KittyBound(T::AccountId, T::KittyIndex),
+		AcceptanceRequirementSet(T::AccountId, bool),
+		TransferPending(T::AccountId, T::AccountId, T::KittyIndex, T::BlockNumber),
+		IncomingTransferAccepted(T::AccountId, T::KittyIndex),
+		IncomingTransferExpired(T::AccountId, T::KittyIndex),
+		SupplySealed,
+		PityBreed(T::AccountId, T::KittyIndex),
+		KittyExported(
+			T::AccountId,
+			T::KittyIndex,
+			<T::NftBridge as NftBridge<T::AccountId, T::KittyIndex>>::NftId,
+		),
+		KittyImported(T::AccountId, T::KittyIndex),
+		CountReconciled(T::KittyIndex),
+	}
+
+	#[pallet::genesis_config]
+	pub struct GenesisConfig<T: Config> {
+		pub admin: Option<T::AccountId>,
+	}
+
+	#[cfg(feature = "std")]
+	impl<T: Config> Default for GenesisConfig<T> {
+		fn default() -> Self {
+			Self { admin: None }
+		}
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> GenesisBuild<T> for GenesisConfig<T> {
+		fn build(&self) {
+			if let Some(admin) = &self.admin {
+				Admin::<T>::put(admin);
+			}
+		}
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(now: T::BlockNumber) -> Weight {
+			// 执行到期的任务
+			let (processed, skipped_blocks) = Self::process_agenda(now);
+			let weight = T::WeightInfo::on_initialize(processed)
+				.saturating_add(T::DbWeight::get().reads(skipped_blocks.into()));
+			// 评选已结束周期的获胜Kitty
+			let removed: Weight = Self::crown_featured(now).into();
+			weight.saturating_add(
+				T::DbWeight::get()
+					.reads_writes(removed.saturating_add(1), removed.saturating_add(2)),
+			)
+		}
+
+		fn on_runtime_upgrade() -> Weight {
+			crate::migrations::v1::migrate::<T>()
+		}
+
+		fn integrity_test() {
+			if let Err(e) = Self::check_config() {
+				panic!("{}", e);
+			}
+		}
 	}
 
 	#[pallet::call]
@@ -89,135 +1079,2764 @@ pub mod pallet {
 		#[pallet::weight(0)]
 		pub fn create(origin: OriginFor<T>) -> DispatchResult {
 			let who = ensure_signed(origin)?;
+			// 检查创建冷却时间
+			let now = <frame_system::Pallet<T>>::block_number();
+			let cooldown = T::CreateCooldown::get();
+			if let Some(last) = Self::last_create(&who) {
+				ensure!(now >= last.saturating_add(cooldown), Error::<T>::CreateOnCooldown);
+			}
 			// 随机生成DNA
 			let dna = Self::random_value(&who);
 			// 创建+质押Kitty
-			Self::create_kitty_with_stake(&who, dna)
+			Self::do_mint(&who, dna)?;
+			// 没有冷却时间时不需要记录
+			if !cooldown.is_zero() {
+				LastCreate::<T>::insert(&who, now);
+			}
+			Self::note_activity(&who);
+			Ok(())
 		}
 
 		// 繁殖
 		#[pallet::weight(0)]
+		#[transactional]
 		pub fn breed(
 			origin: OriginFor<T>,
 			kitty_id_1: T::KittyIndex,
 			kitty_id_2: T::KittyIndex,
 		) -> DispatchResult {
 			let who = ensure_signed(origin)?;
-			// 繁殖不能是同一个Kitty
-			ensure!(kitty_id_1 != kitty_id_2, Error::<T>::SameParentIndex);
-			// 获取Kitty1
-			let kitty1 = Self::kitties(kitty_id_1).ok_or(Error::<T>::InvalidKittyIndex)?;
-			// 获取Kitty2
-			let kitty2 = Self::kitties(kitty_id_2).ok_or(Error::<T>::InvalidKittyIndex)?;
-
-			// 获取Parents Kitty的DNA
-			let dna_1 = kitty1.dna;
-			let dna_2 = kitty2.dna;
 			// 混淆DNA
 			let selector = Self::random_value(&who);
-			let mut new_dna = [0u8; 16];
-			for i in 0..dna_1.len() {
-				new_dna[i] = (selector[i] & dna_1[i]) | (!selector[i] & dna_2[i]);
-			}
+			let new_dna = Self::breed_dna(kitty_id_1, kitty_id_2, &selector)?;
 			// 质押+创建Kitty
-			Self::create_kitty_with_stake(&who, new_dna)
+			Self::mint_child(&who, &who, new_dna, kitty_id_1, kitty_id_2)?;
+			Self::note_activity(&who);
+			Ok(())
 		}
 
-		// 卖出
+		// 繁殖并立即以price出售子Kitty，任一步骤失败时全部回滚
 		#[pallet::weight(0)]
-		pub fn sell(
+		#[transactional]
+		pub fn breed_and_list(
 			origin: OriginFor<T>,
-			kitty_id: T::KittyIndex,
-			price: Option<BalanceOf<T>>,
+			kitty_id_1: T::KittyIndex,
+			kitty_id_2: T::KittyIndex,
+			price: BalanceOf<T>,
 		) -> DispatchResult {
 			let who = ensure_signed(origin)?;
-			// 检查是否有权限卖出
-			ensure!(Some(who.clone()) == Owner::<T>::get(kitty_id), Error::<T>::NotOwner);
-			// 将Kitty添加到出售列表
-			ListForSale::<T>::insert(kitty_id, price);
-			// 发出Kitty卖出事件
-			Self::deposit_event(Event::KittyListed(who, kitty_id, price));
+			// 混淆DNA
+			let selector = Self::random_value(&who);
+			let new_dna = Self::breed_dna(kitty_id_1, kitty_id_2, &selector)?;
+			// 质押+创建Kitty
+			let kitty_id = Self::mint_child(&who, &who, new_dna, kitty_id_1, kitty_id_2)?;
+			// 出售子Kitty
+			Self::do_list(&who, kitty_id, Some(price))?;
+			Self::note_activity(&who);
 			Ok(())
 		}
 
-		// 转移
+		// 由赞助者支付子Kitty质押的繁殖，子Kitty归调用者所有
+		// 赞助者需要对sponsor_payload返回的内容签名，每个签名只能使用一次
 		#[pallet::weight(0)]
-		pub fn transfer(
+		#[transactional]
+		pub fn sponsored_breed(
 			origin: OriginFor<T>,
-			new_owner: T::AccountId,
-			kitty_id: T::KittyIndex,
-		) -> DispatchResult {
+			sponsor: T::AccountId,
+			kitty_id_1: T::KittyIndex,
+			kitty_id_2: T::KittyIndex,
+			sponsor_signature: T::SponsorSignature,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let nonce = Self::sponsor_nonce(&sponsor);
+			let payload = Self::sponsor_payload(&who, kitty_id_1, kitty_id_2, nonce);
+			ensure!(
+				sponsor_signature.verify(&payload[..], &sponsor),
+				Error::<T>::InvalidSponsorSignature
+			);
+			SponsorNonces::<T>::insert(&sponsor, nonce.wrapping_add(1));
+			// 混淆DNA
+			let selector = Self::random_value(&who);
+			let new_dna = Self::breed_dna(kitty_id_1, kitty_id_2, &selector)?;
+			// 赞助者将质押金额转给调用者，由调用者作为子Kitty的拥有者质押
+			let stake = T::StakeForEachKitty::get();
+			T::Currency::transfer(&sponsor, &who, stake, ExistenceRequirement::KeepAlive)
+				.map_err(|_| Error::<T>::SponsorCannotPay)?;
+			// 繁殖材料也由赞助者支付
+			let kitty_id = Self::mint_child(&who, &sponsor, new_dna, kitty_id_1, kitty_id_2)?;
+			Self::note_activity(&who);
+			Self::deposit_event(Event::SponsoredBreed(sponsor, who, kitty_id, stake));
+			Ok(())
+		}
+
+		// 付费繁殖，每个等级额外进行一轮变异
+		// 更高的等级只能提高获得多样DNA的概率，并不保证结果
+		#[pallet::weight(0)]
+		#[transactional]
+		pub fn breed_premium(
+			origin: OriginFor<T>,
+			kitty_id_1: T::KittyIndex,
+			kitty_id_2: T::KittyIndex,
+			tier: u8,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(tier > 0 && tier <= T::MaxPremiumTier::get(), Error::<T>::InvalidPremiumTier);
+			// 混淆DNA
+			let selector = Self::random_value(&who);
+			let new_dna = Self::breed_dna(kitty_id_1, kitty_id_2, &selector)?;
+			// 按等级进行额外的变异
+			let new_dna = Self::mutate_dna(new_dna, selector, tier);
+			// 质押+创建Kitty
+			let kitty_id = Self::mint_child(&who, &who, new_dna, kitty_id_1, kitty_id_2)?;
+			// 按等级收取繁殖费用
+			let fee = T::PremiumBreedFee::get().saturating_mul(tier.into());
+			Self::charge_fee(&who, fee)?;
+			// 发出付费繁殖事件
+			Self::note_activity(&who);
+			Self::deposit_event(Event::KittyBredPremium(who, kitty_id, tier, fee));
+			Ok(())
+		}
+
+		// 卖出
+		#[pallet::weight(0)]
+		pub fn sell(
+			origin: OriginFor<T>,
+			kitty_id: T::KittyIndex,
+			price: Option<BalanceOf<T>>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::do_list(&who, kitty_id, price)?;
+			Self::note_activity(&who);
+			Ok(())
+		}
+
+		// 以允许的其他资产定价出售
+		#[pallet::weight(0)]
+		#[transactional]
+		pub fn sell_in_asset(
+			origin: OriginFor<T>,
+			kitty_id: T::KittyIndex,
+			price: BalanceOf<T>,
+			asset_id: T::AssetId,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(T::AllowedAssets::get().contains(&asset_id), Error::<T>::AssetNotAllowed);
+			Self::do_list(&who, kitty_id, Some(price))?;
+			ListingAsset::<T>::insert(kitty_id, asset_id);
+			Self::note_activity(&who);
+			Self::deposit_event(Event::KittyListedInAsset(who, kitty_id, price, asset_id));
+			Ok(())
+		}
+
+		// 转移
+		#[pallet::weight(0)]
+		pub fn transfer(
+			origin: OriginFor<T>,
+			new_owner: T::AccountId,
+			kitty_id: T::KittyIndex,
+		) -> DispatchResult {
 			let who = ensure_signed(origin)?;
 			// 检查是否是原拥有者
 			ensure!(Some(who.clone()) == Owner::<T>::get(kitty_id), Error::<T>::NotOwner);
-			// 更新Kitty的拥有者（双方分别释放和重新质押）
-			// 获取质押金额
-			let stake_amount = T::StakeForEachKitty::get();
+			Self::do_transfer(&who, &new_owner, kitty_id)?;
+			Self::note_activity(&who);
+			Ok(())
+		}
+
+		// 遍历Kitties重新计算KittiesCount（下一个Kitty的ID，至少为现存最大ID加1）
+		// 每次最多遍历limit个Kitty，未遍历完时记录位置，下次调用继续
+		// 计数只会提高：降低计数会重新分配已销毁的ID，遍历期间新建的Kitty也可能落在已遍历的位置
+		#[pallet::weight(0)]
+		pub fn recount(origin: OriginFor<T>, limit: u32) -> DispatchResult {
+			let admin = Self::ensure_privileged(origin)?;
+			ensure!(limit > 0, Error::<T>::InvalidRecountLimit);
+			Self::note_privileged_activity(admin);
+			let (cursor, mut max_id) = RecountCursor::<T>::take().unwrap_or_default();
+			let mut iter = if cursor.is_empty() {
+				Kitties::<T>::iter()
+			} else {
+				Kitties::<T>::iter_from(cursor)
+			};
+			let mut scanned = 0u32;
+			let mut finished = false;
+			while scanned < limit {
+				match iter.next() {
+					Some((kitty_id, Some(_))) => max_id = max_id.max(Some(kitty_id)),
+					Some((_, None)) => {},
+					None => {
+						finished = true;
+						break;
+					},
+				}
+				scanned += 1;
+			}
+			if !finished {
+				RecountCursor::<T>::put((iter.last_raw_key().to_vec(), max_id));
+				return Ok(());
+			}
+			let live = max_id.map(|id| id + One::one()).unwrap_or_else(Zero::zero);
+			let count = Self::kitties_count().unwrap_or_else(Zero::zero).max(live);
+			KittiesCount::<T>::put(count);
+			Self::deposit_event(Event::CountReconciled(count));
+			Ok(())
+		}
+
+		// 永久封顶总量，之后所有创建Kitty的交易都会失败，交易和销毁不受影响
+		#[pallet::weight(0)]
+		pub fn seal_supply(origin: OriginFor<T>) -> DispatchResult {
+			let admin = Self::ensure_privileged(origin)?;
+			ensure!(!Self::is_supply_sealed(), Error::<T>::SupplySealed);
+			SupplySealed::<T>::put(true);
+			Self::note_privileged_activity(admin);
+			Self::deposit_event(Event::SupplySealed);
+			Ok(())
+		}
+
+		// 将Kitty导出为外部集合中的NFT，导出期间Kitty被锁定
+		#[pallet::weight(0)]
+		#[transactional]
+		pub fn export_to_nft(origin: OriginFor<T>, kitty_id: T::KittyIndex) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(Some(who.clone()) == Owner::<T>::get(kitty_id), Error::<T>::NotOwner);
+			Self::ensure_transferable(kitty_id)?;
+			Self::ensure_unlocked(kitty_id)?;
+			let kitty = Self::kitties(kitty_id).ok_or(Error::<T>::InvalidKittyIndex)?;
+			// 导出期间不能在这里交易，取消出售、转移计划和报价
+			Self::remove_listing(kitty_id);
+			Self::cancel_fallback_transfer(kitty_id);
+			Self::cancel_offers(kitty_id);
+			let nft_id = T::NftBridge::mint(&who, kitty_id, kitty.dna)?;
+			ExportedKitties::<T>::insert(kitty_id, nft_id.clone());
+			KittyLocks::<T>::insert(kitty_id, KittyLock::Exported);
+			Self::note_activity(&who);
+			Self::deposit_event(Event::KittyExported(who, kitty_id, nft_id));
+			Ok(())
+		}
+
+		// 销毁外部NFT并解锁对应的Kitty，NFT已转给他人时Kitty一并转给NFT的持有者
+		#[pallet::weight(0)]
+		#[transactional]
+		pub fn import_from_nft(origin: OriginFor<T>, kitty_id: T::KittyIndex) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let nft_id = ExportedKitties::<T>::take(kitty_id).ok_or(Error::<T>::NotExported)?;
+			T::NftBridge::burn(&who, &nft_id)?;
+			KittyLocks::<T>::remove(kitty_id);
+			let owner = Owner::<T>::get(kitty_id).ok_or(Error::<T>::InvalidKittyIndex)?;
+			if owner != who {
+				Self::move_kitty(&owner, &who, kitty_id)?;
+			}
+			Self::note_activity(&who);
+			Self::deposit_event(Event::KittyImported(who, kitty_id));
+			Ok(())
+		}
+
+		// 设置转移给自己的Kitty是否需要确认
+		#[pallet::weight(0)]
+		pub fn set_require_acceptance(origin: OriginFor<T>, required: bool) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			if required {
+				RequireAcceptance::<T>::insert(&who, true);
+			} else {
+				RequireAcceptance::<T>::remove(&who);
+			}
+			Self::note_activity(&who);
+			Self::deposit_event(Event::AcceptanceRequirementSet(who, required));
+			Ok(())
+		}
+
+		// 确认接收等待中的转移
+		#[pallet::weight(0)]
+		#[transactional]
+		pub fn accept_incoming(origin: OriginFor<T>, kitty_id: T::KittyIndex) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let (from, to, expires_at) =
+				PendingTransfers::<T>::get(kitty_id).ok_or(Error::<T>::NoPendingTransfer)?;
+			ensure!(to == who, Error::<T>::NoPendingTransfer);
+			PendingTransfers::<T>::remove(kitty_id);
+			Agenda::<T>::remove(expires_at, ScheduledTask::ExpireIncoming(kitty_id));
+			KittyLocks::<T>::remove(kitty_id);
+			Self::move_kitty(&from, &who, kitty_id)?;
+			Self::note_activity(&who);
+			Self::deposit_event(Event::IncomingTransferAccepted(who, kitty_id));
+			Ok(())
+		}
+
+		// 购买
+		#[pallet::weight(0)]
+		pub fn buy(origin: OriginFor<T>, kitty_id: T::KittyIndex) -> DispatchResult {
+			let buyer = ensure_signed(origin)?;
+			let (seller, kitty_price) = Self::check_buy(&buyer, kitty_id)?;
+			let asset = ListingAsset::<T>::get(kitty_id);
+			Self::settle_sale(&buyer, &seller, kitty_id, kitty_price, asset)?;
+			Self::note_activity(&buyer);
+			Ok(())
+		}
+
+		// 追加质押
+		#[pallet::weight(0)]
+		pub fn top_up_stake(
+			origin: OriginFor<T>,
+			kitty_id: T::KittyIndex,
+			amount: BalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			// 检查是否是拥有者
+			ensure!(Some(who.clone()) == Owner::<T>::get(kitty_id), Error::<T>::NotOwner);
+			ensure!(!amount.is_zero(), Error::<T>::ZeroStakeAmount);
+			// 追加质押拥有者一定金额
+			T::Currency::reserve(&who, amount)
+				.map_err(|_| Error::<T>::NotEnoughBalanceForStaking)?;
+			// 优先补足提高质押要求后的差额，剩余部分增加Kitty的质押金额
+			let mut extra = amount;
+			if let Some((shortfall, due)) = StakeShortfall::<T>::take(kitty_id) {
+				if amount < shortfall {
+					StakeShortfall::<T>::insert(kitty_id, (shortfall - amount, due));
+					extra = Zero::zero();
+				} else {
+					extra = amount - shortfall;
+					Self::deposit_event(Event::StakeShortfallCovered(kitty_id));
+				}
+			}
+			KittyStake::<T>::insert(kitty_id, Self::stake_of(kitty_id) + extra);
+			Self::note_activity(&who);
+			// 发出追加质押事件
+			Self::deposit_event(Event::KittyStakeToppedUp(who, kitty_id, amount));
+			Ok(())
+		}
+
+		// 强制下架，挂单押金被罚没
+		#[pallet::weight(0)]
+		pub fn force_delist(origin: OriginFor<T>, kitty_id: T::KittyIndex) -> DispatchResult {
+			let admin = Self::ensure_privileged(origin)?;
+			// Kitty必须在出售列表中
+			ensure!(ListForSale::<T>::get(kitty_id).is_some(), Error::<T>::KittyNotForSell);
+			ListForSale::<T>::remove(kitty_id);
+			ListingAsset::<T>::remove(kitty_id);
+			// 罚没挂单押金
+			let slashed = match ListingDeposits::<T>::take(kitty_id) {
+				Some((depositor, deposit)) => {
+					let (_, remaining) = T::Currency::slash_reserved(&depositor, deposit);
+					deposit - remaining
+				},
+				None => Zero::zero(),
+			};
+			Self::note_privileged_activity(admin);
+			// 发出强制下架事件
+			Self::deposit_event(Event::KittyForceDelisted(kitty_id, slashed));
+			Ok(())
+		}
+
+		// 拥有者的质押已丢失时，将Kitty转给恢复账户并由其重新质押
+		// 判断方法：拥有者的保留余额低于该Kitty的质押金额，说明质押已不存在（账户被回收或保留余额被罚没）
+		// 拥有多个Kitty的账户只丢失部分保留余额时无法被检测到
+		#[pallet::weight(0)]
+		#[transactional]
+		pub fn recover_stranded_stake(
+			origin: OriginFor<T>,
+			kitty_id: T::KittyIndex,
+		) -> DispatchResult {
+			let admin = Self::ensure_privileged(origin)?;
+			let owner = Owner::<T>::get(kitty_id).ok_or(Error::<T>::InvalidKittyIndex)?;
+			Self::ensure_unlocked(kitty_id)?;
+			let stake_amount = Self::reserved_stake_of(kitty_id);
+			ensure!(
+				T::Currency::reserved_balance(&owner) < stake_amount,
+				Error::<T>::StakeNotStranded
+			);
+			// 释放残留的保留余额
+			T::Currency::unreserve(&owner, stake_amount);
+			Self::remove_listing(kitty_id);
+			Self::cancel_fallback_transfer(kitty_id);
+			Self::remove_note(kitty_id);
+			// 恢复账户重新质押
+			let recovery = T::RecoveryAccount::get();
+			let new_stake = T::StakeForEachKitty::get();
+			T::Currency::reserve(&recovery, new_stake)
+				.map_err(|_| Error::<T>::NotEnoughBalanceForStaking)?;
+			KittyStake::<T>::insert(kitty_id, new_stake);
+			StakeShortfall::<T>::remove(kitty_id);
+			DemandStakeRaise::<T>::remove(kitty_id);
+			Self::set_owner(kitty_id, Some(recovery.clone()));
+			Self::index_history(
+				kitty_id,
+				HistoryRecord::Transfer { from: owner.clone(), to: recovery.clone() },
+			);
+			Self::note_privileged_activity(admin);
+			Self::deposit_event(Event::StrandedStakeRecovered(kitty_id, owner, recovery));
+			Ok(())
+		}
+
+		// 将Kitty灵魂绑定到拥有者，绑定后不能解除
+		#[pallet::weight(0)]
+		pub fn bind(origin: OriginFor<T>, kitty_id: T::KittyIndex) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(Some(who.clone()) == Owner::<T>::get(kitty_id), Error::<T>::NotOwner);
+			Self::ensure_transferable(kitty_id)?;
+			Self::ensure_unlocked(kitty_id)?;
+			// 绑定后不能再交易，取消出售、转移计划和报价
+			Self::remove_listing(kitty_id);
+			Self::cancel_fallback_transfer(kitty_id);
+			Self::cancel_offers(kitty_id);
+			Soulbound::<T>::insert(kitty_id, true);
+			Self::note_activity(&who);
+			Self::deposit_event(Event::KittyBound(who, kitty_id));
+			Ok(())
+		}
+
+		// 在指定区块将Kitty转移给to，执行前Kitty被锁定
+		#[pallet::weight(0)]
+		pub fn schedule_transfer(
+			origin: OriginFor<T>,
+			to: T::AccountId,
+			kitty_id: T::KittyIndex,
+			at_block: T::BlockNumber,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(Some(who.clone()) == Owner::<T>::get(kitty_id), Error::<T>::NotOwner);
+			ensure!(
+				at_block > <frame_system::Pallet<T>>::block_number(),
+				Error::<T>::DeadlineInPast
+			);
+			Self::ensure_transferable(kitty_id)?;
+			Self::ensure_unlocked(kitty_id)?;
+			// 锁定期间不能出售
+			Self::remove_listing(kitty_id);
+			Self::cancel_fallback_transfer(kitty_id);
+			ScheduledTransfers::<T>::insert(kitty_id, (to.clone(), at_block));
+			Agenda::<T>::insert(at_block, ScheduledTask::ScheduledTransfer(kitty_id), ());
+			KittyLocks::<T>::insert(kitty_id, KittyLock::ScheduledTransfer);
+			Self::note_activity(&who);
+			Self::deposit_event(Event::TransferScheduled(who, to, kitty_id, at_block));
+			Ok(())
+		}
+
+		// 在执行前取消预定的转移
+		#[pallet::weight(0)]
+		pub fn cancel_scheduled_transfer(
+			origin: OriginFor<T>,
+			kitty_id: T::KittyIndex,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(Some(who.clone()) == Owner::<T>::get(kitty_id), Error::<T>::NotOwner);
+			let (_, at_block) =
+				ScheduledTransfers::<T>::take(kitty_id).ok_or(Error::<T>::NoScheduledTransfer)?;
+			Agenda::<T>::remove(at_block, ScheduledTask::ScheduledTransfer(kitty_id));
+			KittyLocks::<T>::remove(kitty_id);
+			Self::note_activity(&who);
+			Self::deposit_event(Event::ScheduledTransferCancelled(who, kitty_id));
+			Ok(())
+		}
+
+		// 截止区块前未售出则转移给备用拥有者
+		#[pallet::weight(0)]
+		pub fn transfer_if_unsold(
+			origin: OriginFor<T>,
+			kitty_id: T::KittyIndex,
+			fallback_owner: T::AccountId,
+			deadline: T::BlockNumber,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			// 检查是否是拥有者
+			ensure!(Some(who.clone()) == Owner::<T>::get(kitty_id), Error::<T>::NotOwner);
+			// Kitty必须在出售列表中
+			ensure!(ListForSale::<T>::get(kitty_id).is_some(), Error::<T>::KittyNotForSell);
+			ensure!(fallback_owner != who, Error::<T>::FallbackIsOwner);
+			ensure!(
+				deadline > <frame_system::Pallet<T>>::block_number(),
+				Error::<T>::DeadlineInPast
+			);
+			// 覆盖之前的计划
+			Self::cancel_fallback_transfer(kitty_id);
+			FallbackTransfers::<T>::insert(
+				kitty_id,
+				FallbackTransfer {
+					owner: who.clone(),
+					fallback_owner: fallback_owner.clone(),
+					deadline,
+				},
+			);
+			Agenda::<T>::insert(deadline, ScheduledTask::FallbackTransfer(kitty_id), ());
+			Self::note_activity(&who);
+			// 发出计划事件
+			Self::deposit_event(Event::FallbackTransferScheduled(
+				who,
+				kitty_id,
+				fallback_owner,
+				deadline,
+			));
+			Ok(())
+		}
+
+		// 放弃Kitty并取回质押，Kitty成为无主Kitty等待领养
+		#[pallet::weight(0)]
+		pub fn abandon(origin: OriginFor<T>, kitty_id: T::KittyIndex) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			// 检查是否是拥有者
+			ensure!(Some(who.clone()) == Owner::<T>::get(kitty_id), Error::<T>::NotOwner);
+			// 灵魂绑定的Kitty被放弃后会被其他账户领养，相当于转移
+			Self::ensure_transferable(kitty_id)?;
+			Self::ensure_unlocked(kitty_id)?;
+			// 取消出售和转移计划
+			Self::remove_listing(kitty_id);
+			Self::cancel_fallback_transfer(kitty_id);
+			Self::remove_note(kitty_id);
+			// 退还质押
+			let stake_amount = Self::reserved_stake_of(kitty_id);
+			KittyStake::<T>::remove(kitty_id);
+			StakeShortfall::<T>::remove(kitty_id);
+			T::Currency::unreserve(&who, stake_amount);
+			// 移除拥有者
+			Self::set_owner(kitty_id, None);
+			Self::index_history(kitty_id, HistoryRecord::Abandon { owner: who.clone() });
+			Self::note_activity(&who);
+			// 发出放弃事件
+			Self::deposit_event(Event::KittyAbandoned(who, kitty_id));
+			Ok(())
+		}
+
+		// 领养无主Kitty
+		#[pallet::weight(0)]
+		pub fn adopt(origin: OriginFor<T>, kitty_id: T::KittyIndex) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			// Kitty必须存在且没有拥有者
+			ensure!(Self::kitties(kitty_id).is_some(), Error::<T>::InvalidKittyIndex);
+			ensure!(Owner::<T>::get(kitty_id).is_none(), Error::<T>::KittyNotOrphaned);
+			// 质押领养者一定的金额
+			let stake_amount = T::StakeForEachKitty::get();
+			T::Currency::reserve(&who, stake_amount)
+				.map_err(|_| Error::<T>::NotEnoughBalanceForStaking)?;
+			// 为Kitty绑定所有人
+			Self::set_owner(kitty_id, Some(who.clone()));
+			KittyStake::<T>::insert(kitty_id, stake_amount);
+			Self::index_history(kitty_id, HistoryRecord::Adopt { owner: who.clone() });
+			Self::note_activity(&who);
+			// 发出领养事件
+			Self::deposit_event(Event::KittyAdopted(who, kitty_id));
+			Ok(())
+		}
+
+		// 移交管理员权限
+		#[pallet::weight(0)]
+		pub fn transfer_admin(origin: OriginFor<T>, new_admin: T::AccountId) -> DispatchResult {
+			let admin = Self::ensure_privileged(origin)?;
+			let old_admin = Admin::<T>::get();
+			Admin::<T>::put(&new_admin);
+			Self::note_privileged_activity(admin);
+			Self::deposit_event(Event::AdminChanged(old_admin, new_admin));
+			Ok(())
+		}
+
+		// 创建比赛
+		#[pallet::weight(0)]
+		pub fn create_race(
+			origin: OriginFor<T>,
+			entry_fee: BalanceOf<T>,
+			start: T::BlockNumber,
+		) -> DispatchResult {
+			let admin = Self::ensure_privileged(origin)?;
+			ensure!(start > <frame_system::Pallet<T>>::block_number(), Error::<T>::DeadlineInPast);
+			let race_id = Self::next_race_id();
+			Races::<T>::insert(race_id, Race { entry_fee, start, pot: Zero::zero() });
+			NextRaceId::<T>::put(race_id.saturating_add(1));
+			// 开始区块结算比赛
+			Agenda::<T>::insert(start, ScheduledTask::ResolveRace(race_id), ());
+			Self::note_privileged_activity(admin);
+			Self::deposit_event(Event::RaceCreated(race_id, entry_fee, start));
+			Ok(())
+		}
+
+		// 报名参赛，报名费进入奖池，Kitty锁定到比赛结束
+		#[pallet::weight(0)]
+		pub fn enter_race(
+			origin: OriginFor<T>,
+			race_id: RaceId,
+			kitty_id: T::KittyIndex,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let mut race = Self::races(race_id).ok_or(Error::<T>::InvalidRaceId)?;
+			ensure!(
+				<frame_system::Pallet<T>>::block_number() < race.start,
+				Error::<T>::RaceAlreadyStarted
+			);
+			// 检查是否是拥有者
+			ensure!(Some(who.clone()) == Owner::<T>::get(kitty_id), Error::<T>::NotOwner);
+			Self::ensure_unlocked(kitty_id)?;
+			let mut entrants = Self::race_entrants(race_id);
+			entrants
+				.try_push((who.clone(), kitty_id))
+				.map_err(|_| Error::<T>::TooManyRaceEntrants)?;
+			// 支付报名费
+			T::Currency::transfer(
+				&who,
+				&Self::account_id(),
+				race.entry_fee,
+				ExistenceRequirement::KeepAlive,
+			)?;
+			race.pot = race.pot.saturating_add(race.entry_fee);
+			Races::<T>::insert(race_id, race);
+			RaceEntrants::<T>::insert(race_id, entrants);
+			// 参赛期间不能出售
+			Self::remove_listing(kitty_id);
+			KittyLocks::<T>::insert(kitty_id, KittyLock::Race(race_id));
+			Self::note_activity(&who);
+			Self::deposit_event(Event::RaceEntered(who, race_id, kitty_id));
+			Ok(())
+		}
+
+		// 比赛开始前退出比赛并取回报名费
+		#[pallet::weight(0)]
+		pub fn withdraw_entry(
+			origin: OriginFor<T>,
+			race_id: RaceId,
+			kitty_id: T::KittyIndex,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let mut race = Self::races(race_id).ok_or(Error::<T>::InvalidRaceId)?;
+			ensure!(
+				<frame_system::Pallet<T>>::block_number() < race.start,
+				Error::<T>::RaceAlreadyStarted
+			);
+			let mut entrants = Self::race_entrants(race_id);
+			let position = entrants
+				.iter()
+				.position(|(entrant, id)| *entrant == who && *id == kitty_id)
+				.ok_or(Error::<T>::NotRaceEntrant)?;
+			entrants.remove(position);
+			// 退还报名费
+			T::Currency::transfer(
+				&Self::account_id(),
+				&who,
+				race.entry_fee,
+				ExistenceRequirement::AllowDeath,
+			)?;
+			race.pot = race.pot.saturating_sub(race.entry_fee);
+			Races::<T>::insert(race_id, race);
+			RaceEntrants::<T>::insert(race_id, entrants);
+			KittyLocks::<T>::remove(kitty_id);
+			Self::note_activity(&who);
+			Self::deposit_event(Event::RaceEntryWithdrawn(who, race_id, kitty_id));
+			Ok(())
+		}
+
+		// 一次领取所有累计的版税
+		#[pallet::weight(0)]
+		#[transactional]
+		pub fn claim_royalties(origin: OriginFor<T>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let amount = PendingRoyalties::<T>::take(&who);
+			ensure!(!amount.is_zero(), Error::<T>::NoPendingRoyalties);
+			T::Currency::transfer(
+				&Self::proceeds_account_id(),
+				&who,
+				amount,
+				ExistenceRequirement::KeepAlive,
+			)?;
+			Self::note_activity(&who);
+			Self::deposit_event(Event::RoyaltiesClaimed(who, amount));
+			Ok(())
+		}
+
+		// 为本周期的Kitty投票，每个账户每个周期只能投一票
+		#[pallet::weight(0)]
+		pub fn vote_featured(origin: OriginFor<T>, kitty_id: T::KittyIndex) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(Self::kitties(kitty_id).is_some(), Error::<T>::InvalidKittyIndex);
+			let period = Self::current_period();
+			ensure!(
+				!FeaturedVoters::<T>::contains_key(period, &who),
+				Error::<T>::AlreadyVotedThisPeriod
+			);
+			FeaturedVoters::<T>::insert(period, &who, kitty_id);
+			FeaturedVotes::<T>::mutate(period, kitty_id, |votes| *votes = votes.saturating_add(1));
+			Self::note_activity(&who);
+			Self::deposit_event(Event::FeaturedVoted(who, period, kitty_id));
+			Ok(())
+		}
+
+		// 分期购买出售中的Kitty，开始时支付第一期，付清前Kitty由卖家保管并锁定
+		#[pallet::weight(0)]
+		#[transactional]
+		pub fn start_installment(
+			origin: OriginFor<T>,
+			kitty_id: T::KittyIndex,
+			num_installments: u32,
+		) -> DispatchResult {
+			let buyer = ensure_signed(origin)?;
+			ensure!(
+				num_installments >= 2 && num_installments <= T::MaxInstallments::get(),
+				Error::<T>::InvalidInstallmentCount
+			);
+			let (seller, price) = Self::check_buy(&buyer, kitty_id)?;
+			ensure!(
+				!ListingAsset::<T>::contains_key(kitty_id),
+				Error::<T>::AssetListingNotSupported
+			);
+			// 先支付第一期，避免无成本地锁定他人出售的Kitty
+			let amount = Self::installment_amount(price, num_installments, 0);
+			T::Currency::transfer(
+				&buyer,
+				&Self::account_id(),
+				amount,
+				ExistenceRequirement::KeepAlive,
+			)?;
+			let next_due = <frame_system::Pallet<T>>::block_number() + T::InstallmentPeriod::get();
+			InstallmentPlans::<T>::insert(
+				kitty_id,
+				InstallmentPlan {
+					buyer: buyer.clone(),
+					seller,
+					price,
+					installments: num_installments,
+					paid_installments: 1,
+					paid: amount,
+					next_due,
+				},
+			);
+			Agenda::<T>::insert(next_due, ScheduledTask::InstallmentDue(kitty_id), ());
+			// 分期期间不能出售或转移
+			Self::remove_listing(kitty_id);
+			Self::cancel_fallback_transfer(kitty_id);
+			KittyLocks::<T>::insert(kitty_id, KittyLock::Installment);
+			Self::deposit_event(Event::InstallmentPlanStarted(
+				buyer.clone(),
+				kitty_id,
+				num_installments,
+				amount,
+			));
+			Self::note_activity(&buyer);
+			Self::deposit_event(Event::InstallmentPaid(buyer, kitty_id, 1, amount));
+			Ok(())
+		}
+
+		// 支付一期款项，付清最后一期时完成购买
+		#[pallet::weight(0)]
+		#[transactional]
+		pub fn pay_installment(origin: OriginFor<T>, kitty_id: T::KittyIndex) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let mut plan = Self::installment_plan(kitty_id).ok_or(Error::<T>::NoInstallmentPlan)?;
+			ensure!(plan.buyer == who, Error::<T>::NotInstallmentBuyer);
+			let amount =
+				Self::installment_amount(plan.price, plan.installments, plan.paid_installments);
+			T::Currency::transfer(
+				&who,
+				&Self::account_id(),
+				amount,
+				ExistenceRequirement::KeepAlive,
+			)?;
+			plan.paid_installments += 1;
+			plan.paid = plan.paid.saturating_add(amount);
+			Agenda::<T>::remove(plan.next_due, ScheduledTask::InstallmentDue(kitty_id));
+			Self::note_activity(&who);
+			Self::deposit_event(Event::InstallmentPaid(
+				who.clone(),
+				kitty_id,
+				plan.paid_installments,
+				amount,
+			));
+			if plan.paid_installments < plan.installments {
+				plan.next_due =
+					<frame_system::Pallet<T>>::block_number() + T::InstallmentPeriod::get();
+				Agenda::<T>::insert(plan.next_due, ScheduledTask::InstallmentDue(kitty_id), ());
+				InstallmentPlans::<T>::insert(kitty_id, plan);
+				return Ok(());
+			}
+			// 付清后退回托管款项，按正常交易结算
+			InstallmentPlans::<T>::remove(kitty_id);
+			KittyLocks::<T>::remove(kitty_id);
+			T::Currency::transfer(
+				&Self::account_id(),
+				&who,
+				plan.paid,
+				ExistenceRequirement::AllowDeath,
+			)?;
+			Self::do_settle_sale(&who, &plan.seller, kitty_id, plan.price)?;
+			Self::deposit_event(Event::InstallmentPlanCompleted(who, kitty_id));
+			Ok(())
+		}
+
+		// 设置私人笔记，按字节质押押金
+		#[pallet::weight(0)]
+		#[transactional]
+		pub fn set_note(
+			origin: OriginFor<T>,
+			kitty_id: T::KittyIndex,
+			note: Vec<u8>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(Some(who.clone()) == Owner::<T>::get(kitty_id), Error::<T>::NotOwner);
+			let deposit = T::NoteDepositPerByte::get().saturating_mul((note.len() as u32).into());
+			let note: BoundedVec<u8, T::MaxNoteLength> =
+				note.try_into().map_err(|_| Error::<T>::NoteTooLong)?;
+			// 退还之前的押金后重新质押
+			Self::remove_note(kitty_id);
+			T::Currency::reserve(&who, deposit)
+				.map_err(|_| Error::<T>::NotEnoughBalanceForNoteDeposit)?;
+			PrivateNotes::<T>::insert(kitty_id, note);
+			NoteDeposits::<T>::insert(kitty_id, (who.clone(), deposit));
+			Self::note_activity(&who);
+			Self::deposit_event(Event::NoteSet(who, kitty_id, deposit));
+			Ok(())
+		}
+
+		// 清除私人笔记并退还押金
+		#[pallet::weight(0)]
+		pub fn clear_note(origin: OriginFor<T>, kitty_id: T::KittyIndex) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(Some(who.clone()) == Owner::<T>::get(kitty_id), Error::<T>::NotOwner);
+			Self::remove_note(kitty_id);
+			Self::note_activity(&who);
+			Ok(())
+		}
+
+		// 将出售中的Kitty转为拍卖，以出售价格作为保留价
+		#[pallet::weight(0)]
+		pub fn escalate_to_auction(
+			origin: OriginFor<T>,
+			kitty_id: T::KittyIndex,
+			duration: T::BlockNumber,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(Some(who.clone()) == Owner::<T>::get(kitty_id), Error::<T>::NotOwner);
+			ensure!(!Auctions::<T>::contains_key(kitty_id), Error::<T>::AlreadyInAuction);
+			ensure!(
+				!duration.is_zero() && duration <= T::MaxAuctionDuration::get(),
+				Error::<T>::InvalidAuctionDuration
+			);
+			let reserve = ListForSale::<T>::get(kitty_id).ok_or(Error::<T>::KittyNotForSell)?;
+			ensure!(
+				!ListingAsset::<T>::contains_key(kitty_id),
+				Error::<T>::AssetListingNotSupported
+			);
+			Self::ensure_unlocked(kitty_id)?;
+			// 先计算结束区块，溢出时不修改任何存储
+			let end = <frame_system::Pallet<T>>::block_number()
+				.checked_add(&duration)
+				.ok_or(Error::<T>::InvalidAuctionDuration)?;
+			// 取消定价出售
+			Self::remove_listing(kitty_id);
+			Self::deposit_event(Event::KittyListed(who.clone(), kitty_id, None));
+			// 开始拍卖，拍卖期间不能出售或转移
+			Auctions::<T>::insert(
+				kitty_id,
+				Auction { seller: who.clone(), reserve, end, highest_bid: None },
+			);
+			Agenda::<T>::insert(end, ScheduledTask::SettleAuction(kitty_id), ());
+			KittyLocks::<T>::insert(kitty_id, KittyLock::Auction);
+			Self::note_activity(&who);
+			Self::deposit_event(Event::AuctionStarted(who, kitty_id, reserve, end));
+			Ok(())
+		}
+
+		// 出价，质押出价金额并退还上一个最高出价
+		#[pallet::weight(0)]
+		pub fn bid(
+			origin: OriginFor<T>,
+			kitty_id: T::KittyIndex,
+			amount: BalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_trading_started()?;
+			let mut auction = Self::auction(kitty_id).ok_or(Error::<T>::NotInAuction)?;
+			let now = <frame_system::Pallet<T>>::block_number();
+			ensure!(now < auction.end, Error::<T>::AuctionEnded);
+			ensure!(who != auction.seller, Error::<T>::BuyerIsOwner);
+			ensure!(amount >= auction.reserve, Error::<T>::BidTooLow);
+			let highest_bid = auction
+				.highest_bid
+				.as_ref()
+				.map(|(bidder, highest, _)| (bidder.clone(), *highest));
+			match highest_bid {
+				// 与最高出价相同的出价不会替换最早的出价
+				Some((bidder, highest)) if amount == highest => {
+					ensure!(
+						bidder != who && !TiedBids::<T>::contains_key(kitty_id, &who),
+						Error::<T>::BidTooLow
+					);
+					T::Currency::reserve(&who, amount)
+						.map_err(|_| Error::<T>::NotEnoughBalanceForBidding)?;
+					TiedBids::<T>::insert(kitty_id, &who, (amount, now));
+				},
+				Some((bidder, highest)) => {
+					ensure!(amount > highest, Error::<T>::BidTooLow);
+					T::Currency::reserve(&who, amount)
+						.map_err(|_| Error::<T>::NotEnoughBalanceForBidding)?;
+					T::Currency::unreserve(&bidder, highest);
+					Self::refund_tied_bids(kitty_id);
+					auction.highest_bid = Some((who.clone(), amount, now));
+				},
+				None => {
+					T::Currency::reserve(&who, amount)
+						.map_err(|_| Error::<T>::NotEnoughBalanceForBidding)?;
+					auction.highest_bid = Some((who.clone(), amount, now));
+				},
+			}
+			Auctions::<T>::insert(kitty_id, auction);
+			Self::note_activity(&who);
+			Self::deposit_event(Event::AuctionBid(who, kitty_id, amount));
+			Ok(())
+		}
+
+		// 对Kitty报价，重复报价时替换之前的报价
+		#[pallet::weight(0)]
+		#[transactional]
+		pub fn make_offer(
+			origin: OriginFor<T>,
+			kitty_id: T::KittyIndex,
+			amount: BalanceOf<T>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_trading_started()?;
+			let owner = Owner::<T>::get(kitty_id).ok_or(Error::<T>::InvalidKittyIndex)?;
+			ensure!(who != owner, Error::<T>::BuyerIsOwner);
+			Self::ensure_transferable(kitty_id)?;
+			ensure!(!amount.is_zero(), Error::<T>::InvalidOfferAmount);
+			match Offers::<T>::get(kitty_id, &who) {
+				Some(previous) => T::Currency::unreserve(&who, previous),
+				None => {
+					let count = Self::offer_count(kitty_id);
+					ensure!(count < T::MaxOffersPerKitty::get(), Error::<T>::TooManyOffers);
+					let active = Self::account_offer_count(&who);
+					ensure!(
+						active < T::MaxOffersPerAccount::get(),
+						Error::<T>::TooManyActiveOffers
+					);
+					OfferCount::<T>::insert(kitty_id, count + 1);
+					AccountOfferCount::<T>::insert(&who, active + 1);
+					if count + 1 == T::HighDemandOfferThreshold::get() {
+						Self::raise_stake_for_demand(&owner, kitty_id);
+					}
+				},
+			};
+			T::Currency::reserve(&who, amount).map_err(|_| Error::<T>::NotEnoughBalanceForOffer)?;
+			Offers::<T>::insert(kitty_id, &who, amount);
+			BidderOffers::<T>::insert(&who, kitty_id, ());
+			Self::note_activity(&who);
+			Self::deposit_event(Event::OfferMade(who, kitty_id, amount));
+			Ok(())
+		}
+
+		// 撤回报价并退还质押
+		#[pallet::weight(0)]
+		pub fn withdraw_offer(origin: OriginFor<T>, kitty_id: T::KittyIndex) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let amount = Self::take_offer(kitty_id, &who).ok_or(Error::<T>::NoOffer)?;
+			T::Currency::unreserve(&who, amount);
+			Self::note_activity(&who);
+			Self::deposit_event(Event::OfferWithdrawn(who, kitty_id, amount));
+			Ok(())
+		}
+
+		// 批量撤回调用者的报价，每次最多撤回limit个
+		#[pallet::weight(0)]
+		pub fn withdraw_all_offers(origin: OriginFor<T>, limit: u32) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let kitty_ids: Vec<T::KittyIndex> =
+				BidderOffers::<T>::iter_key_prefix(&who).take(limit as usize).collect();
+			let mut count = 0u32;
+			for kitty_id in kitty_ids {
+				if let Some(amount) = Self::take_offer(kitty_id, &who) {
+					T::Currency::unreserve(&who, amount);
+					Self::deposit_event(Event::OfferWithdrawn(who.clone(), kitty_id, amount));
+					count += 1;
+				}
+			}
+			Self::note_activity(&who);
+			Self::deposit_event(Event::OffersWithdrawn(who, count));
+			Ok(())
+		}
+
+		// 拥有者接受任意一个报价（不必是最高报价），按报价金额出售
+		// 拥有者改变后其余报价全部取消并退还
+		#[pallet::weight(0)]
+		#[transactional]
+		pub fn accept_offer(
+			origin: OriginFor<T>,
+			kitty_id: T::KittyIndex,
+			bidder: T::AccountId,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_trading_started()?;
+			ensure!(Some(who.clone()) == Owner::<T>::get(kitty_id), Error::<T>::NotOwner);
+			Self::ensure_unlocked(kitty_id)?;
+			Self::ensure_resale_allowed(kitty_id)?;
+			Self::ensure_stake_sufficient(kitty_id)?;
+			let amount = Self::take_offer(kitty_id, &bidder).ok_or(Error::<T>::NoOffer)?;
+			T::Currency::unreserve(&bidder, amount);
+			Self::do_settle_sale(&bidder, &who, kitty_id, amount)?;
+			Self::note_activity(&who);
+			Self::deposit_event(Event::OfferAccepted(who, bidder, kitty_id, amount));
+			Ok(())
+		}
+
+		// 销毁Kitty并退还质押
+		#[pallet::weight(0)]
+		#[transactional]
+		pub fn burn(origin: OriginFor<T>, kitty_id: T::KittyIndex) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_can_burn(&who, kitty_id)?;
+			Self::do_burn(&who, kitty_id);
+			Self::note_activity(&who);
+			Ok(())
+		}
+
+		// 批量销毁Kitty并退还质押，任一Kitty不能销毁时全部回滚
+		#[pallet::weight(0)]
+		#[transactional]
+		pub fn burn_batch(
+			origin: OriginFor<T>,
+			kitty_ids: BoundedVec<T::KittyIndex, T::MaxBatchSize>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			for kitty_id in kitty_ids {
+				Self::ensure_can_burn(&who, kitty_id)?;
+				Self::do_burn(&who, kitty_id);
+			}
+			Self::note_activity(&who);
+			Ok(())
+		}
+
+		// 销毁Kitty并支付费用，按概率获得一个更稀有的Kitty，否则一无所获
+		#[pallet::weight(0)]
+		#[transactional]
+		pub fn gamble(origin: OriginFor<T>, kitty_id: T::KittyIndex) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			// 获胜时需要创建新的Kitty
+			ensure!(!Self::is_supply_sealed(), Error::<T>::SupplySealed);
+			Self::ensure_can_burn(&who, kitty_id)?;
+			let kitty = Self::kitties(kitty_id).ok_or(Error::<T>::InvalidKittyIndex)?;
+			ensure!(!kitty.clone, Error::<T>::CloneCannotGamble);
+			// 灵魂绑定的Kitty不能换成新的Kitty
+			Self::ensure_transferable(kitty_id)?;
+			Self::charge_fee(&who, T::GambleFee::get())?;
+			let generation = Self::generation_of(kitty_id);
+			Self::do_burn(&who, kitty_id);
+			let random = Self::random_value(&who);
+			Self::note_activity(&who);
+			if Self::gamble_wins(&random, T::GambleWinChance::get()) {
+				// 新的Kitty与销毁的Kitty同代
+				let dna = Self::rarer_dna(kitty.dna, &random);
+				let new_id = Self::mint_kitty(&who, Kitty { dna, clone: false }, generation)?;
+				Self::deposit_event(Event::GambleWon(who, kitty_id, new_id));
+			} else {
+				Self::deposit_event(Event::GambleLost(who, kitty_id));
+			}
+			Ok(())
+		}
+
+		// 将多个Kitty锁定为一个捆绑包，捆绑包作为整体交易
+		#[pallet::weight(0)]
+		#[transactional]
+		pub fn create_bundle(
+			origin: OriginFor<T>,
+			kitty_ids: Vec<T::KittyIndex>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(kitty_ids.len() >= 2, Error::<T>::InvalidBundleSize);
+			let kitties: BoundedVec<T::KittyIndex, T::MaxBundleSize> =
+				kitty_ids.clone().try_into().map_err(|_| Error::<T>::InvalidBundleSize)?;
+			let bundle_id = NextBundleId::<T>::get();
+			for kitty_id in kitties.iter() {
+				ensure!(Some(who.clone()) == Owner::<T>::get(kitty_id), Error::<T>::NotOwner);
+				// 同一个Kitty不能重复加入捆绑包
+				ensure!(!KittyBundle::<T>::contains_key(kitty_id), Error::<T>::KittyAlreadyBundled);
+				Self::ensure_transferable(*kitty_id)?;
+				Self::ensure_unlocked(*kitty_id)?;
+				// 加入捆绑包后取消单独出售
+				Self::remove_listing(*kitty_id);
+				KittyBundle::<T>::insert(kitty_id, bundle_id);
+				KittyLocks::<T>::insert(kitty_id, KittyLock::Bundle(bundle_id));
+			}
+			Bundles::<T>::insert(bundle_id, (who.clone(), kitties));
+			NextBundleId::<T>::put(bundle_id.wrapping_add(1));
+			Self::note_activity(&who);
+			Self::deposit_event(Event::BundleCreated(who, bundle_id, kitty_ids));
+			Ok(())
+		}
+
+		// 转移捆绑包中的所有Kitty
+		#[pallet::weight(0)]
+		#[transactional]
+		pub fn transfer_bundle(
+			origin: OriginFor<T>,
+			new_owner: T::AccountId,
+			bundle_id: BundleId,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let (owner, kitties) = Self::bundle(bundle_id).ok_or(Error::<T>::InvalidBundleId)?;
+			ensure!(who == owner, Error::<T>::NotOwner);
+			// 捆绑包中的Kitty须一并转移，不能逐个等待接收者确认
+			ensure!(
+				new_owner == who || !Self::requires_acceptance(&new_owner),
+				Error::<T>::RecipientRequiresAcceptance
+			);
+			for kitty_id in kitties.iter() {
+				KittyLocks::<T>::remove(kitty_id);
+				Self::move_kitty(&who, &new_owner, *kitty_id)?;
+				KittyLocks::<T>::insert(kitty_id, KittyLock::Bundle(bundle_id));
+			}
+			BundleListings::<T>::remove(bundle_id);
+			Bundles::<T>::insert(bundle_id, (new_owner.clone(), kitties));
+			Self::note_activity(&who);
+			Self::deposit_event(Event::BundleTransferred(who, new_owner, bundle_id));
+			Ok(())
+		}
+
+		// 出售或取消出售捆绑包，每个Kitty与单独出售时一样检查并质押挂单押金
+		#[pallet::weight(0)]
+		#[transactional]
+		pub fn sell_bundle(
+			origin: OriginFor<T>,
+			bundle_id: BundleId,
+			price: Option<BalanceOf<T>>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_trading_started()?;
+			let (owner, kitties) = Self::bundle(bundle_id).ok_or(Error::<T>::InvalidBundleId)?;
+			ensure!(who == owner, Error::<T>::NotOwner);
+			match price {
+				Some(price) => {
+					for kitty_id in kitties.iter() {
+						Self::ensure_transferable(*kitty_id)?;
+						Self::ensure_resale_allowed(*kitty_id)?;
+						Self::ensure_stake_sufficient(*kitty_id)?;
+						if !ListingDeposits::<T>::contains_key(kitty_id) {
+							let deposit = T::ListingDeposit::get();
+							T::Currency::reserve(&who, deposit)
+								.map_err(|_| Error::<T>::NotEnoughBalanceForListingDeposit)?;
+							ListingDeposits::<T>::insert(kitty_id, (who.clone(), deposit));
+						}
+					}
+					BundleListings::<T>::insert(bundle_id, price);
+				},
+				None => {
+					for kitty_id in kitties.iter() {
+						Self::remove_listing(*kitty_id);
+					}
+					BundleListings::<T>::remove(bundle_id);
+				},
+			}
+			Self::note_activity(&who);
+			Self::deposit_event(Event::BundleListed(who, bundle_id, price));
+			Ok(())
+		}
+
+		// 购买捆绑包，价格平均分配到每个Kitty上结算
+		#[pallet::weight(0)]
+		#[transactional]
+		pub fn buy_bundle(origin: OriginFor<T>, bundle_id: BundleId) -> DispatchResult {
+			let buyer = ensure_signed(origin)?;
+			Self::ensure_trading_started()?;
+			let (seller, kitties) = Self::bundle(bundle_id).ok_or(Error::<T>::InvalidBundleId)?;
+			ensure!(buyer != seller, Error::<T>::BuyerIsOwner);
+			let price = BundleListings::<T>::take(bundle_id).ok_or(Error::<T>::BundleNotForSell)?;
+			let count: BalanceOf<T> = (kitties.len() as u32).into();
+			let share = price / count;
+			// 最后一个Kitty承担除不尽的余数
+			let last_share = price - share * (count - One::one());
+			for (index, kitty_id) in kitties.iter().enumerate() {
+				let kitty_price = if index + 1 == kitties.len() { last_share } else { share };
+				Self::ensure_not_wash_trade(&buyer, *kitty_id)?;
+				KittyLocks::<T>::remove(kitty_id);
+				Self::do_settle_sale(&buyer, &seller, *kitty_id, kitty_price)?;
+				KittyLocks::<T>::insert(kitty_id, KittyLock::Bundle(bundle_id));
+			}
+			Bundles::<T>::insert(bundle_id, (buyer.clone(), kitties));
+			Self::note_activity(&buyer);
+			Self::deposit_event(Event::BundleSold(buyer, seller, bundle_id, price));
+			Ok(())
+		}
+
+		// 解散捆绑包，释放其中的Kitty
+		#[pallet::weight(0)]
+		pub fn unbundle(origin: OriginFor<T>, bundle_id: BundleId) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let (owner, kitties) = Self::bundle(bundle_id).ok_or(Error::<T>::InvalidBundleId)?;
+			ensure!(who == owner, Error::<T>::NotOwner);
+			for kitty_id in kitties.iter() {
+				KittyLocks::<T>::remove(kitty_id);
+				KittyBundle::<T>::remove(kitty_id);
+				// 退还捆绑包出售时质押的挂单押金
+				Self::remove_listing(*kitty_id);
+			}
+			Bundles::<T>::remove(bundle_id);
+			BundleListings::<T>::remove(bundle_id);
+			Self::note_activity(&who);
+			Self::deposit_event(Event::Unbundled(who, bundle_id));
+			Ok(())
+		}
+
+		// 领取累计的交易收入
+		#[pallet::weight(0)]
+		#[transactional]
+		pub fn withdraw_proceeds(origin: OriginFor<T>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let amount = Proceeds::<T>::take(&who);
+			ensure!(!amount.is_zero(), Error::<T>::NoProceeds);
+			// 收入账户始终保留存在性押金，领取者的存在性押金在转账时检查
+			T::Currency::transfer(
+				&Self::proceeds_account_id(),
+				&who,
+				amount,
+				ExistenceRequirement::KeepAlive,
+			)?;
+			Self::note_activity(&who);
+			Self::deposit_event(Event::ProceedsWithdrawn(who, amount));
+			Ok(())
+		}
+
+		// 克隆，复制一个DNA完全相同的Kitty，除正常质押外还需支付克隆费用
+		#[pallet::weight(0)]
+		#[transactional]
+		pub fn clone_kitty(origin: OriginFor<T>, kitty_id: T::KittyIndex) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			// 只有拥有者可以克隆
+			ensure!(Some(who.clone()) == Owner::<T>::get(kitty_id), Error::<T>::NotOwner);
+			// 灵魂绑定的Kitty不能克隆出可交易的副本
+			Self::ensure_transferable(kitty_id)?;
+			let kitty = Self::kitties(kitty_id).ok_or(Error::<T>::InvalidKittyIndex)?;
+			// 质押+创建克隆体，克隆体与原Kitty同代
+			let generation = Self::generation_of(kitty_id);
+			let clone_id =
+				Self::mint_kitty(&who, Kitty { dna: kitty.dna, clone: true }, generation)?;
+			// 记录克隆来源
+			ClonedFrom::<T>::insert(clone_id, kitty_id);
+			// 收取克隆费用
+			let fee = T::CloneFee::get();
+			Self::charge_fee(&who, fee)?;
+			Self::note_activity(&who);
+			// 发出克隆事件
+			Self::deposit_event(Event::KittyCloned(who, kitty_id, clone_id, fee));
+			Ok(())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		// 检查配置的常量是否合理
+		pub fn check_config() -> Result<(), &'static str> {
+			ensure!(
+				T::StakeForEachKitty::get() >= T::Currency::minimum_balance(),
+				"StakeForEachKitty must not be below the existential deposit"
+			);
+			ensure!(!T::ListingDeposit::get().is_zero(), "ListingDeposit must not be zero");
+			ensure!(T::MaxPremiumTier::get() > 0, "MaxPremiumTier must not be zero");
+			ensure!(!T::TokenSymbol::get().is_empty(), "TokenSymbol must not be empty");
+			ensure!(T::MaxRaceEntrants::get() > 0, "MaxRaceEntrants must not be zero");
+			ensure!(!T::FeaturedPeriod::get().is_zero(), "FeaturedPeriod must not be zero");
+			ensure!(T::MaxInstallments::get() >= 2, "MaxInstallments must be at least 2");
+			ensure!(!T::InstallmentPeriod::get().is_zero(), "InstallmentPeriod must not be zero");
+			ensure!(T::MaxNoteLength::get() > 0, "MaxNoteLength must not be zero");
+			ensure!(T::MaxQueryResults::get() > 0, "MaxQueryResults must not be zero");
+			ensure!(
+				T::MaxOrderBookScan::get() >= T::MaxQueryResults::get(),
+				"MaxOrderBookScan must not be below MaxQueryResults"
+			);
+			ensure!(T::MaxOffersPerKitty::get() > 0, "MaxOffersPerKitty must not be zero");
+			ensure!(T::MaxOffersPerAccount::get() > 0, "MaxOffersPerAccount must not be zero");
+			ensure!(T::MaxBundleSize::get() >= 2, "MaxBundleSize must be at least 2");
+			ensure!(T::MaxScheduledPerBlock::get() > 0, "MaxScheduledPerBlock must not be zero");
+			ensure!(
+				T::MaxFeaturedTallyPerBlock::get() > 0,
+				"MaxFeaturedTallyPerBlock must not be zero"
+			);
+			ensure!(T::MaxBatchSize::get() > 0, "MaxBatchSize must not be zero");
+			ensure!(T::MaxRecentOwners::get() > 0, "MaxRecentOwners must not be zero");
+			ensure!(!T::MaxAuctionDuration::get().is_zero(), "MaxAuctionDuration must not be zero");
+			ensure!(
+				T::RareRarity::get() <= 128,
+				"RareRarity must not exceed the DNA length in bits"
+			);
+			ensure!(
+				!T::IncomingTransferExpiry::get().is_zero(),
+				"IncomingTransferExpiry must not be zero"
+			);
+			let tiers = T::RarityFeeTiers::get();
+			ensure!(
+				tiers.windows(2).all(|pair| pair[0].0 < pair[1].0),
+				"RarityFeeTiers must be sorted by rarity"
+			);
+			// 手续费和版税都从价格中扣除，合计超过100%时收入账户会入不敷出
+			let max_tier = tiers.iter().map(|(_, rate)| rate.deconstruct()).max().unwrap_or(0);
+			ensure!(
+				T::MarketplaceFee::get().deconstruct() as u64
+					+ max_tier as u64
+					+ T::RoyaltyRate::get().deconstruct() as u64
+					<= 1_000_000,
+				"MarketplaceFee, RarityFeeTiers and RoyaltyRate must not exceed 100% in total"
+			);
+			// 冷却期不能超过拍卖的最长持续时间
+			let max_auction = T::MaxAuctionDuration::get();
+			ensure!(
+				T::ResaleCooldown::get() <= max_auction,
+				"ResaleCooldown must not exceed MaxAuctionDuration"
+			);
+			ensure!(
+				T::WashTradeCooldown::get() <= max_auction,
+				"WashTradeCooldown must not exceed MaxAuctionDuration"
+			);
+			ensure!(
+				T::HighDemandOfferThreshold::get() <= T::MaxOffersPerKitty::get(),
+				"HighDemandOfferThreshold must not exceed MaxOffersPerKitty"
+			);
+			if T::HighDemandOfferThreshold::get() > 0 {
+				ensure!(
+					!T::HighDemandStakeIncrease::get().is_zero(),
+					"HighDemandStakeIncrease must not be zero when HighDemandOfferThreshold is set"
+				);
+				ensure!(
+					!T::HighDemandGracePeriod::get().is_zero(),
+					"HighDemandGracePeriod must not be zero when HighDemandOfferThreshold is set"
+				);
+			}
+			ensure!(!T::NoteDepositPerByte::get().is_zero(), "NoteDepositPerByte must not be zero");
+			ensure!(
+				T::GambleWinChance::get() < Permill::one(),
+				"GambleWinChance must be below 100%"
+			);
+			// 以下常量的任何取值都是合理的，不需要检查：
+			// PremiumBreedFee、CloneFee、GambleFee为零时免费，Kitty仍然需要质押
+			// TradingStartBlock、CreateCooldown、MinAgeBeforeBurn、PityThreshold为零时不限制
+			// InstallmentPenalty、SupplyWarningThreshold为Permill，不会超过100%
+			// TokenDecimals只影响显示，AllowedAssets为空时不能以其他资产出售
+			// BreedingMaterialAsset只在BreedingMaterialPerBreed不为零时使用
+			let split = T::RacePrizeSplit::get();
+			ensure!(!split.is_empty(), "RacePrizeSplit must not be empty");
+			ensure!(
+				split.iter().map(|share| share.deconstruct()).sum::<u32>() <= 1_000_000,
+				"RacePrizeSplit must not exceed 100%"
+			);
+			Ok(())
+		}
+
+		// 代币信息，用于客户端格式化价格
+		pub fn price_info() -> PriceInfo<BalanceOf<T>> {
+			PriceInfo {
+				token_symbol: T::TokenSymbol::get(),
+				decimals: T::TokenDecimals::get(),
+				existential_deposit: T::Currency::minimum_balance(),
+				stake_per_kitty: T::StakeForEachKitty::get(),
+			}
+		}
+
+		// 保管交易收入和版税的子账户
+		pub fn proceeds_account_id() -> T::AccountId {
+			T::PalletId::get().into_sub_account(b"proceeds")
+		}
+
+		// 检查买家能否购买，返回卖家和价格
+		fn check_buy(
+			buyer: &T::AccountId,
+			kitty_id: T::KittyIndex,
+		) -> Result<(T::AccountId, BalanceOf<T>), DispatchError> {
+			Self::ensure_trading_started()?;
+			// 获取Kitty的所有者
+			let seller = Owner::<T>::get(kitty_id).ok_or(Error::<T>::InvalidKittyIndex)?;
+			// 检查购买者和所有者是否是同一个人
+			ensure!(*buyer != seller, Error::<T>::BuyerIsOwner);
+			Self::ensure_transferable(kitty_id)?;
+			// 获取Kitty的价格，如果不存在表示Kitty不出售
+			let kitty_price = ListForSale::<T>::get(kitty_id).ok_or(Error::<T>::KittyNotForSell)?;
+			Self::ensure_unlocked(kitty_id)?;
+			Self::ensure_not_wash_trade(buyer, kitty_id)?;
+			Ok((seller, kitty_price))
+		}
+
+		// 冷却期内的最近拥有者不能买回Kitty
+		fn ensure_not_wash_trade(buyer: &T::AccountId, kitty_id: T::KittyIndex) -> DispatchResult {
+			let now = <frame_system::Pallet<T>>::block_number();
+			let cooldown = T::WashTradeCooldown::get();
+			ensure!(
+				!RecentOwners::<T>::get(kitty_id)
+					.iter()
+					.any(|(who, at)| who == buyer && now < at.saturating_add(cooldown)),
+				Error::<T>::WashTradeDetected
+			);
+			Ok(())
+		}
+
+		// 记录失去Kitty的拥有者，同时清除已过冷却期的记录，记录已满时丢弃最早的一条
+		fn note_recent_owner(kitty_id: T::KittyIndex, owner: T::AccountId) {
+			let cooldown = T::WashTradeCooldown::get();
+			if cooldown.is_zero() {
+				return;
+			}
+			let now = <frame_system::Pallet<T>>::block_number();
+			RecentOwners::<T>::mutate(kitty_id, |owners| {
+				owners.retain(|(who, at)| *who != owner && now < at.saturating_add(cooldown));
+				if owners.len() >= T::MaxRecentOwners::get() as usize {
+					owners.remove(0);
+				}
+				let _ = owners.try_push((owner, now));
+			});
+		}
+
+		// 购买前的报价，与购买时的计算完全相同，无法购买时返回None
+		pub fn quote_buy(
+			kitty_id: T::KittyIndex,
+			buyer: &T::AccountId,
+		) -> Option<BuyQuote<BalanceOf<T>>> {
+			let (seller, price) = Self::check_buy(buyer, kitty_id).ok()?;
+			// 报价只适用于原生代币定价的出售
+			if ListingAsset::<T>::contains_key(kitty_id) {
+				return None;
+			}
+			let (marketplace_fee, royalty, seller_amount) =
+				Self::sale_split(kitty_id, &seller, price);
+			let stake = Self::buyer_stake_of(kitty_id);
+			Some(BuyQuote {
+				price,
+				marketplace_fee,
+				royalty: royalty.map(|(_, royalty)| royalty).unwrap_or_else(Zero::zero),
+				seller_amount,
+				stake,
+				total: price.saturating_add(stake),
+			})
+		}
+
+		// 计算价格中的市场手续费、版税和卖家所得
+		fn sale_split(
+			kitty_id: T::KittyIndex,
+			seller: &T::AccountId,
+			price: BalanceOf<T>,
+		) -> (BalanceOf<T>, Option<(T::AccountId, BalanceOf<T>)>, BalanceOf<T>) {
+			let marketplace_fee = (T::MarketplaceFee::get() * price)
+				.saturating_add(Self::rarity_fee_rate(kitty_id) * price);
+			let royalty = Self::royalty_of(kitty_id, seller, price);
+			let seller_amount = price
+				.saturating_sub(marketplace_fee)
+				.saturating_sub(royalty.as_ref().map(|r| r.1).unwrap_or_else(Zero::zero));
+			(marketplace_fee, royalty, seller_amount)
+		}
+
+		// 按Kitty稀有度所在的档位额外收取的手续费比例
+		fn rarity_fee_rate(kitty_id: T::KittyIndex) -> Permill {
+			let rarity = match Self::kitties(kitty_id) {
+				Some(kitty) => Self::rarity(&kitty),
+				None => return Permill::zero(),
+			};
+			T::RarityFeeTiers::get()
+				.into_iter()
+				.take_while(|(min_rarity, _)| *min_rarity <= rarity)
+				.last()
+				.map(|(_, rate)| rate)
+				.unwrap_or_else(Permill::zero)
+		}
+
+		// 第index期的金额，取整余数计入最后一期
+		fn installment_amount(price: BalanceOf<T>, installments: u32, index: u32) -> BalanceOf<T> {
+			let amount = price / installments.into();
+			if index + 1 == installments {
+				price.saturating_sub(amount.saturating_mul((installments - 1).into()))
+			} else {
+				amount
+			}
+		}
+
+		// 分期逾期，扣除罚金后退还已付款项，Kitty留在卖家手中
+		fn default_installment(kitty_id: T::KittyIndex) {
+			let plan = match InstallmentPlans::<T>::take(kitty_id) {
+				Some(plan) => plan,
+				None => return,
+			};
+			KittyLocks::<T>::remove(kitty_id);
+			let penalty = T::InstallmentPenalty::get() * plan.paid;
+			let refund = plan.paid.saturating_sub(penalty);
+			// 已付款项由模块账户托管，金额总能支付
+			let _ = T::Currency::transfer(
+				&Self::account_id(),
+				&plan.buyer,
+				refund,
+				ExistenceRequirement::AllowDeath,
+			);
+			let _ = T::Currency::transfer(
+				&Self::account_id(),
+				&plan.seller,
+				penalty,
+				ExistenceRequirement::AllowDeath,
+			);
+			Self::deposit_event(Event::InstallmentPlanDefaulted(
+				plan.buyer, kitty_id, refund, penalty,
+			));
+		}
+
+		// 当前的评选周期
+		pub fn current_period() -> T::BlockNumber {
+			<frame_system::Pallet<T>>::block_number() / T::FeaturedPeriod::get()
+		}
+
+		// 新周期开始时评选上一个周期票数最多的Kitty，票数相同时ID较小的获胜
+		// 每个区块最多删除MaxFeaturedTallyPerBlock条投票记录，未完成时记录进度，下个区块继续
+		// 返回删除的记录数量
+		fn crown_featured(now: T::BlockNumber) -> u32 {
+			let period_length = T::FeaturedPeriod::get();
+			let (mut period, mut winner) = match FeaturedTally::<T>::take() {
+				Some(tally) => tally,
+				None if !now.is_zero() && (now % period_length).is_zero() => {
+					(now / period_length - One::one(), None)
+				},
+				None => return 0,
+			};
+			let last_period = (now / period_length).saturating_sub(One::one());
+			let limit = T::MaxFeaturedTallyPerBlock::get();
+			let mut removed = 0u32;
+			loop {
+				if removed >= limit {
+					FeaturedTally::<T>::put((period, winner));
+					return removed;
+				}
+				// 先删除投票者记录，不影响统计结果
+				let voters = FeaturedVoters::<T>::drain_prefix(period)
+					.take((limit - removed) as usize)
+					.count();
+				removed = removed.saturating_add(voters as u32);
+				if FeaturedVoters::<T>::iter_key_prefix(period).next().is_some() {
+					FeaturedTally::<T>::put((period, winner));
+					return removed;
+				}
+				let votes: Vec<(T::KittyIndex, u32)> = FeaturedVotes::<T>::drain_prefix(period)
+					.take(limit.saturating_sub(removed) as usize)
+					.collect();
+				for (kitty_id, votes) in votes {
+					removed += 1;
+					let better = match winner {
+						Some((best_id, best_votes)) => {
+							votes > best_votes || (votes == best_votes && kitty_id < best_id)
+						},
+						None => true,
+					};
+					if better {
+						winner = Some((kitty_id, votes));
+					}
+				}
+				if FeaturedVotes::<T>::iter_key_prefix(period).next().is_some() {
+					FeaturedTally::<T>::put((period, winner));
+					return removed;
+				}
+				if let Some((kitty_id, votes)) = winner {
+					FeaturedKitty::<T>::put(kitty_id);
+					Self::deposit_event(Event::KittyFeatured(period, kitty_id, votes));
+				}
+				// 评选耗时超过一个周期时继续评选下一个已结束的周期
+				if period >= last_period {
+					return removed;
+				}
+				period += One::one();
+				winner = None;
+			}
+		}
+
+		// Kitty当前的交易状态
+		pub fn kitty_status(kitty_id: T::KittyIndex) -> KittyStatus<BalanceOf<T>> {
+			if Auctions::<T>::contains_key(kitty_id) {
+				KittyStatus::InAuction
+			} else if KittyLocks::<T>::contains_key(kitty_id) {
+				KittyStatus::Locked
+			} else if let Some(price) = ListForSale::<T>::get(kitty_id) {
+				KittyStatus::Listed(price)
+			} else {
+				KittyStatus::Idle
+			}
+		}
+
+		// 拍卖结束，最高出价者按出价购买
+		fn settle_auction(kitty_id: T::KittyIndex) {
+			let auction = match Auctions::<T>::take(kitty_id) {
+				Some(auction) => auction,
+				None => return,
+			};
+			KittyLocks::<T>::remove(kitty_id);
+			// 最早的最高出价获胜，其余相同金额的出价退还
+			Self::refund_tied_bids(kitty_id);
+			match auction.highest_bid {
+				Some((bidder, price, _)) => {
+					T::Currency::unreserve(&bidder, price);
+					if Self::do_settle_sale(&bidder, &auction.seller, kitty_id, price).is_ok() {
+						Self::deposit_event(Event::AuctionSettled(kitty_id, bidder, price));
+					} else {
+						Self::deposit_event(Event::AuctionSettlementFailed(kitty_id, bidder));
+					}
+				},
+				None => Self::deposit_event(Event::AuctionEndedWithoutBids(kitty_id)),
+			}
+		}
+
+		// 退还与最高出价金额相同的较晚出价
+		fn refund_tied_bids(kitty_id: T::KittyIndex) {
+			for (bidder, (amount, _)) in TiedBids::<T>::drain_prefix(kitty_id) {
+				T::Currency::unreserve(&bidder, amount);
+			}
+		}
+
+		// 检查购买后的冷却期是否已结束
+		fn ensure_resale_allowed(kitty_id: T::KittyIndex) -> DispatchResult {
+			if let Some(unlock_at) = ResaleUnlockAt::<T>::get(kitty_id) {
+				ensure!(
+					<frame_system::Pallet<T>>::block_number() >= unlock_at,
+					Error::<T>::ResaleCooldownActive
+				);
+			}
+			Ok(())
+		}
+
+		// 出售或取消出售
+		fn do_list(
+			who: &T::AccountId,
+			kitty_id: T::KittyIndex,
+			price: Option<BalanceOf<T>>,
+		) -> DispatchResult {
+			Self::ensure_trading_started()?;
+			// 检查是否有权限卖出
+			ensure!(Some(who.clone()) == Owner::<T>::get(kitty_id), Error::<T>::NotOwner);
+			Self::ensure_unlocked(kitty_id)?;
+			match price {
+				Some(_) => {
+					Self::ensure_transferable(kitty_id)?;
+					Self::ensure_resale_allowed(kitty_id)?;
+					Self::ensure_stake_sufficient(kitty_id)?;
+					// 首次挂单时质押挂单押金
+					if !ListingDeposits::<T>::contains_key(kitty_id) {
+						let deposit = T::ListingDeposit::get();
+						T::Currency::reserve(who, deposit)
+							.map_err(|_| Error::<T>::NotEnoughBalanceForListingDeposit)?;
+						ListingDeposits::<T>::insert(kitty_id, (who.clone(), deposit));
+					}
+					// 将Kitty添加到出售列表，默认使用原生代币
+					ListForSale::<T>::insert(kitty_id, price);
+					ListingAsset::<T>::remove(kitty_id);
+				},
+				// 价格为空表示取消出售
+				None => Self::remove_listing(kitty_id),
+			}
+			// 发出Kitty卖出事件
+			Self::deposit_event(Event::KittyListed(who.clone(), kitty_id, price));
+			Ok(())
+		}
+
+		// 检查是否已到开放交易的区块
+		fn ensure_trading_started() -> DispatchResult {
+			ensure!(
+				<frame_system::Pallet<T>>::block_number() >= T::TradingStartBlock::get(),
+				Error::<T>::TradingNotStarted
+			);
+			Ok(())
+		}
+
+		// 模块账户，保管比赛奖池等资金
+		pub fn account_id() -> T::AccountId {
+			T::PalletId::get().into_account()
+		}
+
+		// Kitty被锁定时不能交易
+		// 检查Kitty是否可以被拥有者销毁
+		fn ensure_can_burn(who: &T::AccountId, kitty_id: T::KittyIndex) -> DispatchResult {
+			ensure!(Some(who.clone()) == Owner::<T>::get(kitty_id), Error::<T>::NotOwner);
+			Self::ensure_unlocked(kitty_id)?;
+			if let Some(created_at) = CreatedAt::<T>::get(kitty_id) {
+				ensure!(
+					<frame_system::Pallet<T>>::block_number()
+						>= created_at.saturating_add(T::MinAgeBeforeBurn::get()),
+					Error::<T>::KittyTooYoungToBurn
+				);
+			}
+			Ok(())
+		}
+
+		// 随机数的前4个字节落在获胜概率内时获胜
+		pub(crate) fn gamble_wins(random: &[u8; 16], chance: Permill) -> bool {
+			let roll = u32::from_le_bytes([random[0], random[1], random[2], random[3]]) % 1_000_000;
+			roll < chance.deconstruct()
+		}
+
+		// 在原DNA上随机增加基因位，保证稀有度至少增加1；稀有度已是最高时保持不变
+		pub(crate) fn rarer_dna(dna: [u8; 16], random: &[u8; 16]) -> [u8; 16] {
+			let mut new_dna = dna;
+			for (gene, bits) in new_dna.iter_mut().zip(random.iter()) {
+				*gene |= bits;
+			}
+			if new_dna == dna {
+				if let Some(gene) = new_dna.iter_mut().find(|gene| **gene != u8::MAX) {
+					*gene |= !*gene & gene.wrapping_add(1);
+				}
+			}
+			new_dna
+		}
+
+		// 从低位开始补齐基因位，直到稀有度达到min_rarity
+		pub(crate) fn pity_dna(dna: [u8; 16], min_rarity: u32) -> [u8; 16] {
+			let mut new_dna = dna;
+			let mut rarity: u32 = new_dna.iter().map(|gene| gene.count_ones()).sum();
+			for gene in new_dna.iter_mut() {
+				while rarity < min_rarity && *gene != u8::MAX {
+					*gene |= !*gene & gene.wrapping_add(1);
+					rarity += 1;
+				}
+			}
+			new_dna
+		}
+
+		// 记录账户的最近活动区块
+		fn note_activity(who: &T::AccountId) {
+			LastActivity::<T>::insert(who, <frame_system::Pallet<T>>::block_number());
+		}
+
+		// 灵魂绑定的Kitty不能转移或出售
+		fn ensure_transferable(kitty_id: T::KittyIndex) -> DispatchResult {
+			ensure!(!Soulbound::<T>::get(kitty_id), Error::<T>::KittySoulbound);
+			Ok(())
+		}
+
+		// 特权交易需要ForceOrigin或管理员签名，管理员签名时返回管理员账户
+		fn ensure_privileged(origin: OriginFor<T>) -> Result<Option<T::AccountId>, DispatchError> {
+			if T::ForceOrigin::ensure_origin(origin.clone()).is_ok() {
+				return Ok(None);
+			}
+			let who = ensure_signed(origin)?;
+			ensure!(Some(who.clone()) == Admin::<T>::get(), DispatchError::BadOrigin);
+			Ok(Some(who))
+		}
+
+		// 记录管理员签名的特权交易的活动
+		fn note_privileged_activity(admin: Option<T::AccountId>) {
+			if let Some(admin) = admin {
+				Self::note_activity(&admin);
+			}
+		}
+
+		fn ensure_unlocked(kitty_id: T::KittyIndex) -> DispatchResult {
+			match KittyLocks::<T>::get(kitty_id) {
+				Some(KittyLock::ScheduledTransfer) => Err(Error::<T>::KittyScheduled.into()),
+				Some(_) => Err(Error::<T>::KittyLocked.into()),
+				None => Ok(()),
+			}
+		}
+
+		// Kitty在比赛中的速度，由DNA和本场比赛的随机数决定
+		pub(crate) fn race_speed(dna: &[u8; 16], seed: &[u8], kitty_id: T::KittyIndex) -> u32 {
+			let base: u32 = dna.iter().map(|gene| *gene as u32).sum();
+			let bonus = (seed, kitty_id).using_encoded(blake2_128)[0] as u32;
+			base + bonus
+		}
+
+		// 结算比赛：按速度排名并分配奖池，然后解锁所有参赛Kitty
+		fn resolve_race(race_id: RaceId) {
+			let race = match Races::<T>::take(race_id) {
+				Some(race) => race,
+				None => return,
+			};
+			let entrants = RaceEntrants::<T>::take(race_id);
+			let (seed, _) = T::Randomness::random(&(b"kitties/race", race_id).encode());
+			let seed = seed.encode();
+			// 速度相同时先报名的排在前面
+			let mut ranking: Vec<(T::AccountId, T::KittyIndex, u32)> = entrants
+				.into_inner()
+				.into_iter()
+				.map(|(who, kitty_id)| {
+					let speed = Self::kitties(kitty_id)
+						.map(|kitty| Self::race_speed(&kitty.dna, &seed, kitty_id))
+						.unwrap_or_default();
+					(who, kitty_id, speed)
+				})
+				.collect();
+			ranking.sort_by(|a, b| b.2.cmp(&a.2));
+			let prizes = Self::race_prizes(race.pot, ranking.len());
+			let mut payouts = Vec::with_capacity(prizes.len());
+			for ((who, kitty_id, _), prize) in ranking.iter().zip(prizes.into_iter()) {
+				// 奖池由模块账户保管，金额总能支付
+				let _ = T::Currency::transfer(
+					&Self::account_id(),
+					who,
+					prize,
+					ExistenceRequirement::AllowDeath,
+				);
+				payouts.push((who.clone(), *kitty_id, prize));
+			}
+			for (_, kitty_id, _) in ranking.iter() {
+				KittyLocks::<T>::remove(kitty_id);
+			}
+			Self::deposit_event(Event::RaceResolved(race_id, payouts));
+		}
+
+		// 按名次计算奖金，未分配的比例和取整余数都归第一名
+		pub(crate) fn race_prizes(pot: BalanceOf<T>, finishers: usize) -> Vec<BalanceOf<T>> {
+			let split = T::RacePrizeSplit::get();
+			let places = split.len().min(finishers);
+			if places == 0 {
+				return Vec::new();
+			}
+			let mut prizes: Vec<BalanceOf<T>> =
+				split.iter().take(places).map(|share| *share * pot).collect();
+			let others = prizes
+				.iter()
+				.skip(1)
+				.fold(Zero::zero(), |acc: BalanceOf<T>, prize| acc.saturating_add(*prize));
+			prizes[0] = pot.saturating_sub(others);
+			prizes
+		}
+
+		pub(crate) fn random_value(sender: &T::AccountId) -> [u8; 16] {
+			let seed = T::Randomness::random_seed();
+			let secondary_seed = T::SecondaryRandomness::random_seed();
+			let extrinsic_index = <frame_system::Pallet<T>>::extrinsic_index();
+			// 两个来源相同时保持原来的结果
+			if secondary_seed == seed {
+				return (seed, &sender, extrinsic_index).using_encoded(blake2_128);
+			}
+			(seed, secondary_seed, &sender, extrinsic_index).using_encoded(blake2_128)
+		}
+
+		// 根据选择器混淆父母的DNA
+		fn breed_dna(
+			kitty_id_1: T::KittyIndex,
+			kitty_id_2: T::KittyIndex,
+			selector: &[u8; 16],
+		) -> Result<[u8; 16], DispatchError> {
+			// 繁殖不能是同一个Kitty
+			ensure!(kitty_id_1 != kitty_id_2, Error::<T>::SameParentIndex);
+			// 获取Kitty1
+			let kitty1 = Self::kitties(kitty_id_1).ok_or(Error::<T>::InvalidKittyIndex)?;
+			// 获取Kitty2
+			let kitty2 = Self::kitties(kitty_id_2).ok_or(Error::<T>::InvalidKittyIndex)?;
+			// 克隆体不能繁殖
+			ensure!(!kitty1.clone && !kitty2.clone, Error::<T>::CloneCannotBreed);
+			// 出售或拍卖中的Kitty不能繁殖，避免繁殖过程中被卖出；不会自动取消出售
+			for kitty_id in [kitty_id_1, kitty_id_2] {
+				ensure!(
+					ListForSale::<T>::get(kitty_id).is_none()
+						&& !Auctions::<T>::contains_key(kitty_id),
+					Error::<T>::ParentListed
+				);
+			}
+			// 获取Parents Kitty的DNA
+			Ok(Self::mix_dna(&kitty1.dna, &kitty2.dna, selector))
+		}
+
+		pub(crate) fn mix_dna(dna_1: &[u8; 16], dna_2: &[u8; 16], selector: &[u8; 16]) -> [u8; 16] {
+			let mut new_dna = [0u8; 16];
+			for i in 0..dna_1.len() {
+				new_dna[i] = (selector[i] & dna_1[i]) | (!selector[i] & dna_2[i]);
+			}
+			new_dna
+		}
+
+		// 进行多轮变异，每轮从上一轮的随机数派生新的随机数
+		pub(crate) fn mutate_dna(mut dna: [u8; 16], seed: [u8; 16], passes: u8) -> [u8; 16] {
+			let mut entropy = seed;
+			for pass in 0..passes {
+				entropy = (entropy, pass).using_encoded(blake2_128);
+				for (gene, bits) in dna.iter_mut().zip(entropy.iter()) {
+					*gene ^= bits & MUTATION_MASK;
+				}
+			}
+			dna
+		}
+
+		// 收取手续费
+		fn charge_fee(who: &T::AccountId, fee: BalanceOf<T>) -> DispatchResult {
+			if fee.is_zero() {
+				return Ok(());
+			}
+			let imbalance = T::Currency::withdraw(
+				who,
+				fee,
+				WithdrawReasons::FEE,
+				ExistenceRequirement::KeepAlive,
+			)
+			.map_err(|_| Error::<T>::NotEnoughBalanceForFee)?;
+			T::FeeDestination::on_unbalanced(imbalance);
+			Ok(())
+		}
+
+		// 转移Kitty，接收者要求确认时先锁定并等待接收者确认
+		pub fn do_transfer(
+			from: &T::AccountId,
+			to: &T::AccountId,
+			kitty_id: T::KittyIndex,
+		) -> DispatchResult {
+			if from != to && Self::requires_acceptance(to) {
+				Self::propose_transfer(from, to, kitty_id)
+			} else {
+				Self::move_kitty(from, to, kitty_id)
+			}
+		}
+
+		// 直接转移Kitty（双方分别释放和重新质押）
+		#[transactional]
+		fn move_kitty(
+			from: &T::AccountId,
+			to: &T::AccountId,
+			kitty_id: T::KittyIndex,
+		) -> DispatchResult {
+			Self::ensure_transferable(kitty_id)?;
+			Self::ensure_unlocked(kitty_id)?;
+			// 新的拥有者不承担需求旺盛时提高的质押
+			Self::lift_demand_stake(kitty_id);
+			// 获取Kitty当前的质押金额
+			let stake_amount = Self::stake_of(kitty_id);
 			// 质押新的拥有者一定金额
-			T::Currency::reserve(&new_owner, stake_amount)
+			T::Currency::reserve(to, stake_amount)
 				.map_err(|_| Error::<T>::NotEnoughBalanceForStaking)?;
 			// 解除旧拥有者的质押
-			T::Currency::unreserve(&who, stake_amount);
+			T::Currency::unreserve(from, Self::reserved_stake_of(kitty_id));
+			// 新的拥有者已质押全部金额
+			StakeShortfall::<T>::remove(kitty_id);
 			// 更新Kitty的所有者为新的拥有者
-			Owner::<T>::insert(kitty_id, Some(new_owner.clone()));
+			Self::set_owner(kitty_id, Some(to.clone()));
+			// 转移后取消出售
+			Self::remove_listing(kitty_id);
+			// 拥有者已改变，取消转移给备用拥有者的计划
+			Self::cancel_fallback_transfer(kitty_id);
+			// 笔记不能泄露给新的拥有者
+			Self::remove_note(kitty_id);
+			// 写入链下索引
+			Self::index_history(
+				kitty_id,
+				HistoryRecord::Transfer { from: from.clone(), to: to.clone() },
+			);
 			// 发布转移事件
-			Self::deposit_event(Event::KittyTransfer(who, new_owner, kitty_id));
+			Self::deposit_event(Event::KittyTransfer(from.clone(), to.clone(), kitty_id));
 			Ok(())
 		}
 
-		// 购买
-		#[pallet::weight(0)]
-		pub fn buy(origin: OriginFor<T>, kitty_id: T::KittyIndex) -> DispatchResult {
-			let buyer = ensure_signed(origin)?;
-			// 获取Kitty的所有者
-			let seller = Owner::<T>::get(kitty_id).unwrap();
-			// 检查购买者和所有者是否是同一个人
-			ensure!(Some(buyer.clone()) != Some(seller.clone()), Error::<T>::BuyerIsOwner);
-			// 获取Kitty的价格，如果不存在表示Kitty不出售
-			let kitty_price = ListForSale::<T>::get(kitty_id).ok_or(Error::<T>::KittyNotForSell)?;
+		// 使用原生代币结算Kitty交易
+		pub fn do_settle_sale(
+			buyer: &T::AccountId,
+			seller: &T::AccountId,
+			kitty_id: T::KittyIndex,
+			kitty_price: BalanceOf<T>,
+		) -> DispatchResult {
+			Self::settle_sale(buyer, seller, kitty_id, kitty_price, None)
+		}
+
+		// 结算Kitty交易，asset为空时使用原生代币
+		#[transactional]
+		fn settle_sale(
+			buyer: &T::AccountId,
+			seller: &T::AccountId,
+			kitty_id: T::KittyIndex,
+			kitty_price: BalanceOf<T>,
+			asset: Option<T::AssetId>,
+		) -> DispatchResult {
+			Self::ensure_transferable(kitty_id)?;
+			Self::ensure_unlocked(kitty_id)?;
+			// 买家需要质押的金额，与报价一致
+			let stake_amount = Self::buyer_stake_of(kitty_id);
+			// 买家不承担需求旺盛时提高的质押
+			Self::lift_demand_stake(kitty_id);
 			// 获取买家余额
-			let buyer_balance = T::Currency::free_balance(&buyer);
-			// 质押的金额
-			let stake_amount = T::StakeForEachKitty::get();
-			// 检查买家余额是否足够
+			let buyer_balance = T::Currency::free_balance(buyer);
+			// 检查买家余额是否足够，使用其他资产结算时原生代币只需支付质押
+			let native_price = if asset.is_none() { kitty_price } else { Zero::zero() };
 			ensure!(
-				buyer_balance > (kitty_price + stake_amount),
+				buyer_balance > (native_price + stake_amount),
 				Error::<T>::NotEnoughBalanceForBuying
 			);
 			// 质押新的拥有者一定金额
-			T::Currency::reserve(&buyer, stake_amount)
+			T::Currency::reserve(buyer, stake_amount)
 				.map_err(|_| Error::<T>::NotEnoughBalanceForStaking)?;
 			// 解除旧拥有者的质押
-			T::Currency::unreserve(&seller, stake_amount);
-			// 买家向卖家转账
-			T::Currency::transfer(&buyer, &seller, kitty_price, ExistenceRequirement::KeepAlive)?;
+			T::Currency::unreserve(seller, Self::reserved_stake_of(kitty_id));
+			StakeShortfall::<T>::remove(kitty_id);
+			match asset {
+				None => {
+					Self::pay_proceeds(buyer, seller, kitty_id, kitty_price)?;
+					LastSalePrice::<T>::insert(kitty_id, kitty_price);
+				},
+				Some(asset_id) => {
+					Self::pay_in_asset(buyer, seller, kitty_id, kitty_price, asset_id)?
+				},
+			}
 			// 更新Kitty的所有者为买家
-			Owner::<T>::insert(kitty_id, Some(buyer.clone()));
+			Self::set_owner(kitty_id, Some(buyer.clone()));
 			// 将Kitty从出售列表中移除
-			ListForSale::<T>::remove(kitty_id);
+			Self::remove_listing(kitty_id);
+			// 已售出，取消转移给备用拥有者的计划
+			Self::cancel_fallback_transfer(kitty_id);
+			// 笔记不能泄露给买家
+			Self::remove_note(kitty_id);
+			// 购买后的冷却期内不能再次出售
+			let cooldown = T::ResaleCooldown::get();
+			if cooldown.is_zero() {
+				ResaleUnlockAt::<T>::remove(kitty_id);
+			} else {
+				ResaleUnlockAt::<T>::insert(
+					kitty_id,
+					<frame_system::Pallet<T>>::block_number() + cooldown,
+				);
+			}
+			// 写入链下索引
+			Self::index_history(
+				kitty_id,
+				HistoryRecord::Sale {
+					seller: seller.clone(),
+					buyer: buyer.clone(),
+					price: kitty_price,
+				},
+			);
 			// 发出交易完成事件
-			Self::deposit_event(Event::KittySold(buyer, seller, kitty_id));
+			Self::deposit_event(Event::KittySold(buyer.clone(), seller.clone(), kitty_id));
 			Ok(())
 		}
-	}
 
-	impl<T: Config> Pallet<T> {
-		fn random_value(sender: &T::AccountId) -> [u8; 16] {
-			let payload = (
-				T::Randomness::random_seed(),
-				&sender,
-				<frame_system::Pallet<T>>::extrinsic_index(),
+		// 货款转入收入账户，由各方之后自行领取
+		fn pay_proceeds(
+			buyer: &T::AccountId,
+			seller: &T::AccountId,
+			kitty_id: T::KittyIndex,
+			kitty_price: BalanceOf<T>,
+		) -> DispatchResult {
+			let (marketplace_fee, royalty, seller_amount) =
+				Self::sale_split(kitty_id, seller, kitty_price);
+			// 收取市场手续费
+			Self::charge_fee(buyer, marketplace_fee)?;
+			// 买家将其余货款转入收入账户，各方之后自行领取
+			T::Currency::transfer(
+				buyer,
+				&Self::proceeds_account_id(),
+				kitty_price.saturating_sub(marketplace_fee),
+				ExistenceRequirement::KeepAlive,
+			)?;
+			// 记录创建者的版税
+			if let Some((creator, royalty)) = royalty.clone() {
+				PendingRoyalties::<T>::mutate(&creator, |pending| {
+					*pending = pending.saturating_add(royalty)
+				});
+			}
+			// 记录卖家的收入
+			Proceeds::<T>::mutate(seller, |proceeds| {
+				*proceeds = proceeds.saturating_add(seller_amount)
+			});
+			if let Some((creator, royalty)) = royalty {
+				Self::deposit_event(Event::RoyaltyAccrued(creator, kitty_id, royalty));
+			}
+			Self::deposit_event(Event::ProceedsCredited(seller.clone(), kitty_id, seller_amount));
+			Ok(())
+		}
+
+		// 使用其他资产结算时直接支付给创建者和卖家，市场手续费以该资产转入模块账户
+		fn pay_in_asset(
+			buyer: &T::AccountId,
+			seller: &T::AccountId,
+			kitty_id: T::KittyIndex,
+			kitty_price: BalanceOf<T>,
+			asset_id: T::AssetId,
+		) -> DispatchResult {
+			let (marketplace_fee, royalty, seller_amount) =
+				Self::sale_split(kitty_id, seller, kitty_price);
+			if !marketplace_fee.is_zero() {
+				let fee_destination = T::FeeAssetDestination::get();
+				T::Assets::transfer(asset_id, buyer, &fee_destination, marketplace_fee, true)?;
+			}
+			if let Some((creator, royalty)) = royalty {
+				T::Assets::transfer(asset_id, buyer, &creator, royalty, true)?;
+			}
+			T::Assets::transfer(asset_id, buyer, seller, seller_amount, true)?;
+			Self::deposit_event(Event::KittySoldInAsset(
+				buyer.clone(),
+				seller.clone(),
+				kitty_id,
+				asset_id,
+				kitty_price,
+			));
+			Ok(())
+		}
+
+		// 链下索引中Kitty第seq条历史记录的键
+		pub fn history_key(kitty_id: T::KittyIndex, seq: u32) -> Vec<u8> {
+			let mut key = HISTORY_KEY_PREFIX.to_vec();
+			key.extend(kitty_id.encode());
+			key.push(b'/');
+			key.extend(seq.encode());
+			key
+		}
+
+		// 将历史记录写入链下索引，只影响链下存储
+		fn index_history(
+			kitty_id: T::KittyIndex,
+			record: HistoryRecord<T::AccountId, BalanceOf<T>>,
+		) {
+			if !T::EnableOffchainIndexing::get() {
+				return;
+			}
+			let seq = HistoryLength::<T>::mutate(kitty_id, |len| {
+				let seq = *len;
+				*len = len.saturating_add(1);
+				seq
+			});
+			sp_io::offchain_index::set(&Self::history_key(kitty_id, seq), &record.encode());
+		}
+
+		// 交易应付给创建者的版税，创建者自己出售时没有版税
+		fn royalty_of(
+			kitty_id: T::KittyIndex,
+			seller: &T::AccountId,
+			price: BalanceOf<T>,
+		) -> Option<(T::AccountId, BalanceOf<T>)> {
+			let creator = KittyCreator::<T>::get(kitty_id).filter(|creator| creator != seller)?;
+			let royalty = T::RoyaltyRate::get() * price;
+			if royalty.is_zero() {
+				return None;
+			}
+			Some((creator, royalty))
+		}
+
+		// 从AgendaCursor（没有时为当前区块）开始执行到期的任务，最多执行MaxScheduledPerBlock个
+		// 达到上限时记录未执行完的区块，下个区块继续执行
+		// 返回执行的任务数量和没有执行任务的区块数量
+		fn process_agenda(now: T::BlockNumber) -> (u32, u32) {
+			let limit = T::MaxScheduledPerBlock::get();
+			let mut processed = 0u32;
+			let mut skipped_blocks = 0u32;
+			let mut block = AgendaCursor::<T>::take().unwrap_or(now);
+			while block <= now {
+				let tasks: Vec<ScheduledTask<T::KittyIndex>> = Agenda::<T>::drain_prefix(block)
+					.map(|(task, _)| task)
+					.take(limit.saturating_sub(processed) as usize)
+					.collect();
+				if tasks.is_empty() {
+					skipped_blocks = skipped_blocks.saturating_add(1);
+				}
+				for task in tasks {
+					Self::execute_task(task);
+					processed += 1;
+				}
+				if processed >= limit {
+					if block < now || Agenda::<T>::iter_key_prefix(block).next().is_some() {
+						AgendaCursor::<T>::put(block);
+					}
+					break;
+				}
+				block += One::one();
+			}
+			(processed, skipped_blocks)
+		}
+
+		// 执行到期的任务
+		fn execute_task(task: ScheduledTask<T::KittyIndex>) {
+			match task {
+				ScheduledTask::ResolveRace(race_id) => Self::resolve_race(race_id),
+				ScheduledTask::InstallmentDue(kitty_id) => Self::default_installment(kitty_id),
+				ScheduledTask::SettleAuction(kitty_id) => Self::settle_auction(kitty_id),
+				ScheduledTask::ScheduledTransfer(kitty_id) => {
+					let (to, _) = match ScheduledTransfers::<T>::take(kitty_id) {
+						Some(plan) => plan,
+						None => return,
+					};
+					KittyLocks::<T>::remove(kitty_id);
+					let owner = match Owner::<T>::get(kitty_id) {
+						Some(owner) => owner,
+						None => return,
+					};
+					// 接收者无法质押时转移失败，Kitty解锁并留在原拥有者处
+					if Self::do_transfer(&owner, &to, kitty_id).is_err() {
+						Self::deposit_event(Event::ScheduledTransferFailed(kitty_id));
+					}
+				},
+				ScheduledTask::ExpireIncoming(kitty_id) => {
+					// 过期未确认，Kitty解锁并留在发送者处
+					if let Some((from, _, _)) = PendingTransfers::<T>::take(kitty_id) {
+						KittyLocks::<T>::remove(kitty_id);
+						Self::deposit_event(Event::IncomingTransferExpired(from, kitty_id));
+					}
+				},
+				ScheduledTask::FallbackTransfer(kitty_id) => {
+					let plan = match FallbackTransfers::<T>::take(kitty_id) {
+						Some(plan) => plan,
+						None => return,
+					};
+					// 拥有者未改变说明截止前未售出
+					if Owner::<T>::get(kitty_id) != Some(plan.owner.clone()) {
+						return;
+					}
+					if Self::do_transfer(&plan.owner, &plan.fallback_owner, kitty_id).is_err() {
+						Self::deposit_event(Event::FallbackTransferFailed(kitty_id));
+					}
+				},
+			}
+		}
+
+		// 锁定Kitty并等待接收者确认，过期后自动解锁
+		fn propose_transfer(
+			from: &T::AccountId,
+			to: &T::AccountId,
+			kitty_id: T::KittyIndex,
+		) -> DispatchResult {
+			Self::ensure_transferable(kitty_id)?;
+			Self::ensure_unlocked(kitty_id)?;
+			// 锁定期间不能出售
+			Self::remove_listing(kitty_id);
+			Self::cancel_fallback_transfer(kitty_id);
+			let expires_at = <frame_system::Pallet<T>>::block_number()
+				.saturating_add(T::IncomingTransferExpiry::get());
+			PendingTransfers::<T>::insert(kitty_id, (from.clone(), to.clone(), expires_at));
+			Agenda::<T>::insert(expires_at, ScheduledTask::ExpireIncoming(kitty_id), ());
+			KittyLocks::<T>::insert(kitty_id, KittyLock::PendingTransfer);
+			Self::deposit_event(Event::TransferPending(
+				from.clone(),
+				to.clone(),
+				kitty_id,
+				expires_at,
+			));
+			Ok(())
+		}
+
+		// 取消转移给备用拥有者的计划
+		fn cancel_fallback_transfer(kitty_id: T::KittyIndex) {
+			if let Some(plan) = FallbackTransfers::<T>::take(kitty_id) {
+				Agenda::<T>::remove(plan.deadline, ScheduledTask::FallbackTransfer(kitty_id));
+			}
+		}
+
+		// 将Kitty从出售列表中移除，并退还挂单押金
+		fn remove_listing(kitty_id: T::KittyIndex) {
+			ListForSale::<T>::remove(kitty_id);
+			ListingAsset::<T>::remove(kitty_id);
+			if let Some((depositor, deposit)) = ListingDeposits::<T>::take(kitty_id) {
+				T::Currency::unreserve(&depositor, deposit);
+			}
+		}
+
+		// 移除Kitty及其所有数据，退还质押和押金
+		fn do_burn(owner: &T::AccountId, kitty_id: T::KittyIndex) {
+			Self::remove_listing(kitty_id);
+			Self::cancel_fallback_transfer(kitty_id);
+			Self::remove_note(kitty_id);
+			Self::set_owner(kitty_id, None);
+			T::Currency::unreserve(owner, Self::reserved_stake_of(kitty_id));
+			KittyStake::<T>::remove(kitty_id);
+			StakeShortfall::<T>::remove(kitty_id);
+			Kitties::<T>::remove(kitty_id);
+			KittyCreator::<T>::remove(kitty_id);
+			ClonedFrom::<T>::remove(kitty_id);
+			ResaleUnlockAt::<T>::remove(kitty_id);
+			CreatedAt::<T>::remove(kitty_id);
+			Soulbound::<T>::remove(kitty_id);
+			RecentOwners::<T>::remove(kitty_id);
+			LastSalePrice::<T>::remove(kitty_id);
+			let generation = KittyGeneration::<T>::take(kitty_id);
+			match GenerationCounts::<T>::get(generation) {
+				count if count > 1 => GenerationCounts::<T>::insert(generation, count - 1),
+				_ => GenerationCounts::<T>::remove(generation),
+			}
+			if matches!(RarestKitty::<T>::get(), Some((rarest, _)) if rarest == kitty_id) {
+				Self::recompute_rarest();
+			}
+			// 写入链下索引，历史记录在销毁后保留
+			Self::index_history(kitty_id, HistoryRecord::Burn { owner: owner.clone() });
+			Self::deposit_event(Event::KittyBurned(owner.clone(), kitty_id));
+		}
+
+		// Kitty的稀有度为DNA中1的位数，克隆体的稀有度为0
+		pub fn rarity(kitty: &Kitty) -> u32 {
+			if kitty.clone {
+				return 0;
+			}
+			kitty.dna.iter().map(|gene| gene.count_ones()).sum()
+		}
+
+		// 新的Kitty稀有度更高时成为最稀有的Kitty，稀有度相同时保留较早的Kitty
+		pub(crate) fn update_rarest(kitty_id: T::KittyIndex, kitty: &Kitty) {
+			let rarity = Self::rarity(kitty);
+			match RarestKitty::<T>::get() {
+				Some((_, max)) if max >= rarity => {},
+				_ => RarestKitty::<T>::put((kitty_id, rarity)),
+			}
+		}
+
+		// 遍历所有Kitty重新计算最稀有的Kitty，只在销毁当前最稀有的Kitty时调用
+		// 需要读取所有Kitty，开销与Kitty数量成正比
+		fn recompute_rarest() {
+			let mut rarest: Option<(T::KittyIndex, u32)> = None;
+			for (kitty_id, kitty) in Kitties::<T>::iter() {
+				let kitty = match kitty {
+					Some(kitty) => kitty,
+					None => continue,
+				};
+				let rarity = Self::rarity(&kitty);
+				rarest = match rarest {
+					// 稀有度相同时保留ID较小的Kitty
+					Some((id, max)) if max > rarity || (max == rarity && id < kitty_id) => {
+						Some((id, max))
+					},
+					_ => Some((kitty_id, rarity)),
+				};
+			}
+			match rarest {
+				Some(rarest) => RarestKitty::<T>::put(rarest),
+				None => RarestKitty::<T>::kill(),
+			}
+		}
+
+		// 赞助繁殖需要赞助者签名的内容
+		pub fn sponsor_payload(
+			who: &T::AccountId,
+			kitty_id_1: T::KittyIndex,
+			kitty_id_2: T::KittyIndex,
+			nonce: u32,
+		) -> Vec<u8> {
+			(b"sponsored_breed", who, kitty_id_1, kitty_id_2, nonce).encode()
+		}
+
+		// 移除报价并更新报价数量
+		fn take_offer(kitty_id: T::KittyIndex, bidder: &T::AccountId) -> Option<BalanceOf<T>> {
+			let amount = Offers::<T>::take(kitty_id, bidder)?;
+			BidderOffers::<T>::remove(bidder, kitty_id);
+			let count = OfferCount::<T>::mutate(kitty_id, |count| {
+				*count = count.saturating_sub(1);
+				*count
+			});
+			Self::release_offer_slot(bidder);
+			// 报价数量回落到阈值以下时需求结束
+			if count < T::HighDemandOfferThreshold::get() {
+				Self::lift_demand_stake(kitty_id);
+			}
+			Some(amount)
+		}
+
+		// 取消Kitty的所有报价并退还质押
+		fn cancel_offers(kitty_id: T::KittyIndex) {
+			for (bidder, amount) in Offers::<T>::drain_prefix(kitty_id) {
+				T::Currency::unreserve(&bidder, amount);
+				BidderOffers::<T>::remove(&bidder, kitty_id);
+				Self::release_offer_slot(&bidder);
+				Self::deposit_event(Event::OfferCancelled(bidder, kitty_id, amount));
+			}
+			OfferCount::<T>::remove(kitty_id);
+			Self::lift_demand_stake(kitty_id);
+		}
+
+		// 减少账户待处理的报价数量，归零时删除记录
+		fn release_offer_slot(bidder: &T::AccountId) {
+			AccountOfferCount::<T>::mutate_exists(bidder, |count| {
+				*count = count.map(|c| c.saturating_sub(1)).filter(|c| !c.is_zero());
+			});
+		}
+
+		// 更新Kitty的拥有者，同时维护拥有者的Kitty索引
+		// 拥有者改变时取消所有报价
+		fn set_owner(kitty_id: T::KittyIndex, owner: Option<T::AccountId>) {
+			if let Some(old_owner) = Owner::<T>::get(kitty_id) {
+				OwnedKitties::<T>::remove(&old_owner, kitty_id);
+				Self::cancel_offers(kitty_id);
+				if owner.as_ref().map_or(false, |owner| *owner != old_owner) {
+					Self::note_recent_owner(kitty_id, old_owner);
+				}
+			}
+			match owner {
+				Some(owner) => {
+					OwnedKitties::<T>::insert(&owner, kitty_id, ());
+					Owner::<T>::insert(kitty_id, Some(owner));
+				},
+				None => Owner::<T>::remove(kitty_id),
+			}
+		}
+
+		// 错误及其说明，顺序与Error的编号一致，新的错误只能追加在末尾
+		pub fn error_descriptions() -> Vec<(Error<T>, &'static str)> {
+			vec![
+				(
+					Error::<T>::KittiesCountOverflow,
+					"The maximum number of kitties has been reached",
+				),
+				(Error::<T>::NotOwner, "The caller does not own this kitty"),
+				(Error::<T>::SameParentIndex, "A kitty cannot be bred with itself"),
+				(Error::<T>::InvalidKittyIndex, "The kitty does not exist"),
+				(Error::<T>::BuyerIsOwner, "The kitty already belongs to the caller"),
+				(Error::<T>::KittyNotForSell, "The kitty is not for sale"),
+				(Error::<T>::NotEnoughBalanceForBuying, "Not enough balance to buy this kitty"),
+				(
+					Error::<T>::NotEnoughBalanceForStaking,
+					"Not enough balance to cover the kitty stake",
+				),
+				(Error::<T>::ZeroStakeAmount, "The stake amount must not be zero"),
+				(
+					Error::<T>::NotEnoughBalanceForListingDeposit,
+					"Not enough balance to cover the listing deposit",
+				),
+				(Error::<T>::FallbackIsOwner, "The fallback recipient is already the owner"),
+				(Error::<T>::DeadlineInPast, "The deadline must be in the future"),
+				(Error::<T>::InvalidPremiumTier, "The premium tier is out of range"),
+				(Error::<T>::NotEnoughBalanceForFee, "Not enough balance to pay the fee"),
+				(Error::<T>::KittyNotOrphaned, "The kitty still has an owner"),
+				(Error::<T>::KittyLocked, "The kitty is locked and cannot be traded right now"),
+				(Error::<T>::InvalidRaceId, "The race does not exist"),
+				(Error::<T>::RaceAlreadyStarted, "The race has already started"),
+				(Error::<T>::TooManyRaceEntrants, "The race is full"),
+				(Error::<T>::NotRaceEntrant, "The kitty is not entered in this race"),
+				(Error::<T>::NoPendingRoyalties, "There are no royalties to claim"),
+				(Error::<T>::CloneCannotBreed, "Cloned kitties cannot breed"),
+				(Error::<T>::TradingNotStarted, "Trading has not started yet"),
+				(Error::<T>::NoProceeds, "There are no proceeds to withdraw"),
+				(Error::<T>::AlreadyVotedThisPeriod, "The caller has already voted this period"),
+				(Error::<T>::InvalidInstallmentCount, "The number of installments is out of range"),
+				(Error::<T>::NoInstallmentPlan, "The kitty has no installment plan"),
+				(
+					Error::<T>::NotInstallmentBuyer,
+					"The caller is not the buyer of this installment plan",
+				),
+				(Error::<T>::NoteTooLong, "The note is too long"),
+				(
+					Error::<T>::NotEnoughBalanceForNoteDeposit,
+					"Not enough balance to cover the note deposit",
+				),
+				(
+					Error::<T>::ResaleCooldownActive,
+					"The kitty was bought recently and cannot be resold yet",
+				),
+				(Error::<T>::StakeNotStranded, "The kitty stake is not stranded"),
+				(Error::<T>::AlreadyInAuction, "The kitty is already in auction"),
+				(Error::<T>::InvalidAuctionDuration, "The auction duration is out of range"),
+				(Error::<T>::NotInAuction, "The kitty is not in auction"),
+				(Error::<T>::AuctionEnded, "The auction has ended"),
+				(Error::<T>::BidTooLow, "The bid is too low"),
+				(Error::<T>::NotEnoughBalanceForBidding, "Not enough balance to place this bid"),
+				(Error::<T>::ParentListed, "A listed or auctioned kitty cannot breed"),
+				(Error::<T>::AssetNotAllowed, "The asset is not accepted for listings"),
+				(
+					Error::<T>::AssetListingNotSupported,
+					"This operation is not supported for kitties listed in an asset",
+				),
+				(Error::<T>::InvalidOfferAmount, "The offer amount must not be zero"),
+				(Error::<T>::NotEnoughBalanceForOffer, "Not enough balance to make this offer"),
+				(Error::<T>::TooManyOffers, "The kitty has too many pending offers"),
+				(Error::<T>::NoOffer, "There is no such offer"),
+				(Error::<T>::KittyTooYoungToBurn, "The kitty is too young to be burned"),
+				(
+					Error::<T>::InvalidBundleSize,
+					"The number of kitties in the bundle is out of range",
+				),
+				(Error::<T>::KittyAlreadyBundled, "The kitty is already in a bundle"),
+				(Error::<T>::InvalidBundleId, "The bundle does not exist"),
+				(Error::<T>::BundleNotForSell, "The bundle is not for sale"),
+				(
+					Error::<T>::StakeTopUpRequired,
+					"The kitty stake must be topped up before it can be sold",
+				),
+				(Error::<T>::InvalidSponsorSignature, "The sponsor signature is invalid"),
+				(Error::<T>::SponsorCannotPay, "The sponsor cannot pay for this breeding"),
+				(Error::<T>::CloneCannotGamble, "Cloned kitties cannot be gambled"),
+				(Error::<T>::KittyScheduled, "The kitty is scheduled to be transferred"),
+				(Error::<T>::NoScheduledTransfer, "The kitty has no scheduled transfer"),
+				(Error::<T>::KittySoulbound, "The kitty is soulbound and cannot be traded"),
+				(Error::<T>::TooManyActiveOffers, "The account has too many active offers"),
+				(Error::<T>::CreateOnCooldown, "The account must wait before creating again"),
+				(Error::<T>::NoPendingTransfer, "There is no pending transfer of the kitty to you"),
+				(
+					Error::<T>::SupplySealed,
+					"The supply is sealed and no more kitties can be minted",
+				),
+				(Error::<T>::NotExported, "The kitty has not been exported"),
+				(Error::<T>::WashTradeDetected, "The buyer owned the kitty too recently"),
+				(Error::<T>::NotEnoughBreedingMaterial, "Not enough breeding material to breed"),
+				(Error::<T>::InvalidRecountLimit, "The recount limit must not be zero"),
+				(
+					Error::<T>::RecipientRequiresAcceptance,
+					"The recipient must confirm each incoming transfer",
+				),
+			]
+		}
+
+		// who是否是kitty_id当前的拥有者，未知的Kitty返回false
+		pub fn owns(who: &T::AccountId, kitty_id: T::KittyIndex) -> bool {
+			Owner::<T>::get(kitty_id).as_ref() == Some(who)
+		}
+
+		// 从start开始按ID顺序返回最多limit（至少为1，不超过MaxQueryResults）个出售中或拍卖中的Kitty
+		// 每次最多检查MaxOrderBookScan个ID，未检查完时返回下次开始的ID
+		// 已到结束区块但尚未结算的拍卖不包含在内
+		pub fn order_book(
+			start: T::KittyIndex,
+			limit: u32,
+		) -> OrderBookPage<T::AccountId, T::KittyIndex, BalanceOf<T>, T::BlockNumber, T::AssetId>
+		{
+			// limit为0时每页至少返回一个，避免客户端在同一位置反复查询
+			let limit = limit.max(1).min(T::MaxQueryResults::get()) as usize;
+			let end = Self::kitties_count().unwrap_or_else(Zero::zero);
+			let now = <frame_system::Pallet<T>>::block_number();
+			let mut entries = Vec::new();
+			let mut kitty_id = start;
+			let mut scanned = 0u32;
+			while kitty_id < end && entries.len() < limit && scanned < T::MaxOrderBookScan::get() {
+				if let Some(entry) = Self::order_of(kitty_id, now) {
+					entries.push(entry);
+				}
+				kitty_id += One::one();
+				scanned += 1;
+			}
+			let next = if kitty_id < end { Some(kitty_id) } else { None };
+			OrderBookPage { entries, next }
+		}
+
+		// Kitty在订单簿中的记录，未出售或拍卖已过期时为空
+		fn order_of(
+			kitty_id: T::KittyIndex,
+			now: T::BlockNumber,
+		) -> Option<
+			OrderBookEntry<T::AccountId, T::KittyIndex, BalanceOf<T>, T::BlockNumber, T::AssetId>,
+		> {
+			if let Some(auction) = Auctions::<T>::get(kitty_id) {
+				if auction.end <= now {
+					return None;
+				}
+				return Some(OrderBookEntry {
+					kitty_id,
+					seller: auction.seller,
+					kind: OrderKind::Auction {
+						reserve: auction.reserve,
+						end: auction.end,
+						highest_bid: auction
+							.highest_bid
+							.map(|(bidder, amount, _)| (bidder, amount)),
+					},
+				});
+			}
+			let price = ListForSale::<T>::get(kitty_id)?;
+			let seller = Owner::<T>::get(kitty_id)?;
+			Some(OrderBookEntry {
+				kitty_id,
+				seller,
+				kind: OrderKind::FixedPrice { price, asset: ListingAsset::<T>::get(kitty_id) },
+			})
+		}
+
+		// Kitty的形象描述，未知的Kitty返回None
+		pub fn image_descriptor(kitty_id: T::KittyIndex) -> Option<ImageDescriptor> {
+			Self::kitties(kitty_id).map(|kitty| Self::describe_dna(&kitty))
+		}
+
+		// 第i层的素材编号取DNA第2i个字节，调色板编号取第2i+1个字节
+		pub fn describe_dna(kitty: &Kitty) -> ImageDescriptor {
+			let layers = IMAGE_LAYERS
+				.iter()
+				.zip(kitty.dna.chunks(2))
+				.map(|(kind, genes)| ImageLayer {
+					kind: *kind,
+					variant: genes[0] % LAYER_VARIANTS,
+					palette: genes[1] % PALETTE_SIZE,
+				})
+				.collect();
+			ImageDescriptor { layers, clone: kitty.clone }
+		}
+
+		// 每一代现存的Kitty数量，按代数排序
+		pub fn supply_by_generation() -> Vec<(u32, u32)> {
+			let mut supply: Vec<(u32, u32)> = GenerationCounts::<T>::iter().collect();
+			supply.sort_unstable();
+			supply
+		}
+
+		// 按编号返回错误的说明
+		pub fn describe_error(index: u8) -> Option<Vec<u8>> {
+			Self::error_descriptions()
+				.into_iter()
+				.nth(index as usize)
+				.map(|(_, description)| description.as_bytes().to_vec())
+		}
+
+		// 账户拥有的Kitty，最多返回MaxQueryResults个，结果被截断时返回true
+		pub fn kitties_of(owner: &T::AccountId) -> (Vec<T::KittyIndex>, bool) {
+			let max = T::MaxQueryResults::get() as usize;
+			let mut kitties: Vec<T::KittyIndex> =
+				OwnedKitties::<T>::iter_key_prefix(owner).take(max + 1).collect();
+			let truncated = kitties.len() > max;
+			kitties.truncate(max);
+			(kitties, truncated)
+		}
+
+		// 账户持有的Kitty的估值总和，最多计算MaxQueryResults个Kitty，并返回是否被截断
+		// 每个Kitty依次按以下方式估值：原生代币出售价格、最近一次原生代币成交价格，都没有时为0
+		pub fn portfolio_value(owner: &T::AccountId) -> (BalanceOf<T>, bool) {
+			let (kitties, truncated) = Self::kitties_of(owner);
+			let value = kitties.into_iter().fold(Zero::zero(), |total: BalanceOf<T>, kitty_id| {
+				total.saturating_add(Self::estimate_value(kitty_id))
+			});
+			(value, truncated)
+		}
+
+		// Kitty的估值，顺序见portfolio_value
+		fn estimate_value(kitty_id: T::KittyIndex) -> BalanceOf<T> {
+			ListForSale::<T>::get(kitty_id)
+				.filter(|_| !ListingAsset::<T>::contains_key(kitty_id))
+				.or_else(|| LastSalePrice::<T>::get(kitty_id))
+				.unwrap_or_else(Zero::zero)
+		}
+
+		// 批量查询Kitty，结果与输入的ID一一对应，最多查询MaxQueryResults个
+		pub fn kitties_by_ids(ids: Vec<T::KittyIndex>) -> Vec<Option<Kitty>> {
+			ids.into_iter()
+				.take(T::MaxQueryResults::get() as usize)
+				.map(Self::kitties)
+				.collect()
+		}
+
+		// 分页查询账户拥有的Kitty，跳过前start个，每页最多MaxQueryResults个
+		pub fn kitties_of_paged(
+			owner: &T::AccountId,
+			start: u32,
+			limit: u32,
+		) -> Vec<T::KittyIndex> {
+			let limit = limit.min(T::MaxQueryResults::get());
+			OwnedKitties::<T>::iter_key_prefix(owner)
+				.skip(start as usize)
+				.take(limit as usize)
+				.collect()
+		}
+
+		// 清除私人笔记，并向设置者退还押金
+		fn remove_note(kitty_id: T::KittyIndex) {
+			let removed = PrivateNotes::<T>::take(kitty_id).is_some();
+			if let Some((depositor, deposit)) = NoteDeposits::<T>::take(kitty_id) {
+				T::Currency::unreserve(&depositor, deposit);
+			}
+			if removed {
+				Self::deposit_event(Event::NoteCleared(kitty_id));
+			}
+		}
+
+		// Kitty当前的质押金额，没有记录时使用默认质押金额
+		fn stake_of(kitty_id: T::KittyIndex) -> BalanceOf<T> {
+			KittyStake::<T>::get(kitty_id).unwrap_or_else(T::StakeForEachKitty::get)
+		}
+
+		// 新的拥有者需要质押的金额，不包括需求旺盛时提高的部分
+		fn buyer_stake_of(kitty_id: T::KittyIndex) -> BalanceOf<T> {
+			let raise = DemandStakeRaise::<T>::get(kitty_id).unwrap_or_else(Zero::zero);
+			Self::stake_of(kitty_id).saturating_sub(raise)
+		}
+
+		// 拥有者实际质押的金额，即质押要求减去尚未补足的差额
+		fn reserved_stake_of(kitty_id: T::KittyIndex) -> BalanceOf<T> {
+			let shortfall = StakeShortfall::<T>::get(kitty_id)
+				.map(|(shortfall, _)| shortfall)
+				.unwrap_or_else(Zero::zero);
+			Self::stake_of(kitty_id).saturating_sub(shortfall)
+		}
+
+		// 需求旺盛时按StakeForEachKitty的比例提高质押要求，拥有者需要在宽限期内通过top_up_stake补足
+		// 需求结束前只提高一次
+		fn raise_stake_for_demand(owner: &T::AccountId, kitty_id: T::KittyIndex) {
+			if DemandStakeRaise::<T>::contains_key(kitty_id) {
+				return;
+			}
+			let increase = T::HighDemandStakeIncrease::get() * T::StakeForEachKitty::get();
+			if increase.is_zero() {
+				return;
+			}
+			let required = Self::stake_of(kitty_id).saturating_add(increase);
+			let due = <frame_system::Pallet<T>>::block_number()
+				.saturating_add(T::HighDemandGracePeriod::get());
+			KittyStake::<T>::insert(kitty_id, required);
+			StakeShortfall::<T>::insert(kitty_id, (increase, due));
+			DemandStakeRaise::<T>::insert(kitty_id, increase);
+			Self::deposit_event(Event::StakeIncreaseRequired(
+				owner.clone(),
+				kitty_id,
+				required,
+				due,
+			));
+		}
+
+		// 撤销需求旺盛时提高的质押要求，并退还拥有者已补足的部分
+		fn lift_demand_stake(kitty_id: T::KittyIndex) {
+			let increase = match DemandStakeRaise::<T>::take(kitty_id) {
+				Some(increase) => increase,
+				None => return,
+			};
+			let shortfall = StakeShortfall::<T>::take(kitty_id)
+				.map(|(shortfall, _)| shortfall)
+				.unwrap_or_else(Zero::zero);
+			// 放弃时质押记录已经移除，不需要调整
+			let stake = match KittyStake::<T>::get(kitty_id) {
+				Some(stake) => stake.saturating_sub(increase),
+				None => return,
+			};
+			KittyStake::<T>::insert(kitty_id, stake);
+			if let Some(owner) = Owner::<T>::get(kitty_id) {
+				T::Currency::unreserve(&owner, increase.saturating_sub(shortfall));
+			}
+			Self::deposit_event(Event::StakeIncreaseLifted(kitty_id, stake));
+		}
+
+		// 宽限期结束后仍未补足质押的Kitty不能出售
+		fn ensure_stake_sufficient(kitty_id: T::KittyIndex) -> DispatchResult {
+			if let Some((_, due)) = StakeShortfall::<T>::get(kitty_id) {
+				ensure!(
+					<frame_system::Pallet<T>>::block_number() < due,
+					Error::<T>::StakeTopUpRequired
+				);
+			}
+			Ok(())
+		}
+
+		// 创建+质押Kitty
+		pub fn do_mint(
+			owner: &T::AccountId,
+			dna: [u8; 16],
+		) -> Result<T::KittyIndex, DispatchError> {
+			Self::mint_kitty(owner, Kitty { dna, clone: false }, 0)
+		}
+
+		// 创建繁殖的子Kitty，payer支付繁殖材料
+		fn mint_child(
+			owner: &T::AccountId,
+			payer: &T::AccountId,
+			dna: [u8; 16],
+			kitty_id_1: T::KittyIndex,
+			kitty_id_2: T::KittyIndex,
+		) -> Result<T::KittyIndex, DispatchError> {
+			Self::consume_breeding_material(payer)?;
+			let generation = Self::generation_of(kitty_id_1)
+				.max(Self::generation_of(kitty_id_2))
+				.saturating_add(1);
+			// 保底：连续多次未繁殖出稀有Kitty时，本次的子Kitty一定是稀有的
+			let rare = T::RareRarity::get();
+			let threshold = T::PityThreshold::get();
+			let pity = threshold > 0 && Self::pity_counter(owner) >= threshold;
+			let dna = if pity { Self::pity_dna(dna, rare) } else { dna };
+			let kitty = Kitty { dna, clone: false };
+			let is_rare = Self::rarity(&kitty) >= rare;
+			let kitty_id = Self::mint_kitty(owner, kitty, generation)?;
+			// 繁殖出稀有Kitty时重置计数
+			if is_rare {
+				PityCounter::<T>::remove(owner);
+			} else if threshold > 0 {
+				PityCounter::<T>::mutate(owner, |count| *count = count.saturating_add(1));
+			}
+			if pity {
+				Self::deposit_event(Event::PityBreed(owner.clone(), kitty_id));
+			}
+			Ok(kitty_id)
+		}
+
+		// 销毁payer的繁殖材料
+		fn consume_breeding_material(payer: &T::AccountId) -> DispatchResult {
+			let amount = T::BreedingMaterialPerBreed::get();
+			if amount.is_zero() {
+				return Ok(());
+			}
+			let asset = T::BreedingMaterialAsset::get();
+			ensure!(
+				<T::BreedingMaterial as fungibles::Inspect<_>>::reducible_balance(
+					asset, payer, false
+				) >= amount,
+				Error::<T>::NotEnoughBreedingMaterial
 			);
-			payload.using_encoded(blake2_128)
+			T::BreedingMaterial::burn_from(asset, payer, amount)
+				.map_err(|_| Error::<T>::NotEnoughBreedingMaterial)?;
+			Ok(())
 		}
 
-		fn create_kitty_with_stake(owner: &T::AccountId, dna: [u8; 16]) -> DispatchResult {
+		fn mint_kitty(
+			owner: &T::AccountId,
+			kitty: Kitty,
+			generation: u32,
+		) -> Result<T::KittyIndex, DispatchError> {
+			ensure!(!Self::is_supply_sealed(), Error::<T>::SupplySealed);
+			let dna = kitty.dna;
 			// Child Kitty的ID
 			let kitty_id = match Self::kitties_count() {
 				Some(id) => {
 					ensure!(id != T::KittyIndex::max_value(), Error::<T>::KittiesCountOverflow);
 					id
-				}
+				},
 				None => 0u32.into(),
 			};
 			// 获取质押的金额
@@ -225,15 +3844,68 @@ pub mod pallet {
 			// 质押创建者一定的金额
 			T::Currency::reserve(&owner, stake_amount)
 				.map_err(|_| Error::<T>::NotEnoughBalanceForStaking)?;
+			Self::update_rarest(kitty_id, &kitty);
 			// 将Kitty加入Kitties集合
-			Kitties::<T>::insert(kitty_id, Some(Kitty { dna }));
+			Kitties::<T>::insert(kitty_id, Some(kitty));
 			// 为Kitty绑定所有人
-			Owner::<T>::insert(kitty_id, Some(owner.clone()));
+			Self::set_owner(kitty_id, Some(owner.clone()));
+			// 记录Kitty的质押金额
+			KittyStake::<T>::insert(kitty_id, stake_amount);
+			// 记录Kitty的创建者
+			KittyCreator::<T>::insert(kitty_id, owner.clone());
+			// 记录Kitty的创建区块
+			CreatedAt::<T>::insert(kitty_id, <frame_system::Pallet<T>>::block_number());
+			// 记录Kitty的代数
+			KittyGeneration::<T>::insert(kitty_id, generation);
+			GenerationCounts::<T>::mutate(generation, |count| *count = count.saturating_add(1));
 			// 更新下一个Kitty的ID
-			KittiesCount::<T>::put(kitty_id + 1u32.into());
+			let count = kitty_id + 1u32.into();
+			KittiesCount::<T>::put(count);
+			// 数量越过警告线时发出一次警告
+			let threshold = T::SupplyWarningThreshold::get() * T::KittyIndex::max_value();
+			if kitty_id < threshold && count >= threshold {
+				Self::deposit_event(Event::SupplyNearingLimit(count));
+			}
+			// 写入链下索引
+			Self::index_history(kitty_id, HistoryRecord::Mint { owner: owner.clone(), dna });
 			// 发出创建事件
 			Self::deposit_event(Event::KittyCreate(owner.clone(), kitty_id));
-			Ok(())
+			Ok(kitty_id)
+		}
+	}
+
+	impl<T: Config> crate::KittyMutate<T::AccountId> for Pallet<T> {
+		type KittyIndex = T::KittyIndex;
+		type Balance = BalanceOf<T>;
+
+		fn do_mint(owner: &T::AccountId, dna: [u8; 16]) -> Result<Self::KittyIndex, DispatchError> {
+			Self::do_mint(owner, dna)
+		}
+
+		fn do_transfer(
+			from: &T::AccountId,
+			to: &T::AccountId,
+			kitty_id: Self::KittyIndex,
+		) -> DispatchResult {
+			// 调用者传入的from必须是当前拥有者，否则会释放其他账户的保留余额
+			ensure!(Owner::<T>::get(kitty_id).as_ref() == Some(from), Error::<T>::NotOwner);
+			Self::do_transfer(from, to, kitty_id)
+		}
+
+		fn do_settle_sale(
+			buyer: &T::AccountId,
+			seller: &T::AccountId,
+			kitty_id: Self::KittyIndex,
+			price: Self::Balance,
+		) -> DispatchResult {
+			ensure!(Owner::<T>::get(kitty_id).as_ref() == Some(seller), Error::<T>::NotOwner);
+			// 只能按原生代币的挂单价格结算
+			ensure!(
+				ListForSale::<T>::get(kitty_id) == Some(price)
+					&& !ListingAsset::<T>::contains_key(kitty_id),
+				Error::<T>::KittyNotForSell
+			);
+			Self::do_settle_sale(buyer, seller, kitty_id, price)
 		}
 	}
 }
@@ -0,0 +1,44 @@
+// 存储迁移，每个版本只执行一次
+
+use crate::{Config, GenerationCounts, Kitties, Kitty, KittyStake, OwnedKitties, Owner, Pallet};
+use codec::{Decode, Encode};
+use frame_support::{
+	traits::{Get, GetStorageVersion, StorageVersion},
+	weights::Weight,
+};
+
+// 版本1：Kitty增加clone字段，并为升级前的Kitty补齐之后加入的索引
+pub mod v1 {
+	use super::*;
+
+	// 版本0的Kitty只有DNA
+	#[derive(Encode, Decode)]
+	pub struct OldKitty {
+		pub dna: [u8; 16],
+	}
+
+	// 按新格式重写所有Kitty，升级前不存在克隆体
+	// 升级前的Kitty都是第0代，按StakeForEachKitty质押，没有创建者和创建区块记录
+	pub fn migrate<T: Config>() -> Weight {
+		if Pallet::<T>::on_chain_storage_version() >= 1 {
+			return T::DbWeight::get().reads(1);
+		}
+		let mut translated = 0u64;
+		Kitties::<T>::translate::<Option<OldKitty>, _>(|kitty_id, old| {
+			translated += 1;
+			let kitty = Kitty { dna: old?.dna, clone: false };
+			if let Some(owner) = Owner::<T>::get(kitty_id) {
+				OwnedKitties::<T>::insert(&owner, kitty_id, ());
+				if !KittyStake::<T>::contains_key(kitty_id) {
+					KittyStake::<T>::insert(kitty_id, T::StakeForEachKitty::get());
+				}
+			}
+			GenerationCounts::<T>::mutate(0, |count| *count = count.saturating_add(1));
+			Pallet::<T>::update_rarest(kitty_id, &kitty);
+			Some(Some(kitty))
+		});
+		StorageVersion::new(1).put::<Pallet<T>>();
+		T::DbWeight::get()
+			.reads_writes(translated.saturating_mul(5) + 1, translated.saturating_mul(5) + 1)
+	}
+}
@@ -1,10 +1,17 @@
 use crate as pallet_kitties;
-use frame_support::parameter_types;
+use frame_support::{
+	dispatch::{DispatchError, DispatchResult},
+	parameter_types,
+	traits::{OnInitialize, Randomness},
+	PalletId,
+};
 use frame_system as system;
+use frame_system::EnsureRoot;
 use sp_core::H256;
 use sp_runtime::{
-	testing::Header,
+	testing::{Header, TestSignature, UintAuthorityId},
 	traits::{BlakeTwo256, IdentityLookup},
+	Permill,
 };
 
 type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
@@ -19,12 +26,70 @@ frame_support::construct_runtime!(
 		UncheckedExtrinsic = UncheckedExtrinsic,
 	{
 		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
-		SubstrateKitties: pallet_kitties::{Pallet, Call, Storage, Event<T>},
+		SubstrateKitties: pallet_kitties::{Pallet, Call, Storage, Config<T>, Event<T>},
 		Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
 		RandomnessCollectiveFlip: pallet_randomness_collective_flip::{Pallet, Storage},
+		KittyConsumer: pallet_kitty_consumer::{Pallet, Call},
+		Assets: pallet_assets::{Pallet, Call, Storage, Event<T>},
 	}
 );
 
+// 通过KittyMutate接口操作Kitty的模块
+#[frame_support::pallet]
+pub mod pallet_kitty_consumer {
+	use crate::KittyMutate;
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		type Kitties: KittyMutate<Self::AccountId, KittyIndex = u32, Balance = u128>;
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		#[pallet::weight(0)]
+		pub fn mint_for(
+			origin: OriginFor<T>,
+			owner: T::AccountId,
+			dna: [u8; 16],
+		) -> DispatchResult {
+			ensure_root(origin)?;
+			T::Kitties::do_mint(&owner, dna)?;
+			Ok(())
+		}
+
+		#[pallet::weight(0)]
+		pub fn move_kitty(
+			origin: OriginFor<T>,
+			from: T::AccountId,
+			to: T::AccountId,
+			kitty_id: u32,
+		) -> DispatchResult {
+			ensure_root(origin)?;
+			T::Kitties::do_transfer(&from, &to, kitty_id)
+		}
+
+		#[pallet::weight(0)]
+		pub fn settle_sale(
+			origin: OriginFor<T>,
+			buyer: T::AccountId,
+			seller: T::AccountId,
+			kitty_id: u32,
+			price: u128,
+		) -> DispatchResult {
+			ensure_root(origin)?;
+			T::Kitties::do_settle_sale(&buyer, &seller, kitty_id, price)
+		}
+	}
+}
+
 parameter_types! {
 	pub const BlockHashCount: u64 = 250;
 	pub const SS58Prefix: u8 = 42;
@@ -76,15 +141,196 @@ impl pallet_balances::Config for Test {
 impl pallet_randomness_collective_flip::Config for Test {}
 
 parameter_types! {
-	pub const StakeForEachKitty: u128 = 10_000;
+	pub const AssetDeposit: u128 = 1;
+	pub const ApprovalDeposit: u128 = 1;
+	pub const StringLimit: u32 = 50;
+	pub const MetadataDepositBase: u128 = 1;
+	pub const MetadataDepositPerByte: u128 = 1;
+}
+
+impl pallet_assets::Config for Test {
+	type Event = Event;
+	type Balance = Balance;
+	type AssetId = u32;
+	type Currency = Balances;
+	type ForceOrigin = EnsureRoot<u64>;
+	type AssetDeposit = AssetDeposit;
+	type MetadataDepositBase = MetadataDepositBase;
+	type MetadataDepositPerByte = MetadataDepositPerByte;
+	type ApprovalDeposit = ApprovalDeposit;
+	type StringLimit = StringLimit;
+	type Freezer = ();
+	type Extra = ();
+	type WeightInfo = ();
+}
+
+impl pallet_kitty_consumer::Config for Test {
+	type Kitties = SubstrateKitties;
+}
+
+parameter_types! {
+	pub static StakeForEachKitty: u128 = 10_000;
+	pub static ListingDeposit: u128 = 100;
+	pub const PremiumBreedFee: u128 = 1_000;
+	pub static MaxPremiumTier: u8 = 3;
+	pub static EnableOffchainIndexing: bool = false;
+	pub TokenSymbol: Vec<u8> = b"KITTY".to_vec();
+	pub const TokenDecimals: u8 = 12;
+	pub const KittiesPalletId: PalletId = PalletId(*b"py/kitty");
+	pub static MaxRaceEntrants: u32 = 3;
+	pub static RacePrizeSplit: Vec<Permill> =
+		vec![Permill::from_percent(50), Permill::from_percent(30), Permill::from_percent(20)];
+	pub static RoyaltyRate: Permill = Permill::from_percent(10);
+	pub const CloneFee: u128 = 50_000;
+	pub static TradingStartBlock: u64 = 0;
+	pub const FeaturedPeriod: u64 = 10;
+	pub static MarketplaceFee: Permill = Permill::zero();
+	pub static RarityFeeTiers: Vec<(u32, Permill)> = vec![];
+	pub const MaxInstallments: u32 = 12;
+	pub const InstallmentPeriod: u64 = 10;
+	pub const InstallmentPenalty: Permill = Permill::from_percent(10);
+	pub const MaxNoteLength: u32 = 64;
+	pub static NoteDepositPerByte: u128 = 10;
+	pub const MaxQueryResults: u32 = 5;
+	pub static ResaleCooldown: u64 = 0;
+	pub const RecoveryAccount: u64 = 5;
+	pub const SupplyWarningThreshold: Permill = Permill::from_percent(90);
+	pub AllowedAssets: Vec<u32> = vec![1, 2];
+	pub const FeeAssetDestination: u64 = 8;
+	pub static MaxOffersPerKitty: u32 = 3;
+	pub static MaxOffersPerAccount: u32 = 10;
+	pub static MinAgeBeforeBurn: u64 = 0;
+	pub const MaxBundleSize: u32 = 3;
+	pub static HighDemandOfferThreshold: u32 = 0;
+	pub static HighDemandStakeIncrease: Permill = Permill::from_percent(50);
+	pub static HighDemandGracePeriod: u64 = 10;
+	pub const GambleFee: u128 = 1_000;
+	pub static GambleWinChance: Permill = Permill::from_percent(50);
+	pub static MaxScheduledPerBlock: u32 = 10;
+	pub static CreateCooldown: u64 = 0;
+	pub const IncomingTransferExpiry: u64 = 10;
+	pub static RareRarity: u32 = 96;
+	pub static PityThreshold: u32 = 0;
+	pub const MaxBatchSize: u32 = 4;
+	pub static WashTradeCooldown: u64 = 0;
+	pub const MaxRecentOwners: u32 = 2;
+	pub const BreedingMaterialAsset: u32 = 7;
+	pub static BreedingMaterialPerBreed: u128 = 0;
+	pub static MaxAuctionDuration: u64 = 100;
+	pub static MaxFeaturedTallyPerBlock: u32 = 10;
+	pub static MaxOrderBookScan: u32 = 20;
+}
+
+// 与RandomnessCollectiveFlip不同的第二个随机数来源
+pub struct SecondaryBeacon;
+
+impl Randomness<H256, u64> for SecondaryBeacon {
+	fn random(_subject: &[u8]) -> (H256, u64) {
+		(H256::repeat_byte(0x5a), System::block_number())
+	}
+}
+
+thread_local! {
+	// 外部集合中NFT的持有者
+	pub static NFT_HOLDERS: std::cell::RefCell<std::collections::BTreeMap<u32, u64>> =
+		Default::default();
+}
+
+// 测试用的外部NFT集合，NFT的标识与Kitty ID相同
+pub struct MockNftBridge;
+
+impl MockNftBridge {
+	pub fn holder(nft_id: u32) -> Option<u64> {
+		NFT_HOLDERS.with(|holders| holders.borrow().get(&nft_id).cloned())
+	}
+
+	// 在外部集合中转移NFT
+	pub fn transfer(nft_id: u32, to: u64) {
+		NFT_HOLDERS.with(|holders| holders.borrow_mut().insert(nft_id, to));
+	}
+}
+
+impl pallet_kitties::NftBridge<u64, u32> for MockNftBridge {
+	type NftId = u32;
+
+	fn mint(owner: &u64, kitty_id: u32, _dna: [u8; 16]) -> Result<u32, DispatchError> {
+		NFT_HOLDERS.with(|holders| holders.borrow_mut().insert(kitty_id, *owner));
+		Ok(kitty_id)
+	}
+
+	fn burn(holder: &u64, nft_id: &u32) -> DispatchResult {
+		if Self::holder(*nft_id) != Some(*holder) {
+			return Err(DispatchError::Other("not the NFT holder"));
+		}
+		NFT_HOLDERS.with(|holders| holders.borrow_mut().remove(nft_id));
+		Ok(())
+	}
 }
 
 impl pallet_kitties::Config for Test {
 	type Event = Event;
 	type Randomness = RandomnessCollectiveFlip;
+	type SecondaryRandomness = SecondaryBeacon;
 	type KittyIndex = u32;
 	type StakeForEachKitty = StakeForEachKitty;
 	type Currency = Balances;
+	type ListingDeposit = ListingDeposit;
+	type ForceOrigin = EnsureRoot<u64>;
+	type PremiumBreedFee = PremiumBreedFee;
+	type MaxPremiumTier = MaxPremiumTier;
+	type FeeDestination = ();
+	type EnableOffchainIndexing = EnableOffchainIndexing;
+	type TokenSymbol = TokenSymbol;
+	type TokenDecimals = TokenDecimals;
+	type PalletId = KittiesPalletId;
+	type MaxRaceEntrants = MaxRaceEntrants;
+	type RacePrizeSplit = RacePrizeSplit;
+	type RoyaltyRate = RoyaltyRate;
+	type CloneFee = CloneFee;
+	type TradingStartBlock = TradingStartBlock;
+	type FeaturedPeriod = FeaturedPeriod;
+	type MarketplaceFee = MarketplaceFee;
+	type RarityFeeTiers = RarityFeeTiers;
+	type MaxInstallments = MaxInstallments;
+	type InstallmentPeriod = InstallmentPeriod;
+	type InstallmentPenalty = InstallmentPenalty;
+	type MaxNoteLength = MaxNoteLength;
+	type NoteDepositPerByte = NoteDepositPerByte;
+	type MaxQueryResults = MaxQueryResults;
+	type ResaleCooldown = ResaleCooldown;
+	type RecoveryAccount = RecoveryAccount;
+	type SupplyWarningThreshold = SupplyWarningThreshold;
+	type AssetId = u32;
+	type Assets = Assets;
+	type AllowedAssets = AllowedAssets;
+	type FeeAssetDestination = FeeAssetDestination;
+	type MaxOffersPerKitty = MaxOffersPerKitty;
+	type MaxOffersPerAccount = MaxOffersPerAccount;
+	type MinAgeBeforeBurn = MinAgeBeforeBurn;
+	type MaxBundleSize = MaxBundleSize;
+	type HighDemandOfferThreshold = HighDemandOfferThreshold;
+	type HighDemandStakeIncrease = HighDemandStakeIncrease;
+	type HighDemandGracePeriod = HighDemandGracePeriod;
+	type SponsorSignature = TestSignature;
+	type SponsorPublic = UintAuthorityId;
+	type GambleFee = GambleFee;
+	type GambleWinChance = GambleWinChance;
+	type MaxScheduledPerBlock = MaxScheduledPerBlock;
+	type CreateCooldown = CreateCooldown;
+	type IncomingTransferExpiry = IncomingTransferExpiry;
+	type RareRarity = RareRarity;
+	type PityThreshold = PityThreshold;
+	type NftBridge = MockNftBridge;
+	type MaxBatchSize = MaxBatchSize;
+	type WashTradeCooldown = WashTradeCooldown;
+	type MaxRecentOwners = MaxRecentOwners;
+	type BreedingMaterial = Assets;
+	type BreedingMaterialAsset = BreedingMaterialAsset;
+	type BreedingMaterialPerBreed = BreedingMaterialPerBreed;
+	type MaxAuctionDuration = MaxAuctionDuration;
+	type MaxFeaturedTallyPerBlock = MaxFeaturedTallyPerBlock;
+	type MaxOrderBookScan = MaxOrderBookScan;
+	type WeightInfo = ();
 }
 
 #[macro_export]
@@ -94,16 +340,40 @@ macro_rules! assert_has_event {
 	};
 }
 
+// 模块管理员
+pub const ADMIN: u64 = 6;
+
 pub fn new_test_ext() -> sp_io::TestExternalities {
 	let mut storage = system::GenesisConfig::default().build_storage::<Test>().unwrap();
 
 	pallet_balances::GenesisConfig::<Test> {
-		balances: vec![(1, 10_000_000_000), (2, 10_000_000_000), (3, 9_000)],
+		balances: vec![
+			(1, 10_000_000_000),
+			(2, 10_000_000_000),
+			(3, 9_000),
+			(4, 10_000_000_000),
+			// 恢复账户
+			(5, 10_000_000_000),
+			// 模块账户保留存在性押金
+			(SubstrateKitties::account_id(), 500),
+			(SubstrateKitties::proceeds_account_id(), 500),
+		],
 	}
 	.assimilate_storage(&mut storage)
 	.unwrap();
 
+	pallet_kitties::GenesisConfig::<Test> { admin: Some(ADMIN) }
+		.assimilate_storage(&mut storage)
+		.unwrap();
+
 	let mut ext = sp_io::TestExternalities::new(storage);
 	ext.execute_with(|| System::set_block_number(1));
 	ext
 }
+
+pub fn run_to_block(n: u64) {
+	while System::block_number() < n {
+		System::set_block_number(System::block_number() + 1);
+		SubstrateKitties::on_initialize(System::block_number());
+	}
+}
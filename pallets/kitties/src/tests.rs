@@ -1,8 +1,23 @@
 use super::*;
 use crate::mock::{
-	new_test_ext, Event as TestEvent, Origin, SubstrateKitties as Kitties, System, Test,
+	new_test_ext, run_to_block, Assets, Balances, BreedingMaterialAsset, BreedingMaterialPerBreed,
+	CreateCooldown, EnableOffchainIndexing, Event as TestEvent, FeeAssetDestination,
+	GambleWinChance, HighDemandGracePeriod, HighDemandOfferThreshold, HighDemandStakeIncrease,
+	KittyConsumer, ListingDeposit, MarketplaceFee, MaxAuctionDuration, MaxFeaturedTallyPerBlock,
+	MaxOffersPerAccount, MaxOffersPerKitty, MaxOrderBookScan, MaxPremiumTier, MaxRaceEntrants,
+	MaxScheduledPerBlock, MinAgeBeforeBurn, MockNftBridge, NoteDepositPerByte, Origin,
+	PityThreshold, RacePrizeSplit, RandomnessCollectiveFlip, RareRarity, RarityFeeTiers,
+	ResaleCooldown, RoyaltyRate, SecondaryBeacon, StakeForEachKitty, SubstrateKitties as Kitties,
+	System, Test, TradingStartBlock, WashTradeCooldown, ADMIN,
 };
-use frame_support::{assert_noop, assert_ok};
+use codec::{Decode, Encode};
+use frame_support::{
+	assert_noop, assert_ok,
+	traits::{GetStorageVersion, Hooks, Randomness, StorageVersion},
+	BoundedVec,
+};
+use sp_core::offchain::{OffchainStorage, STORAGE_PREFIX};
+use sp_runtime::{testing::TestSignature, traits::BadOrigin, DispatchError, Permill};
 
 #[test]
 fn create_works() {
@@ -251,7 +266,7 @@ fn buy_failed_buyer_is_owner() {
 fn buy_failed_not_for_sell() {
 	new_test_ext().execute_with(|| {
 		let account_id_1: u64 = 1;
-        let account_id_2: u64 = 2;
+		let account_id_2: u64 = 2;
 		let kitty_id = 1u32;
 		// 创建Kitty
 		assert_ok!(Kitties::create(Origin::signed(account_id_1)));
@@ -267,7 +282,7 @@ fn buy_failed_not_for_sell() {
 fn buy_failed_buyer_not_enough_balance_for_buying() {
 	new_test_ext().execute_with(|| {
 		let account_id_1: u64 = 1;
-        let account_id_3: u64 = 3;
+		let account_id_3: u64 = 3;
 		let kitty_id = 1u32;
 		let price: u128 = 100000;
 		// 创建Kitty
@@ -280,4 +295,3551 @@ fn buy_failed_buyer_not_enough_balance_for_buying() {
 			Error::<Test>::NotEnoughBalanceForBuying
 		);
 	});
-}
\ No newline at end of file
+}
+#[test]
+fn kitty_stake_matches_reserved_at_creation() {
+	new_test_ext().execute_with(|| {
+		let account_id: u64 = 1;
+		let kitty_id = 0u32;
+		assert_ok!(Kitties::create(Origin::signed(account_id)));
+		// 质押金额与创建时质押的金额一致
+		assert_eq!(Kitties::kitty_stake(kitty_id), Some(10_000));
+		assert_eq!(Balances::reserved_balance(account_id), 10_000);
+		// 不存在的Kitty没有质押
+		assert_eq!(Kitties::kitty_stake(kitty_id + 1), None);
+	});
+}
+
+#[test]
+fn top_up_stake_works() {
+	new_test_ext().execute_with(|| {
+		let account_id: u64 = 1;
+		let kitty_id = 0u32;
+		assert_ok!(Kitties::create(Origin::signed(account_id)));
+		// 追加质押
+		assert_ok!(Kitties::top_up_stake(Origin::signed(account_id), kitty_id, 500));
+		assert_eq!(Kitties::kitty_stake(kitty_id), Some(10_500));
+		assert_eq!(Balances::reserved_balance(account_id), 10_500);
+		assert_has_event!(Event::<Test>::KittyStakeToppedUp(account_id, kitty_id, 500));
+	});
+}
+
+#[test]
+fn top_up_stake_moves_with_transfer() {
+	new_test_ext().execute_with(|| {
+		let account_id_1: u64 = 1;
+		let account_id_2: u64 = 2;
+		let kitty_id = 0u32;
+		assert_ok!(Kitties::create(Origin::signed(account_id_1)));
+		assert_ok!(Kitties::top_up_stake(Origin::signed(account_id_1), kitty_id, 500));
+		assert_ok!(Kitties::transfer(Origin::signed(account_id_1), account_id_2, kitty_id));
+		// 转让后新的拥有者质押相同金额
+		assert_eq!(Balances::reserved_balance(account_id_1), 0);
+		assert_eq!(Balances::reserved_balance(account_id_2), 10_500);
+	});
+}
+
+#[test]
+fn top_up_stake_failed_not_owner() {
+	new_test_ext().execute_with(|| {
+		let kitty_id = 0u32;
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_noop!(
+			Kitties::top_up_stake(Origin::signed(2), kitty_id, 500),
+			Error::<Test>::NotOwner
+		);
+		assert_noop!(
+			Kitties::top_up_stake(Origin::signed(1), kitty_id, 0),
+			Error::<Test>::ZeroStakeAmount
+		);
+	});
+}
+
+#[test]
+fn sell_reserves_listing_deposit() {
+	new_test_ext().execute_with(|| {
+		let account_id: u64 = 1;
+		let kitty_id = 0u32;
+		assert_ok!(Kitties::create(Origin::signed(account_id)));
+		assert_ok!(Kitties::sell(Origin::signed(account_id), kitty_id, Some(100)));
+		// 挂单时质押押金
+		assert_eq!(Kitties::listing_deposit(kitty_id), Some((account_id, 100)));
+		assert_eq!(Balances::reserved_balance(account_id), 10_100);
+		// 修改价格不会重复质押
+		assert_ok!(Kitties::sell(Origin::signed(account_id), kitty_id, Some(200)));
+		assert_eq!(Balances::reserved_balance(account_id), 10_100);
+	});
+}
+
+#[test]
+fn sell_failed_not_enough_balance_for_listing_deposit() {
+	new_test_ext().execute_with(|| {
+		let account_id: u64 = 1;
+		let kitty_id = 0u32;
+		assert_ok!(Kitties::create(Origin::signed(account_id)));
+		// 将余额转走，只剩下不足以支付押金的金额
+		let free = Balances::free_balance(account_id);
+		assert_ok!(Balances::transfer(Origin::signed(account_id), 2, free - 50));
+		assert_noop!(
+			Kitties::sell(Origin::signed(account_id), kitty_id, Some(100)),
+			Error::<Test>::NotEnoughBalanceForListingDeposit
+		);
+	});
+}
+
+#[test]
+fn delist_refunds_listing_deposit() {
+	new_test_ext().execute_with(|| {
+		let account_id: u64 = 1;
+		let kitty_id = 0u32;
+		assert_ok!(Kitties::create(Origin::signed(account_id)));
+		assert_ok!(Kitties::sell(Origin::signed(account_id), kitty_id, Some(100)));
+		// 取消出售退还押金
+		assert_ok!(Kitties::sell(Origin::signed(account_id), kitty_id, None));
+		assert_eq!(Kitties::listing_deposit(kitty_id), None);
+		assert_eq!(Kitties::kitties_list_for_sales(kitty_id), None);
+		assert_eq!(Balances::reserved_balance(account_id), 10_000);
+	});
+}
+
+#[test]
+fn buy_refunds_listing_deposit() {
+	new_test_ext().execute_with(|| {
+		let seller: u64 = 1;
+		let buyer: u64 = 2;
+		let kitty_id = 0u32;
+		assert_ok!(Kitties::create(Origin::signed(seller)));
+		assert_ok!(Kitties::sell(Origin::signed(seller), kitty_id, Some(1_000)));
+		assert_ok!(Kitties::buy(Origin::signed(buyer), kitty_id));
+		// 售出后退还卖家押金
+		assert_eq!(Kitties::listing_deposit(kitty_id), None);
+		assert_eq!(Balances::reserved_balance(seller), 0);
+		assert_eq!(Balances::reserved_balance(buyer), 10_000);
+	});
+}
+
+#[test]
+fn transfer_refunds_listing_deposit() {
+	new_test_ext().execute_with(|| {
+		let account_id_1: u64 = 1;
+		let account_id_2: u64 = 2;
+		let kitty_id = 0u32;
+		assert_ok!(Kitties::create(Origin::signed(account_id_1)));
+		assert_ok!(Kitties::sell(Origin::signed(account_id_1), kitty_id, Some(1_000)));
+		assert_ok!(Kitties::transfer(Origin::signed(account_id_1), account_id_2, kitty_id));
+		// 转让后取消出售并退还押金
+		assert_eq!(Kitties::kitties_list_for_sales(kitty_id), None);
+		assert_eq!(Kitties::listing_deposit(kitty_id), None);
+		assert_eq!(Balances::reserved_balance(account_id_1), 0);
+	});
+}
+
+#[test]
+fn force_delist_slashes_listing_deposit() {
+	new_test_ext().execute_with(|| {
+		let account_id: u64 = 1;
+		let kitty_id = 0u32;
+		assert_ok!(Kitties::create(Origin::signed(account_id)));
+		assert_ok!(Kitties::sell(Origin::signed(account_id), kitty_id, Some(1_000)));
+		let free = Balances::free_balance(account_id);
+		let issuance = Balances::total_issuance();
+		// 只有治理可以强制下架
+		assert_noop!(Kitties::force_delist(Origin::signed(account_id), kitty_id), BadOrigin);
+		assert_ok!(Kitties::force_delist(Origin::root(), kitty_id));
+		// 押金被罚没，不会退还
+		assert_eq!(Kitties::kitties_list_for_sales(kitty_id), None);
+		assert_eq!(Kitties::listing_deposit(kitty_id), None);
+		assert_eq!(Balances::reserved_balance(account_id), 10_000);
+		assert_eq!(Balances::free_balance(account_id), free);
+		assert_eq!(Balances::total_issuance(), issuance - 100);
+		assert_has_event!(Event::<Test>::KittyForceDelisted(kitty_id, 100));
+	});
+}
+
+#[test]
+fn transfer_if_unsold_transfers_at_deadline() {
+	new_test_ext().execute_with(|| {
+		let owner: u64 = 1;
+		let fallback_owner: u64 = 2;
+		let kitty_id = 0u32;
+		assert_ok!(Kitties::create(Origin::signed(owner)));
+		assert_ok!(Kitties::sell(Origin::signed(owner), kitty_id, Some(1_000)));
+		assert_ok!(Kitties::transfer_if_unsold(Origin::signed(owner), kitty_id, fallback_owner, 5));
+		// 截止前拥有者不变
+		run_to_block(4);
+		assert_eq!(Owner::<Test>::get(kitty_id), Some(owner));
+		// 截止时未售出，转移给备用拥有者
+		run_to_block(5);
+		assert_eq!(Owner::<Test>::get(kitty_id), Some(fallback_owner));
+		assert_eq!(Kitties::kitties_list_for_sales(kitty_id), None);
+		assert_eq!(Kitties::fallback_transfer(kitty_id), None);
+		assert_eq!(Balances::reserved_balance(owner), 0);
+		assert_eq!(Balances::reserved_balance(fallback_owner), 10_000);
+		assert_has_event!(Event::<Test>::KittyTransfer(owner, fallback_owner, kitty_id));
+	});
+}
+
+#[test]
+fn transfer_if_unsold_cancelled_on_sale() {
+	new_test_ext().execute_with(|| {
+		let owner: u64 = 1;
+		let fallback_owner: u64 = 2;
+		let buyer: u64 = 4;
+		let kitty_id = 0u32;
+		assert_ok!(Kitties::create(Origin::signed(owner)));
+		assert_ok!(Kitties::sell(Origin::signed(owner), kitty_id, Some(1_000)));
+		assert_ok!(Kitties::transfer_if_unsold(Origin::signed(owner), kitty_id, fallback_owner, 5));
+		run_to_block(3);
+		assert_ok!(Kitties::buy(Origin::signed(buyer), kitty_id));
+		assert_eq!(Kitties::fallback_transfer(kitty_id), None);
+		// 已售出，截止时不会转移
+		run_to_block(6);
+		assert_eq!(Owner::<Test>::get(kitty_id), Some(buyer));
+	});
+}
+
+#[test]
+fn transfer_if_unsold_failed_not_listed() {
+	new_test_ext().execute_with(|| {
+		let owner: u64 = 1;
+		let kitty_id = 0u32;
+		assert_ok!(Kitties::create(Origin::signed(owner)));
+		assert_noop!(
+			Kitties::transfer_if_unsold(Origin::signed(owner), kitty_id, 2, 5),
+			Error::<Test>::KittyNotForSell
+		);
+		assert_ok!(Kitties::sell(Origin::signed(owner), kitty_id, Some(1_000)));
+		assert_noop!(
+			Kitties::transfer_if_unsold(Origin::signed(owner), kitty_id, 2, 1),
+			Error::<Test>::DeadlineInPast
+		);
+		assert_noop!(
+			Kitties::transfer_if_unsold(Origin::signed(2), kitty_id, 3, 5),
+			Error::<Test>::NotOwner
+		);
+	});
+}
+
+#[test]
+fn check_config_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::check_config());
+	});
+}
+
+#[test]
+fn check_config_failed_stake_below_existential_deposit() {
+	new_test_ext().execute_with(|| {
+		StakeForEachKitty::set(&499);
+		assert_eq!(
+			Kitties::check_config(),
+			Err("StakeForEachKitty must not be below the existential deposit")
+		);
+	});
+}
+
+#[test]
+fn check_config_failed_zero_listing_deposit() {
+	new_test_ext().execute_with(|| {
+		ListingDeposit::set(&0);
+		assert_eq!(Kitties::check_config(), Err("ListingDeposit must not be zero"));
+	});
+}
+
+#[test]
+fn check_config_failed_fees_above_total_price() {
+	new_test_ext().execute_with(|| {
+		// 市场手续费、最高一档的稀有度手续费和版税合计超过100%
+		MarketplaceFee::set(&Permill::from_percent(50));
+		RarityFeeTiers::set(&vec![(10, Permill::from_percent(5)), (20, Permill::from_percent(41))]);
+		assert_eq!(
+			Kitties::check_config(),
+			Err("MarketplaceFee, RarityFeeTiers and RoyaltyRate must not exceed 100% in total")
+		);
+		RarityFeeTiers::set(&vec![(10, Permill::from_percent(40))]);
+		assert_ok!(Kitties::check_config());
+	});
+}
+
+#[test]
+fn check_config_failed_cooldown_longer_than_auction() {
+	new_test_ext().execute_with(|| {
+		ResaleCooldown::set(&101);
+		assert_eq!(
+			Kitties::check_config(),
+			Err("ResaleCooldown must not exceed MaxAuctionDuration")
+		);
+		ResaleCooldown::set(&100);
+		WashTradeCooldown::set(&101);
+		assert_eq!(
+			Kitties::check_config(),
+			Err("WashTradeCooldown must not exceed MaxAuctionDuration")
+		);
+	});
+}
+
+#[test]
+fn check_config_failed_high_demand_settings() {
+	new_test_ext().execute_with(|| {
+		HighDemandOfferThreshold::set(&4);
+		assert_eq!(
+			Kitties::check_config(),
+			Err("HighDemandOfferThreshold must not exceed MaxOffersPerKitty")
+		);
+		HighDemandOfferThreshold::set(&3);
+		HighDemandStakeIncrease::set(&Permill::zero());
+		assert_eq!(
+			Kitties::check_config(),
+			Err("HighDemandStakeIncrease must not be zero when HighDemandOfferThreshold is set")
+		);
+		HighDemandStakeIncrease::set(&Permill::from_percent(50));
+		HighDemandGracePeriod::set(&0);
+		assert_eq!(
+			Kitties::check_config(),
+			Err("HighDemandGracePeriod must not be zero when HighDemandOfferThreshold is set")
+		);
+		// 不提高质押要求时不检查增加比例和宽限期
+		HighDemandOfferThreshold::set(&0);
+		assert_ok!(Kitties::check_config());
+	});
+}
+
+#[test]
+fn check_config_failed_zero_note_deposit_or_certain_gamble() {
+	new_test_ext().execute_with(|| {
+		NoteDepositPerByte::set(&0);
+		assert_eq!(Kitties::check_config(), Err("NoteDepositPerByte must not be zero"));
+		NoteDepositPerByte::set(&10);
+		GambleWinChance::set(&Permill::one());
+		assert_eq!(Kitties::check_config(), Err("GambleWinChance must be below 100%"));
+	});
+}
+
+#[test]
+fn check_config_failed_zero_max_featured_tally() {
+	new_test_ext().execute_with(|| {
+		MaxFeaturedTallyPerBlock::set(&0);
+		assert_eq!(Kitties::check_config(), Err("MaxFeaturedTallyPerBlock must not be zero"));
+	});
+}
+
+#[test]
+fn check_config_failed_order_book_scan_below_query_results() {
+	new_test_ext().execute_with(|| {
+		MaxOrderBookScan::set(&4);
+		assert_eq!(
+			Kitties::check_config(),
+			Err("MaxOrderBookScan must not be below MaxQueryResults")
+		);
+	});
+}
+
+#[test]
+fn check_config_failed_zero_max_auction_duration() {
+	new_test_ext().execute_with(|| {
+		MaxAuctionDuration::set(&0);
+		assert_eq!(Kitties::check_config(), Err("MaxAuctionDuration must not be zero"));
+	});
+}
+
+#[test]
+#[should_panic(expected = "StakeForEachKitty must not be below the existential deposit")]
+fn integrity_test_panics_on_broken_config() {
+	new_test_ext().execute_with(|| {
+		StakeForEachKitty::set(&0);
+		<Kitties as Hooks<u64>>::integrity_test();
+	});
+}
+
+#[test]
+fn breed_premium_charges_tier_scaled_fee() {
+	new_test_ext().execute_with(|| {
+		let account_id: u64 = 1;
+		assert_ok!(Kitties::create(Origin::signed(account_id)));
+		assert_ok!(Kitties::create(Origin::signed(account_id)));
+		let issuance = Balances::total_issuance();
+		// 等级1收取一倍费用
+		assert_ok!(Kitties::breed_premium(Origin::signed(account_id), 0, 1, 1));
+		assert_eq!(Balances::total_issuance(), issuance - 1_000);
+		assert_has_event!(Event::<Test>::KittyBredPremium(account_id, 2, 1, 1_000));
+		// 等级3收取三倍费用
+		assert_ok!(Kitties::breed_premium(Origin::signed(account_id), 0, 1, 3));
+		assert_eq!(Balances::total_issuance(), issuance - 4_000);
+		assert_has_event!(Event::<Test>::KittyBredPremium(account_id, 3, 3, 3_000));
+	});
+}
+
+#[test]
+fn breed_premium_applies_tier_mutation_passes() {
+	new_test_ext().execute_with(|| {
+		let account_id: u64 = 1;
+		assert_ok!(Kitties::create(Origin::signed(account_id)));
+		assert_ok!(Kitties::create(Origin::signed(account_id)));
+		let dna_1 = Kitties::kitties(0).unwrap().dna;
+		let dna_2 = Kitties::kitties(1).unwrap().dna;
+		// 同一区块内随机数固定
+		let selector = Kitties::random_value(&account_id);
+		let mixed = Kitties::mix_dna(&dna_1, &dna_2, &selector);
+		assert_ok!(Kitties::breed_premium(Origin::signed(account_id), 0, 1, 2));
+		let child_dna = Kitties::kitties(2).unwrap().dna;
+		assert_eq!(child_dna, Kitties::mutate_dna(mixed, selector, 2));
+		// 变异轮数不同结果不同
+		assert_ne!(Kitties::mutate_dna(mixed, selector, 1), child_dna);
+		assert_ne!(mixed, child_dna);
+	});
+}
+
+#[test]
+fn breed_premium_failed_invalid_tier() {
+	new_test_ext().execute_with(|| {
+		let account_id: u64 = 1;
+		assert_ok!(Kitties::create(Origin::signed(account_id)));
+		assert_ok!(Kitties::create(Origin::signed(account_id)));
+		assert_noop!(
+			Kitties::breed_premium(Origin::signed(account_id), 0, 1, 0),
+			Error::<Test>::InvalidPremiumTier
+		);
+		assert_noop!(
+			Kitties::breed_premium(Origin::signed(account_id), 0, 1, 4),
+			Error::<Test>::InvalidPremiumTier
+		);
+	});
+}
+
+#[test]
+fn breed_premium_failed_not_enough_balance_for_fee() {
+	new_test_ext().execute_with(|| {
+		let account_id: u64 = 1;
+		assert_ok!(Kitties::create(Origin::signed(account_id)));
+		assert_ok!(Kitties::create(Origin::signed(account_id)));
+		// 余额只够质押，不够支付费用
+		let free = Balances::free_balance(account_id);
+		assert_ok!(Balances::transfer(Origin::signed(account_id), 2, free - 11_000));
+		assert_noop!(
+			Kitties::breed_premium(Origin::signed(account_id), 0, 1, 3),
+			Error::<Test>::NotEnoughBalanceForFee
+		);
+	});
+}
+
+#[test]
+fn check_config_failed_zero_max_premium_tier() {
+	new_test_ext().execute_with(|| {
+		MaxPremiumTier::set(&0);
+		assert_eq!(Kitties::check_config(), Err("MaxPremiumTier must not be zero"));
+	});
+}
+
+#[test]
+fn kitty_mutate_mint_from_consumer_pallet() {
+	new_test_ext().execute_with(|| {
+		let owner: u64 = 1;
+		let dna = [7u8; 16];
+		assert_ok!(KittyConsumer::mint_for(Origin::root(), owner, dna));
+		// 与create相同的事件和质押
+		assert_eq!(Owner::<Test>::get(0), Some(owner));
+		assert_eq!(Kitties::kitties(0).unwrap().dna, dna);
+		assert_eq!(Balances::reserved_balance(owner), 10_000);
+		assert_has_event!(Event::<Test>::KittyCreate(owner, 0));
+	});
+}
+
+#[test]
+fn kitty_mutate_transfer_from_consumer_pallet() {
+	new_test_ext().execute_with(|| {
+		let account_id_1: u64 = 1;
+		let account_id_2: u64 = 2;
+		assert_ok!(KittyConsumer::mint_for(Origin::root(), account_id_1, [7u8; 16]));
+		assert_ok!(KittyConsumer::move_kitty(Origin::root(), account_id_1, account_id_2, 0));
+		assert_eq!(Owner::<Test>::get(0), Some(account_id_2));
+		assert_eq!(Balances::reserved_balance(account_id_1), 0);
+		assert_eq!(Balances::reserved_balance(account_id_2), 10_000);
+		assert_has_event!(Event::KittyTransfer(account_id_1, account_id_2, 0));
+		// 新的拥有者质押不足
+		assert_noop!(
+			KittyConsumer::move_kitty(Origin::root(), account_id_2, 5, 0),
+			Error::<Test>::NotEnoughBalanceForStaking
+		);
+	});
+}
+
+#[test]
+fn kitty_mutate_settle_sale_from_consumer_pallet() {
+	new_test_ext().execute_with(|| {
+		let seller: u64 = 1;
+		let buyer: u64 = 2;
+		let price: u128 = 1_000;
+		assert_ok!(KittyConsumer::mint_for(Origin::root(), seller, [7u8; 16]));
+		let seller_free = Balances::free_balance(seller);
+		let buyer_free = Balances::free_balance(buyer);
+		// 未挂单或价格不符时不能结算
+		assert_noop!(
+			KittyConsumer::settle_sale(Origin::root(), buyer, seller, 0, price),
+			Error::<Test>::KittyNotForSell
+		);
+		assert_ok!(Kitties::sell(Origin::signed(seller), 0, Some(price)));
+		assert_noop!(
+			KittyConsumer::settle_sale(Origin::root(), buyer, seller, 0, price - 1),
+			Error::<Test>::KittyNotForSell
+		);
+		assert_ok!(KittyConsumer::settle_sale(Origin::root(), buyer, seller, 0, price));
+		assert_eq!(Owner::<Test>::get(0), Some(buyer));
+		assert_eq!(Balances::free_balance(seller), seller_free + 10_000);
+		assert_eq!(Kitties::proceeds(seller), price);
+		assert_eq!(Balances::free_balance(buyer), buyer_free - price - 10_000);
+		assert_has_event!(Event::KittySold(buyer, seller, 0));
+	});
+}
+
+#[test]
+fn kitty_mutate_rejects_non_owner() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittyConsumer::mint_for(Origin::root(), 1, [7u8; 16]));
+		// 账户2有其他Kitty的质押，不能被错误释放
+		assert_ok!(Kitties::create(Origin::signed(2)));
+		assert_ok!(Kitties::sell(Origin::signed(1), 0, Some(1_000)));
+		assert_noop!(KittyConsumer::move_kitty(Origin::root(), 2, 4, 0), Error::<Test>::NotOwner);
+		assert_noop!(
+			KittyConsumer::settle_sale(Origin::root(), 4, 2, 0, 1_000),
+			Error::<Test>::NotOwner
+		);
+		assert_eq!(Balances::reserved_balance(1), 10_100);
+		assert_eq!(Balances::reserved_balance(2), 10_000);
+		assert_eq!(Owner::<Test>::get(0), Some(1));
+	});
+}
+
+#[test]
+fn abandon_refunds_stake_and_leaves_orphan() {
+	new_test_ext().execute_with(|| {
+		let owner: u64 = 1;
+		let kitty_id = 0u32;
+		assert_ok!(Kitties::create(Origin::signed(owner)));
+		assert_ok!(Kitties::top_up_stake(Origin::signed(owner), kitty_id, 500));
+		assert_ok!(Kitties::sell(Origin::signed(owner), kitty_id, Some(1_000)));
+		assert_ok!(Kitties::abandon(Origin::signed(owner), kitty_id));
+		// 质押和挂单押金全部退还
+		assert_eq!(Balances::reserved_balance(owner), 0);
+		assert_eq!(Kitties::kitty_stake(kitty_id), None);
+		assert_eq!(Kitties::kitties_list_for_sales(kitty_id), None);
+		// Kitty仍然存在但没有拥有者
+		assert!(Kitties::kitties(kitty_id).is_some());
+		assert_eq!(Owner::<Test>::get(kitty_id), None);
+		assert_has_event!(Event::<Test>::KittyAbandoned(owner, kitty_id));
+	});
+}
+
+#[test]
+fn abandoned_kitty_can_be_adopted() {
+	new_test_ext().execute_with(|| {
+		let owner: u64 = 1;
+		let adopter: u64 = 2;
+		let kitty_id = 0u32;
+		assert_ok!(Kitties::create(Origin::signed(owner)));
+		// 有主的Kitty不能被领养
+		assert_noop!(
+			Kitties::adopt(Origin::signed(adopter), kitty_id),
+			Error::<Test>::KittyNotOrphaned
+		);
+		assert_ok!(Kitties::abandon(Origin::signed(owner), kitty_id));
+		assert_ok!(Kitties::adopt(Origin::signed(adopter), kitty_id));
+		assert_eq!(Owner::<Test>::get(kitty_id), Some(adopter));
+		assert_eq!(Balances::reserved_balance(adopter), 10_000);
+		assert_eq!(Kitties::kitty_stake(kitty_id), Some(10_000));
+		assert_has_event!(Event::<Test>::KittyAdopted(adopter, kitty_id));
+	});
+}
+
+#[test]
+fn abandon_failed_not_owner() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_noop!(Kitties::abandon(Origin::signed(2), 0), Error::<Test>::NotOwner);
+		assert_noop!(Kitties::adopt(Origin::signed(2), 1), Error::<Test>::InvalidKittyIndex);
+	});
+}
+
+#[test]
+fn history_key_construction() {
+	let key = Kitties::history_key(3, 1);
+	let mut expected = b"kitties/history/".to_vec();
+	expected.extend(3u32.encode());
+	expected.push(b'/');
+	expected.extend(1u32.encode());
+	assert_eq!(key, expected);
+	// 不同的序号得到不同的键
+	assert_ne!(Kitties::history_key(3, 1), Kitties::history_key(3, 2));
+	assert_ne!(Kitties::history_key(3, 1), Kitties::history_key(4, 1));
+}
+
+#[test]
+fn offchain_index_records_lifecycle() {
+	let mut ext = new_test_ext();
+	let dna = ext.execute_with(|| {
+		EnableOffchainIndexing::set(&true);
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::sell(Origin::signed(1), 0, Some(1_000)));
+		assert_ok!(Kitties::buy(Origin::signed(2), 0));
+		assert_ok!(Kitties::transfer(Origin::signed(2), 4, 0));
+		assert_eq!(Kitties::history_length(0), 3);
+		Kitties::kitties(0).unwrap().dna
+	});
+	ext.persist_offchain_overlay();
+	let db = ext.offchain_db();
+	let record = |seq| {
+		db.get(STORAGE_PREFIX, &Kitties::history_key(0, seq))
+			.map(|v| HistoryRecord::<u64, u128>::decode(&mut &v[..]).unwrap())
+	};
+	assert_eq!(record(0), Some(HistoryRecord::Mint { owner: 1, dna }));
+	assert_eq!(record(1), Some(HistoryRecord::Sale { seller: 1, buyer: 2, price: 1_000 }));
+	assert_eq!(record(2), Some(HistoryRecord::Transfer { from: 2, to: 4 }));
+	assert_eq!(record(3), None);
+}
+
+#[test]
+fn offchain_index_records_abandon_adopt_and_burn() {
+	let mut ext = new_test_ext();
+	ext.execute_with(|| {
+		EnableOffchainIndexing::set(&true);
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::abandon(Origin::signed(1), 0));
+		assert_ok!(Kitties::adopt(Origin::signed(2), 0));
+		assert_ok!(Kitties::burn(Origin::signed(2), 0));
+		assert_eq!(Kitties::history_length(0), 4);
+	});
+	ext.persist_offchain_overlay();
+	let db = ext.offchain_db();
+	let record = |seq| {
+		db.get(STORAGE_PREFIX, &Kitties::history_key(0, seq))
+			.map(|v| HistoryRecord::<u64, u128>::decode(&mut &v[..]).unwrap())
+	};
+	assert_eq!(record(1), Some(HistoryRecord::Abandon { owner: 1 }));
+	assert_eq!(record(2), Some(HistoryRecord::Adopt { owner: 2 }));
+	assert_eq!(record(3), Some(HistoryRecord::Burn { owner: 2 }));
+}
+
+#[test]
+fn offchain_index_disabled_writes_nothing() {
+	let mut ext = new_test_ext();
+	ext.execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_eq!(Kitties::history_length(0), 0);
+	});
+	ext.persist_offchain_overlay();
+	assert_eq!(ext.offchain_db().get(STORAGE_PREFIX, &Kitties::history_key(0, 0)), None);
+}
+
+#[test]
+fn price_info_matches_config() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(
+			Kitties::price_info(),
+			PriceInfo {
+				token_symbol: b"KITTY".to_vec(),
+				decimals: 12,
+				existential_deposit: 500,
+				stake_per_kitty: 10_000,
+			}
+		);
+	});
+}
+
+// 创建DNA固定的Kitty用于比赛
+fn mint_racers() {
+	assert_ok!(KittyConsumer::mint_for(Origin::root(), 1, [255u8; 16]));
+	assert_ok!(KittyConsumer::mint_for(Origin::root(), 2, [100u8; 16]));
+	assert_ok!(KittyConsumer::mint_for(Origin::root(), 4, [0u8; 16]));
+}
+
+#[test]
+fn race_ranks_entrants_and_distributes_pot() {
+	new_test_ext().execute_with(|| {
+		mint_racers();
+		assert_ok!(Kitties::create_race(Origin::root(), 1_001, 5));
+		assert_has_event!(Event::<Test>::RaceCreated(0, 1_001, 5));
+		// 报名顺序与速度排名相反
+		assert_ok!(Kitties::enter_race(Origin::signed(4), 0, 2));
+		assert_ok!(Kitties::enter_race(Origin::signed(2), 0, 1));
+		assert_ok!(Kitties::enter_race(Origin::signed(1), 0, 0));
+		assert_eq!(Kitties::races(0).unwrap().pot, 3_003);
+		let free = |who| Balances::free_balance(who);
+		let (free_1, free_2, free_4) = (free(1), free(2), free(4));
+		run_to_block(5);
+		// 50%/30%/20%分配，取整余数归第一名
+		assert_eq!(free(1), free_1 + 1_503);
+		assert_eq!(free(2), free_2 + 900);
+		assert_eq!(free(4), free_4 + 600);
+		assert_has_event!(Event::<Test>::RaceResolved(
+			0,
+			vec![(1, 0, 1_503), (2, 1, 900), (4, 2, 600)]
+		));
+		assert_eq!(Kitties::races(0), None);
+		assert_eq!(Balances::free_balance(Kitties::account_id()), 500);
+	});
+}
+
+#[test]
+fn race_locks_kitty_until_resolved() {
+	new_test_ext().execute_with(|| {
+		mint_racers();
+		assert_ok!(Kitties::create_race(Origin::root(), 1_000, 5));
+		assert_ok!(Kitties::enter_race(Origin::signed(1), 0, 0));
+		assert_eq!(Kitties::kitty_lock(0), Some(KittyLock::Race(0)));
+		// 比赛期间不能交易
+		assert_noop!(Kitties::transfer(Origin::signed(1), 2, 0), Error::<Test>::KittyLocked);
+		assert_noop!(Kitties::sell(Origin::signed(1), 0, Some(100)), Error::<Test>::KittyLocked);
+		run_to_block(5);
+		// 比赛结束后解锁
+		assert_eq!(Kitties::kitty_lock(0), None);
+		assert_ok!(Kitties::transfer(Origin::signed(1), 2, 0));
+	});
+}
+
+#[test]
+fn race_withdraw_entry_refunds_fee() {
+	new_test_ext().execute_with(|| {
+		mint_racers();
+		assert_ok!(Kitties::create_race(Origin::root(), 1_000, 5));
+		let free = Balances::free_balance(1);
+		assert_ok!(Kitties::enter_race(Origin::signed(1), 0, 0));
+		assert_eq!(Balances::free_balance(1), free - 1_000);
+		assert_noop!(
+			Kitties::withdraw_entry(Origin::signed(2), 0, 0),
+			Error::<Test>::NotRaceEntrant
+		);
+		assert_ok!(Kitties::withdraw_entry(Origin::signed(1), 0, 0));
+		assert_eq!(Balances::free_balance(1), free);
+		assert_eq!(Kitties::races(0).unwrap().pot, 0);
+		assert_eq!(Kitties::kitty_lock(0), None);
+		// 比赛开始后不能退出
+		assert_ok!(Kitties::enter_race(Origin::signed(1), 0, 0));
+		run_to_block(5);
+		assert_noop!(
+			Kitties::withdraw_entry(Origin::signed(1), 0, 0),
+			Error::<Test>::InvalidRaceId
+		);
+	});
+}
+
+#[test]
+fn race_entry_failed() {
+	new_test_ext().execute_with(|| {
+		mint_racers();
+		assert_ok!(KittyConsumer::mint_for(Origin::root(), 1, [1u8; 16]));
+		assert_noop!(Kitties::create_race(Origin::signed(1), 1_000, 5), BadOrigin);
+		assert_ok!(Kitties::create_race(Origin::root(), 1_000, 5));
+		assert_noop!(Kitties::enter_race(Origin::signed(1), 1, 0), Error::<Test>::InvalidRaceId);
+		assert_noop!(Kitties::enter_race(Origin::signed(2), 0, 0), Error::<Test>::NotOwner);
+		assert_ok!(Kitties::enter_race(Origin::signed(1), 0, 0));
+		assert_noop!(Kitties::enter_race(Origin::signed(1), 0, 0), Error::<Test>::KittyLocked);
+		assert_ok!(Kitties::enter_race(Origin::signed(2), 0, 1));
+		assert_ok!(Kitties::enter_race(Origin::signed(4), 0, 2));
+		// 参赛数量达到上限
+		assert_noop!(
+			Kitties::enter_race(Origin::signed(1), 0, 3),
+			Error::<Test>::TooManyRaceEntrants
+		);
+		run_to_block(5);
+		assert_ok!(Kitties::create_race(Origin::root(), 1_000, 8));
+		run_to_block(8);
+		assert_noop!(Kitties::enter_race(Origin::signed(1), 1, 0), Error::<Test>::InvalidRaceId);
+	});
+}
+
+#[test]
+fn check_config_failed_race_prize_split() {
+	new_test_ext().execute_with(|| {
+		RacePrizeSplit::set(&vec![Permill::from_percent(60), Permill::from_percent(50)]);
+		assert_eq!(Kitties::check_config(), Err("RacePrizeSplit must not exceed 100%"));
+		RacePrizeSplit::set(&vec![]);
+		assert_eq!(Kitties::check_config(), Err("RacePrizeSplit must not be empty"));
+		RacePrizeSplit::set(&vec![Permill::from_percent(100)]);
+		MaxRaceEntrants::set(&0);
+		assert_eq!(Kitties::check_config(), Err("MaxRaceEntrants must not be zero"));
+	});
+}
+
+#[test]
+fn royalties_accumulate_and_claim_in_bulk() {
+	new_test_ext().execute_with(|| {
+		let creator: u64 = 1;
+		assert_ok!(Kitties::create(Origin::signed(creator)));
+		assert_eq!(Kitties::creator(0), Some(creator));
+		// 创建者自己出售没有版税
+		assert_ok!(Kitties::sell(Origin::signed(creator), 0, Some(1_000)));
+		assert_ok!(Kitties::buy(Origin::signed(2), 0));
+		assert_eq!(Kitties::pending_royalties(creator), 0);
+		// 之后每次交易创建者获得10%版税
+		let seller_free = Balances::free_balance(2);
+		assert_ok!(Kitties::sell(Origin::signed(2), 0, Some(2_000)));
+		assert_ok!(Kitties::buy(Origin::signed(4), 0));
+		assert_eq!(Balances::free_balance(2), seller_free + 10_000);
+		assert_eq!(Kitties::proceeds(2), 1_800);
+		assert_has_event!(Event::<Test>::RoyaltyAccrued(creator, 0, 200));
+		assert_ok!(Kitties::sell(Origin::signed(4), 0, Some(3_000)));
+		assert_ok!(Kitties::buy(Origin::signed(2), 0));
+		assert_eq!(Kitties::pending_royalties(creator), 500);
+		// 一次领取全部版税
+		let creator_free = Balances::free_balance(creator);
+		assert_ok!(Kitties::claim_royalties(Origin::signed(creator)));
+		assert_eq!(Balances::free_balance(creator), creator_free + 500);
+		assert_eq!(Kitties::pending_royalties(creator), 0);
+		assert_has_event!(Event::<Test>::RoyaltiesClaimed(creator, 500));
+		assert_noop!(
+			Kitties::claim_royalties(Origin::signed(creator)),
+			Error::<Test>::NoPendingRoyalties
+		);
+	});
+}
+
+#[test]
+fn clone_kitty_charges_fee_and_records_provenance() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		let free = Balances::free_balance(1);
+		let reserved = Balances::reserved_balance(1);
+		assert_ok!(Kitties::clone_kitty(Origin::signed(1), 0));
+		// 除正常质押外还收取克隆费用
+		assert_eq!(Balances::reserved_balance(1), reserved + StakeForEachKitty::get());
+		assert_eq!(Balances::free_balance(1), free - StakeForEachKitty::get() - 50_000);
+		assert_has_event!(Event::<Test>::KittyCloned(1, 0, 1, 50_000));
+		// 克隆体与原Kitty的DNA相同，并记录来源
+		let original = Kitties::kitties(0).unwrap();
+		let clone = Kitties::kitties(1).unwrap();
+		assert_eq!(clone.dna, original.dna);
+		assert!(clone.clone);
+		assert!(!original.clone);
+		assert_eq!(Kitties::cloned_from(1), Some(0));
+		assert_eq!(Kitties::cloned_from(0), None);
+		assert_eq!(Kitties::owner(1), Some(1));
+	});
+}
+
+#[test]
+fn migration_v1_adds_clone_flag() {
+	new_test_ext().execute_with(|| {
+		// 版本0只有Kitties、Owner和KittiesCount
+		for (kitty_id, dna) in [(0u32, [7u8; 16]), (1, [0xff; 16])] {
+			frame_support::storage::unhashed::put(
+				&crate::Kitties::<Test>::hashed_key_for(kitty_id),
+				&Some(crate::migrations::v1::OldKitty { dna }),
+			);
+			Owner::<Test>::insert(kitty_id, Some(1));
+		}
+		KittiesCount::<Test>::put(2);
+		StorageVersion::new(0).put::<Kitties>();
+		Kitties::on_runtime_upgrade();
+		assert_eq!(Kitties::kitties(0), Some(Kitty { dna: [7; 16], clone: false }));
+		assert_eq!(Kitties::on_chain_storage_version(), StorageVersion::new(1));
+		// 补齐升级前Kitty的索引
+		let (mut owned, truncated) = Kitties::kitties_of(&1);
+		owned.sort_unstable();
+		assert_eq!((owned, truncated), (vec![0, 1], false));
+		assert_eq!(Kitties::kitty_stake(0), Some(10_000));
+		assert_eq!(Kitties::supply_by_generation(), vec![(0, 2)]);
+		assert_eq!(Kitties::rarest_kitty(), Some((1, 128)));
+		// 已迁移的数据不会再次转换
+		Kitties::on_runtime_upgrade();
+		assert_eq!(Kitties::kitties(0), Some(Kitty { dna: [7; 16], clone: false }));
+		assert_eq!(Kitties::supply_by_generation(), vec![(0, 2)]);
+	});
+}
+
+#[test]
+fn clone_kitty_requires_owner_and_fee() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_noop!(Kitties::clone_kitty(Origin::signed(2), 0), Error::<Test>::NotOwner);
+		assert_noop!(Kitties::clone_kitty(Origin::signed(1), 9), Error::<Test>::NotOwner);
+		// 余额只够质押，不够支付克隆费用
+		let free = Balances::free_balance(1);
+		assert_ok!(Balances::transfer(Origin::signed(1), 2, free - 20_000));
+		assert_noop!(
+			Kitties::clone_kitty(Origin::signed(1), 0),
+			Error::<Test>::NotEnoughBalanceForFee
+		);
+		assert_eq!(Kitties::kitties_count(), Some(1));
+	});
+}
+
+#[test]
+fn clone_cannot_breed() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::clone_kitty(Origin::signed(1), 0));
+		assert_noop!(Kitties::breed(Origin::signed(1), 0, 2), Error::<Test>::CloneCannotBreed);
+		assert_noop!(Kitties::breed(Origin::signed(1), 2, 1), Error::<Test>::CloneCannotBreed);
+		assert_noop!(
+			Kitties::breed_premium(Origin::signed(1), 1, 2, 1),
+			Error::<Test>::CloneCannotBreed
+		);
+		assert_ok!(Kitties::breed(Origin::signed(1), 0, 1));
+	});
+}
+
+#[test]
+fn clones_share_dna_with_original() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		// 克隆体单独记录来源，允许与原Kitty及其他克隆体的DNA重复
+		assert_ok!(Kitties::clone_kitty(Origin::signed(1), 0));
+		assert_ok!(Kitties::clone_kitty(Origin::signed(1), 1));
+		let dna = Kitties::kitties(0).unwrap().dna;
+		assert_eq!(Kitties::kitties(1).unwrap().dna, dna);
+		assert_eq!(Kitties::kitties(2).unwrap().dna, dna);
+		assert_eq!(Kitties::cloned_from(2), Some(1));
+	});
+}
+
+#[test]
+fn trading_fails_before_start_block() {
+	new_test_ext().execute_with(|| {
+		TradingStartBlock::set(10);
+		// 开放交易前仍然可以创建
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_noop!(
+			Kitties::sell(Origin::signed(1), 0, Some(1_000)),
+			Error::<Test>::TradingNotStarted
+		);
+		assert_noop!(Kitties::buy(Origin::signed(2), 0), Error::<Test>::TradingNotStarted);
+		run_to_block(9);
+		assert_noop!(
+			Kitties::sell(Origin::signed(1), 0, Some(1_000)),
+			Error::<Test>::TradingNotStarted
+		);
+	});
+}
+
+#[test]
+fn trading_works_from_start_block() {
+	new_test_ext().execute_with(|| {
+		TradingStartBlock::set(10);
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		run_to_block(10);
+		assert_ok!(Kitties::sell(Origin::signed(1), 0, Some(1_000)));
+		assert_ok!(Kitties::buy(Origin::signed(2), 0));
+		assert_eq!(Kitties::owner(0), Some(2));
+	});
+}
+
+// 收入账户的余额扣除存在性押金后不少于所有待领取的金额
+fn assert_proceeds_solvent(accounts: &[u64]) {
+	let liabilities: u128 = accounts
+		.iter()
+		.map(|who| Kitties::proceeds(who) + Kitties::pending_royalties(who))
+		.sum();
+	assert!(Balances::free_balance(Kitties::proceeds_account_id()) >= liabilities + 500);
+}
+
+#[test]
+fn buy_credits_proceeds_instead_of_paying_seller() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::sell(Origin::signed(1), 0, Some(1_000)));
+		let seller_free = Balances::free_balance(1);
+		assert_ok!(Kitties::buy(Origin::signed(2), 0));
+		// 卖家只拿回质押，货款记入待领取收入
+		assert_eq!(Balances::free_balance(1), seller_free + 10_000);
+		assert_eq!(Kitties::proceeds(1), 1_000);
+		assert_eq!(Balances::free_balance(Kitties::proceeds_account_id()), 500 + 1_000);
+		assert_has_event!(Event::<Test>::ProceedsCredited(1, 0, 1_000));
+		assert_proceeds_solvent(&[1, 2, 4]);
+	});
+}
+
+#[test]
+fn proceeds_accumulate_over_multiple_sales() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::sell(Origin::signed(1), 0, Some(1_000)));
+		assert_ok!(Kitties::sell(Origin::signed(1), 1, Some(2_000)));
+		assert_ok!(Kitties::buy(Origin::signed(2), 0));
+		assert_ok!(Kitties::buy(Origin::signed(4), 1));
+		assert_eq!(Kitties::proceeds(1), 3_000);
+		// 转售时卖家获得扣除版税后的收入
+		assert_ok!(Kitties::sell(Origin::signed(2), 0, Some(5_000)));
+		assert_ok!(Kitties::buy(Origin::signed(4), 0));
+		assert_eq!(Kitties::proceeds(2), 4_500);
+		assert_eq!(Kitties::pending_royalties(1), 500);
+		assert_proceeds_solvent(&[1, 2, 4]);
+		// 一次领取全部收入
+		let free = Balances::free_balance(1);
+		assert_ok!(Kitties::withdraw_proceeds(Origin::signed(1)));
+		assert_eq!(Balances::free_balance(1), free + 3_000);
+		assert_eq!(Kitties::proceeds(1), 0);
+		assert_has_event!(Event::<Test>::ProceedsWithdrawn(1, 3_000));
+		assert_noop!(Kitties::withdraw_proceeds(Origin::signed(1)), Error::<Test>::NoProceeds);
+		assert_proceeds_solvent(&[1, 2, 4]);
+	});
+}
+
+#[test]
+fn proceeds_can_be_claimed_in_any_order() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::sell(Origin::signed(1), 0, Some(1_000)));
+		assert_ok!(Kitties::buy(Origin::signed(2), 0));
+		assert_ok!(Kitties::sell(Origin::signed(2), 0, Some(2_000)));
+		assert_ok!(Kitties::buy(Origin::signed(4), 0));
+		// 转售者先领取，随后创建者领取版税和首次出售的收入
+		assert_ok!(Kitties::withdraw_proceeds(Origin::signed(2)));
+		assert_proceeds_solvent(&[1, 2, 4]);
+		assert_ok!(Kitties::claim_royalties(Origin::signed(1)));
+		assert_proceeds_solvent(&[1, 2, 4]);
+		// 之后的交易继续累计
+		assert_ok!(Kitties::sell(Origin::signed(4), 0, Some(3_000)));
+		assert_ok!(Kitties::buy(Origin::signed(2), 0));
+		assert_ok!(Kitties::withdraw_proceeds(Origin::signed(1)));
+		assert_ok!(Kitties::withdraw_proceeds(Origin::signed(4)));
+		assert_ok!(Kitties::claim_royalties(Origin::signed(1)));
+		assert_eq!(Kitties::proceeds(4), 0);
+		assert_eq!(Kitties::pending_royalties(1), 0);
+		// 全部领取后收入账户只剩存在性押金
+		assert_eq!(Balances::free_balance(Kitties::proceeds_account_id()), 500);
+	});
+}
+
+#[test]
+fn vote_featured_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::vote_featured(Origin::signed(2), 0));
+		assert_eq!(Kitties::featured_votes(0, 0), 1);
+		assert_eq!(Kitties::featured_vote_of(0, 2), Some(0));
+		assert_has_event!(Event::<Test>::FeaturedVoted(2, 0, 0));
+		assert_noop!(
+			Kitties::vote_featured(Origin::signed(2), 9),
+			Error::<Test>::InvalidKittyIndex
+		);
+	});
+}
+
+#[test]
+fn vote_featured_once_per_period() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::vote_featured(Origin::signed(2), 0));
+		// 同一周期内不能再次投票，也不能改投
+		assert_noop!(
+			Kitties::vote_featured(Origin::signed(2), 0),
+			Error::<Test>::AlreadyVotedThisPeriod
+		);
+		assert_noop!(
+			Kitties::vote_featured(Origin::signed(2), 1),
+			Error::<Test>::AlreadyVotedThisPeriod
+		);
+		// 新周期可以重新投票
+		run_to_block(10);
+		assert_ok!(Kitties::vote_featured(Origin::signed(2), 1));
+		assert_eq!(Kitties::featured_votes(1, 1), 1);
+	});
+}
+
+#[test]
+fn featured_kitty_crowned_each_period() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::vote_featured(Origin::signed(1), 0));
+		assert_ok!(Kitties::vote_featured(Origin::signed(2), 1));
+		assert_ok!(Kitties::vote_featured(Origin::signed(4), 1));
+		run_to_block(9);
+		assert_eq!(Kitties::featured_kitty(), None);
+		// 周期结束时票数最多的Kitty获胜
+		run_to_block(10);
+		assert_eq!(Kitties::featured_kitty(), Some(1));
+		assert_has_event!(Event::<Test>::KittyFeatured(0, 1, 2));
+		// 统计数据已清理
+		assert_eq!(Kitties::featured_votes(0, 1), 0);
+		assert_eq!(Kitties::featured_vote_of(0, 2), None);
+		// 票数相同时ID较小的获胜
+		assert_ok!(Kitties::vote_featured(Origin::signed(2), 1));
+		assert_ok!(Kitties::vote_featured(Origin::signed(4), 0));
+		run_to_block(20);
+		assert_eq!(Kitties::featured_kitty(), Some(0));
+		// 没有投票的周期保留上一次的结果
+		run_to_block(30);
+		assert_eq!(Kitties::featured_kitty(), Some(0));
+	});
+}
+
+#[test]
+fn featured_tally_continues_over_blocks() {
+	new_test_ext().execute_with(|| {
+		MaxFeaturedTallyPerBlock::set(&2);
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::vote_featured(Origin::signed(1), 0));
+		assert_ok!(Kitties::vote_featured(Origin::signed(2), 1));
+		assert_ok!(Kitties::vote_featured(Origin::signed(4), 1));
+		// 3条投票者记录和2条票数记录需要3个区块
+		run_to_block(10);
+		assert_eq!(Kitties::featured_tally(), Some((0, None)));
+		assert_eq!(Kitties::featured_kitty(), None);
+		run_to_block(11);
+		assert_eq!(Kitties::featured_vote_of(0, 4), None);
+		assert!(Kitties::featured_tally().is_some());
+		assert_eq!(Kitties::featured_kitty(), None);
+		// 新周期的投票不受影响
+		assert_ok!(Kitties::vote_featured(Origin::signed(2), 0));
+		run_to_block(12);
+		assert_eq!(Kitties::featured_tally(), None);
+		assert_eq!(Kitties::featured_kitty(), Some(1));
+		assert_has_event!(Event::<Test>::KittyFeatured(0, 1, 2));
+		assert_eq!(Kitties::featured_votes(0, 1), 0);
+		assert_eq!(Kitties::featured_votes(1, 0), 1);
+		run_to_block(20);
+		assert_eq!(Kitties::featured_kitty(), Some(0));
+	});
+}
+
+// 购买Kitty并检查实际转移的金额与报价一致
+fn assert_buy_matches_quote(buyer: u64, kitty_id: u32) -> BuyQuote<u128> {
+	let quote = Kitties::quote_buy(kitty_id, &buyer).unwrap();
+	let seller = Kitties::owner(kitty_id).unwrap();
+	let creator = Kitties::creator(kitty_id).unwrap();
+	let buyer_free = Balances::free_balance(buyer);
+	let buyer_reserved = Balances::reserved_balance(buyer);
+	let proceeds = Kitties::proceeds(seller);
+	let royalties = Kitties::pending_royalties(creator);
+	let issuance = Balances::total_issuance();
+	assert_ok!(Kitties::buy(Origin::signed(buyer), kitty_id));
+	assert_eq!(Balances::free_balance(buyer), buyer_free - quote.total);
+	assert_eq!(Balances::reserved_balance(buyer), buyer_reserved + quote.stake);
+	assert_eq!(Kitties::proceeds(seller), proceeds + quote.seller_amount);
+	assert_eq!(Kitties::pending_royalties(creator), royalties + quote.royalty);
+	// 市场手续费交给FeeDestination，测试中直接销毁
+	assert_eq!(Balances::total_issuance(), issuance - quote.marketplace_fee);
+	assert_eq!(quote.price, quote.marketplace_fee + quote.royalty + quote.seller_amount);
+	quote
+}
+
+#[test]
+fn quote_buy_none_when_purchase_rejected() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		// 未出售或不存在的Kitty
+		assert_eq!(Kitties::quote_buy(0, &2), None);
+		assert_eq!(Kitties::quote_buy(9, &2), None);
+		assert_ok!(Kitties::sell(Origin::signed(1), 0, Some(1_000)));
+		// 买家是拥有者
+		assert_eq!(Kitties::quote_buy(0, &1), None);
+		assert!(Kitties::quote_buy(0, &2).is_some());
+		// 未开放交易
+		TradingStartBlock::set(10);
+		assert_eq!(Kitties::quote_buy(0, &2), None);
+	});
+}
+
+#[test]
+fn quote_buy_without_fee_or_royalty() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::sell(Origin::signed(1), 0, Some(1_000)));
+		let quote = assert_buy_matches_quote(2, 0);
+		assert_eq!(
+			quote,
+			BuyQuote {
+				price: 1_000,
+				marketplace_fee: 0,
+				royalty: 0,
+				seller_amount: 1_000,
+				stake: 10_000,
+				total: 11_000,
+			}
+		);
+	});
+}
+
+#[test]
+fn quote_buy_matches_buy_with_fee_and_royalty() {
+	new_test_ext().execute_with(|| {
+		MarketplaceFee::set(Permill::from_percent(5));
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::sell(Origin::signed(1), 0, Some(1_000)));
+		// 创建者出售只收市场手续费
+		let quote = assert_buy_matches_quote(2, 0);
+		assert_eq!((quote.marketplace_fee, quote.royalty, quote.seller_amount), (50, 0, 950));
+		// 转售时同时收取市场手续费和版税
+		assert_ok!(Kitties::top_up_stake(Origin::signed(2), 0, 500));
+		assert_ok!(Kitties::sell(Origin::signed(2), 0, Some(2_000)));
+		let quote = assert_buy_matches_quote(4, 0);
+		assert_eq!((quote.marketplace_fee, quote.royalty, quote.seller_amount), (100, 200, 1_700));
+		assert_eq!(quote.stake, 10_500);
+		// 不同的费率组合
+		MarketplaceFee::set(Permill::from_percent(1));
+		RoyaltyRate::set(Permill::from_percent(25));
+		assert_ok!(Kitties::sell(Origin::signed(4), 0, Some(3_333)));
+		let quote = assert_buy_matches_quote(2, 0);
+		assert_eq!((quote.marketplace_fee, quote.royalty, quote.seller_amount), (33, 833, 2_467));
+	});
+}
+
+#[test]
+fn rare_kitty_sale_pays_rarity_fee() {
+	new_test_ext().execute_with(|| {
+		MarketplaceFee::set(Permill::from_percent(1));
+		RarityFeeTiers::set(vec![(64, Permill::from_percent(2)), (120, Permill::from_percent(5))]);
+		// 稀有度分别为0、64和128
+		assert_ok!(Kitties::do_mint(&1, [0; 16]));
+		assert_ok!(Kitties::do_mint(&1, [0x0f; 16]));
+		assert_ok!(Kitties::do_mint(&1, [0xff; 16]));
+		for kitty_id in 0..3 {
+			assert_ok!(Kitties::sell(Origin::signed(1), kitty_id, Some(1_000)));
+		}
+		// 普通Kitty只收基础手续费
+		let quote = assert_buy_matches_quote(2, 0);
+		assert_eq!((quote.marketplace_fee, quote.seller_amount), (10, 990));
+		// 达到第一档
+		let quote = assert_buy_matches_quote(2, 1);
+		assert_eq!((quote.marketplace_fee, quote.seller_amount), (30, 970));
+		// 使用达到的最高一档
+		let quote = assert_buy_matches_quote(2, 2);
+		assert_eq!((quote.marketplace_fee, quote.seller_amount), (60, 940));
+		assert_eq!(Kitties::proceeds(1), 990 + 970 + 940);
+	});
+}
+
+#[test]
+fn installment_plan_completes_purchase() {
+	new_test_ext().execute_with(|| {
+		let (seller, buyer) = (1u64, 2u64);
+		assert_ok!(Kitties::create(Origin::signed(seller)));
+		assert_ok!(Kitties::sell(Origin::signed(seller), 0, Some(1_000)));
+		assert_noop!(
+			Kitties::start_installment(Origin::signed(buyer), 0, 1),
+			Error::<Test>::InvalidInstallmentCount
+		);
+		assert_noop!(
+			Kitties::start_installment(Origin::signed(buyer), 0, 13),
+			Error::<Test>::InvalidInstallmentCount
+		);
+		// 开始分期时需要支付第一期
+		assert_noop!(
+			Kitties::start_installment(Origin::signed(100), 0, 3),
+			pallet_balances::Error::<Test>::InsufficientBalance
+		);
+		let buyer_free = Balances::free_balance(buyer);
+		assert_ok!(Kitties::start_installment(Origin::signed(buyer), 0, 3));
+		assert_has_event!(Event::<Test>::InstallmentPlanStarted(buyer, 0, 3, 333));
+		assert_has_event!(Event::<Test>::InstallmentPaid(buyer, 0, 1, 333));
+		assert_eq!(Balances::free_balance(Kitties::account_id()), 500 + 333);
+		// 分期期间Kitty被锁定
+		assert_eq!(Kitties::kitties_list_for_sales(0), None);
+		assert_noop!(Kitties::transfer(Origin::signed(seller), 3, 0), Error::<Test>::KittyLocked);
+		assert_noop!(
+			Kitties::pay_installment(Origin::signed(4), 0),
+			Error::<Test>::NotInstallmentBuyer
+		);
+		run_to_block(8);
+		assert_ok!(Kitties::pay_installment(Origin::signed(buyer), 0));
+		let plan = Kitties::installment_plan(0).unwrap();
+		assert_eq!((plan.paid_installments, plan.paid, plan.next_due), (2, 666, 18));
+		assert_eq!(Balances::free_balance(Kitties::account_id()), 500 + 666);
+		// 每期重新计算期限
+		run_to_block(17);
+		assert_ok!(Kitties::pay_installment(Origin::signed(buyer), 0));
+		assert_has_event!(Event::<Test>::InstallmentPaid(buyer, 0, 3, 334));
+		assert_has_event!(Event::<Test>::InstallmentPlanCompleted(buyer, 0));
+		// 付清后完成交易
+		assert_eq!(Kitties::owner(0), Some(buyer));
+		assert_eq!(Kitties::installment_plan(0), None);
+		assert_eq!(Kitties::kitty_lock(0), None);
+		assert_eq!(Kitties::proceeds(seller), 1_000);
+		assert_eq!(Balances::free_balance(buyer), buyer_free - 1_000 - 10_000);
+		assert_eq!(Balances::free_balance(Kitties::account_id()), 500);
+		// 已完成的计划不会再逾期
+		run_to_block(30);
+		assert_eq!(Kitties::owner(0), Some(buyer));
+	});
+}
+
+#[test]
+fn installment_plan_defaults_after_missed_deadline() {
+	new_test_ext().execute_with(|| {
+		let (seller, buyer) = (1u64, 2u64);
+		assert_ok!(Kitties::create(Origin::signed(seller)));
+		assert_ok!(Kitties::sell(Origin::signed(seller), 0, Some(1_000)));
+		let buyer_free = Balances::free_balance(buyer);
+		let seller_free = Balances::free_balance(seller);
+		assert_ok!(Kitties::start_installment(Origin::signed(buyer), 0, 4));
+		assert_ok!(Kitties::pay_installment(Origin::signed(buyer), 0));
+		// 逾期后扣除10%罚金退还已付款项
+		run_to_block(11);
+		assert_has_event!(Event::<Test>::InstallmentPlanDefaulted(buyer, 0, 450, 50));
+		assert_eq!(Balances::free_balance(buyer), buyer_free - 50);
+		assert_eq!(Balances::free_balance(seller), seller_free + 50);
+		assert_eq!(Balances::free_balance(Kitties::account_id()), 500);
+		// Kitty留在卖家手中并解除锁定
+		assert_eq!(Kitties::owner(0), Some(seller));
+		assert_eq!(Kitties::installment_plan(0), None);
+		assert_eq!(Kitties::kitty_lock(0), None);
+		assert_noop!(
+			Kitties::pay_installment(Origin::signed(buyer), 0),
+			Error::<Test>::NoInstallmentPlan
+		);
+		assert_ok!(Kitties::transfer(Origin::signed(seller), 4, 0));
+	});
+}
+
+#[test]
+fn set_note_reserves_deposit_per_byte() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::set_note(Origin::signed(1), 0, vec![7u8; 20]));
+		assert_eq!(Kitties::private_note(0).unwrap().into_inner(), vec![7u8; 20]);
+		assert_eq!(Kitties::note_deposit(0), Some((1, 200)));
+		assert_eq!(Balances::reserved_balance(1), 10_000 + 200);
+		assert_has_event!(Event::<Test>::NoteSet(1, 0, 200));
+		// 覆盖笔记时按新的长度重新计算押金
+		assert_ok!(Kitties::set_note(Origin::signed(1), 0, vec![8u8; 5]));
+		assert_eq!(Balances::reserved_balance(1), 10_000 + 50);
+		assert_ok!(Kitties::clear_note(Origin::signed(1), 0));
+		assert_eq!(Kitties::private_note(0), None);
+		assert_eq!(Kitties::note_deposit(0), None);
+		assert_eq!(Balances::reserved_balance(1), 10_000);
+		assert_has_event!(Event::<Test>::NoteCleared(0));
+	});
+}
+
+#[test]
+fn set_note_failed_too_long() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::set_note(Origin::signed(1), 0, vec![1u8; 64]));
+		assert_noop!(
+			Kitties::set_note(Origin::signed(1), 0, vec![1u8; 65]),
+			Error::<Test>::NoteTooLong
+		);
+	});
+}
+
+#[test]
+fn note_failed_not_owner() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::set_note(Origin::signed(1), 0, vec![1u8; 4]));
+		assert_noop!(
+			Kitties::set_note(Origin::signed(2), 0, vec![2u8; 4]),
+			Error::<Test>::NotOwner
+		);
+		assert_noop!(Kitties::clear_note(Origin::signed(2), 0), Error::<Test>::NotOwner);
+		assert_eq!(Kitties::private_note(0).unwrap().into_inner(), vec![1u8; 4]);
+	});
+}
+
+#[test]
+fn note_cleared_when_kitty_changes_hands() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		// 转移时清除笔记并向原拥有者退还押金
+		assert_ok!(Kitties::set_note(Origin::signed(1), 0, vec![1u8; 10]));
+		assert_ok!(Kitties::transfer(Origin::signed(1), 2, 0));
+		assert_eq!(Kitties::private_note(0), None);
+		assert_eq!(Balances::reserved_balance(1), 0);
+		// 出售时同样清除
+		assert_ok!(Kitties::set_note(Origin::signed(2), 0, vec![2u8; 10]));
+		assert_ok!(Kitties::sell(Origin::signed(2), 0, Some(1_000)));
+		assert_ok!(Kitties::buy(Origin::signed(4), 0));
+		assert_eq!(Kitties::private_note(0), None);
+		assert_eq!(Kitties::note_deposit(0), None);
+		assert_eq!(Balances::reserved_balance(2), 0);
+		// 放弃时同样清除
+		assert_ok!(Kitties::set_note(Origin::signed(4), 0, vec![4u8; 10]));
+		assert_ok!(Kitties::abandon(Origin::signed(4), 0));
+		assert_eq!(Kitties::private_note(0), None);
+		assert_eq!(Balances::reserved_balance(4), 0);
+	});
+}
+
+#[test]
+fn kitties_of_truncated_at_max_query_results() {
+	new_test_ext().execute_with(|| {
+		for _ in 0..3 {
+			assert_ok!(Kitties::create(Origin::signed(1)));
+		}
+		let (kitties, truncated) = Kitties::kitties_of(&1);
+		assert_eq!(kitties.len(), 3);
+		assert!(!truncated);
+		// 超过上限时截断结果
+		for _ in 0..4 {
+			assert_ok!(Kitties::create(Origin::signed(1)));
+		}
+		let (kitties, truncated) = Kitties::kitties_of(&1);
+		assert_eq!(kitties.len(), 5);
+		assert!(truncated);
+		assert_eq!(Kitties::kitties_of(&2), (vec![], false));
+	});
+}
+
+#[test]
+fn kitties_of_paged_walks_all_kitties() {
+	new_test_ext().execute_with(|| {
+		for _ in 0..7 {
+			assert_ok!(Kitties::create(Origin::signed(1)));
+		}
+		// 每页数量不超过上限
+		assert_eq!(Kitties::kitties_of_paged(&1, 0, 100).len(), 5);
+		let mut all = Vec::new();
+		let mut start = 0;
+		loop {
+			let page = Kitties::kitties_of_paged(&1, start, 3);
+			if page.is_empty() {
+				break;
+			}
+			start += page.len() as u32;
+			all.extend(page);
+		}
+		all.sort();
+		assert_eq!(all, (0..7).collect::<Vec<u32>>());
+	});
+}
+
+#[test]
+fn kitties_of_follows_ownership() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::transfer(Origin::signed(1), 2, 0));
+		assert_eq!(Kitties::kitties_of(&1).0, vec![1]);
+		assert_eq!(Kitties::kitties_of(&2).0, vec![0]);
+		assert_ok!(Kitties::sell(Origin::signed(1), 1, Some(1_000)));
+		assert_ok!(Kitties::buy(Origin::signed(2), 1));
+		assert_eq!(Kitties::kitties_of(&1).0, vec![]);
+		assert_ok!(Kitties::abandon(Origin::signed(2), 0));
+		assert_eq!(Kitties::kitties_of(&2).0, vec![1]);
+		assert_ok!(Kitties::adopt(Origin::signed(4), 0));
+		assert_eq!(Kitties::kitties_of(&4).0, vec![0]);
+	});
+}
+
+#[test]
+fn resale_cooldown_blocks_listing_until_unlock_block() {
+	new_test_ext().execute_with(|| {
+		ResaleCooldown::set(5);
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		// 创建后可以直接出售
+		assert_ok!(Kitties::sell(Origin::signed(1), 0, Some(1_000)));
+		assert_ok!(Kitties::buy(Origin::signed(2), 0));
+		assert_eq!(Kitties::resale_unlock_block(0), Some(6));
+		assert_noop!(
+			Kitties::sell(Origin::signed(2), 0, Some(2_000)),
+			Error::<Test>::ResaleCooldownActive
+		);
+		// 取消出售不受限制
+		assert_ok!(Kitties::sell(Origin::signed(2), 0, None));
+		run_to_block(5);
+		assert_noop!(
+			Kitties::sell(Origin::signed(2), 0, Some(2_000)),
+			Error::<Test>::ResaleCooldownActive
+		);
+		run_to_block(6);
+		assert_ok!(Kitties::sell(Origin::signed(2), 0, Some(2_000)));
+	});
+}
+
+#[test]
+fn resale_cooldown_allows_transfer_but_keeps_cooldown() {
+	new_test_ext().execute_with(|| {
+		ResaleCooldown::set(5);
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::sell(Origin::signed(1), 0, Some(1_000)));
+		assert_ok!(Kitties::buy(Origin::signed(2), 0));
+		// 普通转移仍然允许，但冷却期跟随Kitty
+		assert_ok!(Kitties::transfer(Origin::signed(2), 4, 0));
+		assert_noop!(
+			Kitties::sell(Origin::signed(4), 0, Some(1_000)),
+			Error::<Test>::ResaleCooldownActive
+		);
+	});
+}
+
+#[test]
+fn resale_cooldown_restarts_after_each_purchase() {
+	new_test_ext().execute_with(|| {
+		ResaleCooldown::set(5);
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::sell(Origin::signed(1), 0, Some(1_000)));
+		assert_ok!(Kitties::buy(Origin::signed(2), 0));
+		run_to_block(6);
+		assert_ok!(Kitties::sell(Origin::signed(2), 0, Some(1_000)));
+		assert_ok!(Kitties::buy(Origin::signed(4), 0));
+		assert_eq!(Kitties::resale_unlock_block(0), Some(11));
+		assert_noop!(
+			Kitties::sell(Origin::signed(4), 0, Some(1_000)),
+			Error::<Test>::ResaleCooldownActive
+		);
+		run_to_block(11);
+		assert_ok!(Kitties::sell(Origin::signed(4), 0, Some(1_000)));
+	});
+}
+
+#[test]
+fn resale_cooldown_disabled_when_zero() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::sell(Origin::signed(1), 0, Some(1_000)));
+		assert_ok!(Kitties::buy(Origin::signed(2), 0));
+		assert_eq!(Kitties::resale_unlock_block(0), None);
+		assert_ok!(Kitties::sell(Origin::signed(2), 0, Some(1_000)));
+	});
+}
+
+#[test]
+fn recover_stranded_stake_from_reaped_owner() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::transfer(Origin::signed(1), 2, 0));
+		assert_ok!(Kitties::sell(Origin::signed(2), 0, Some(1_000)));
+		// 拥有者的质押仍然存在时不能恢复
+		assert_noop!(
+			Kitties::recover_stranded_stake(Origin::root(), 0),
+			Error::<Test>::StakeNotStranded
+		);
+		assert_noop!(Kitties::recover_stranded_stake(Origin::signed(1), 0), BadOrigin);
+		// 模拟拥有者账户被回收
+		assert_ok!(Balances::set_balance(Origin::root(), 2, 0, 0));
+		assert_eq!(Balances::total_balance(&2), 0);
+		assert_ok!(Kitties::recover_stranded_stake(Origin::root(), 0));
+		assert_has_event!(Event::<Test>::StrandedStakeRecovered(0, 2, 5));
+		// Kitty转给恢复账户并重新质押
+		assert_eq!(Kitties::owner(0), Some(5));
+		assert_eq!(Kitties::kitties_of(&2).0, vec![]);
+		assert_eq!(Kitties::kitties_of(&5).0, vec![0]);
+		assert_eq!(Kitties::kitty_stake(0), Some(10_000));
+		assert_eq!(Balances::reserved_balance(5), 10_000);
+		assert_eq!(Kitties::kitties_list_for_sales(0), None);
+		assert_eq!(Kitties::listing_deposit(0), None);
+		// 恢复后质押不再丢失
+		assert_noop!(
+			Kitties::recover_stranded_stake(Origin::root(), 0),
+			Error::<Test>::StakeNotStranded
+		);
+	});
+}
+
+#[test]
+fn supply_nearing_limit_fires_once() {
+	new_test_ext().execute_with(|| {
+		let threshold = Permill::from_percent(90) * u32::MAX;
+		// 从警告线前两个开始创建
+		KittiesCount::<Test>::put(threshold - 2);
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert!(!System::events().iter().any(|record| matches!(
+			record.event,
+			TestEvent::SubstrateKitties(Event::SupplyNearingLimit(_))
+		)));
+		// 达到警告线时发出警告
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_has_event!(Event::<Test>::SupplyNearingLimit(threshold));
+		// 之后的创建不再重复警告
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		let warnings = System::events()
+			.iter()
+			.filter(|record| {
+				matches!(record.event, TestEvent::SubstrateKitties(Event::SupplyNearingLimit(_)))
+			})
+			.count();
+		assert_eq!(warnings, 1);
+	});
+}
+
+#[test]
+fn escalate_to_auction_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::sell(Origin::signed(1), 0, Some(1_000)));
+		assert_eq!(Kitties::kitty_status(0), KittyStatus::Listed(1_000));
+		assert_ok!(Kitties::escalate_to_auction(Origin::signed(1), 0, 10));
+		// 取消定价出售并以出售价格作为保留价
+		assert_has_event!(Event::<Test>::KittyListed(1, 0, None));
+		assert_has_event!(Event::<Test>::AuctionStarted(1, 0, 1_000, 11));
+		assert_eq!(Kitties::kitties_list_for_sales(0), None);
+		assert_eq!(Kitties::listing_deposit(0), None);
+		assert_eq!(Kitties::kitty_status(0), KittyStatus::InAuction);
+		assert_noop!(Kitties::buy(Origin::signed(2), 0), Error::<Test>::KittyNotForSell);
+		assert_noop!(Kitties::transfer(Origin::signed(1), 2, 0), Error::<Test>::KittyLocked);
+		// 出价不能低于保留价
+		assert_noop!(Kitties::bid(Origin::signed(2), 0, 999), Error::<Test>::BidTooLow);
+		assert_ok!(Kitties::bid(Origin::signed(2), 0, 1_000));
+		assert_ok!(Kitties::bid(Origin::signed(4), 0, 1_500));
+		assert_noop!(Kitties::bid(Origin::signed(2), 0, 1_499), Error::<Test>::BidTooLow);
+		// 被超过的出价已退还
+		assert_eq!(Balances::reserved_balance(2), 0);
+		assert_eq!(Balances::reserved_balance(4), 1_500);
+		run_to_block(11);
+		assert_has_event!(Event::<Test>::AuctionSettled(0, 4, 1_500));
+		assert_eq!(Kitties::owner(0), Some(4));
+		assert_eq!(Kitties::proceeds(1), 1_500);
+		assert_eq!(Balances::reserved_balance(4), 10_000);
+		assert_eq!(Kitties::kitty_status(0), KittyStatus::Idle);
+		assert_noop!(Kitties::bid(Origin::signed(2), 0, 2_000), Error::<Test>::NotInAuction);
+	});
+}
+
+#[test]
+fn auction_without_bids_returns_kitty_to_seller() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::sell(Origin::signed(1), 0, Some(1_000)));
+		assert_ok!(Kitties::escalate_to_auction(Origin::signed(1), 0, 5));
+		run_to_block(6);
+		assert_has_event!(Event::<Test>::AuctionEndedWithoutBids(0));
+		assert_eq!(Kitties::owner(0), Some(1));
+		assert_eq!(Kitties::kitty_status(0), KittyStatus::Idle);
+		assert_ok!(Kitties::transfer(Origin::signed(1), 2, 0));
+	});
+}
+
+#[test]
+fn escalate_to_auction_failed_unlisted_or_already_auctioned() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_noop!(
+			Kitties::escalate_to_auction(Origin::signed(1), 0, 10),
+			Error::<Test>::KittyNotForSell
+		);
+		assert_ok!(Kitties::sell(Origin::signed(1), 0, Some(1_000)));
+		assert_noop!(
+			Kitties::escalate_to_auction(Origin::signed(2), 0, 10),
+			Error::<Test>::NotOwner
+		);
+		assert_noop!(
+			Kitties::escalate_to_auction(Origin::signed(1), 0, 0),
+			Error::<Test>::InvalidAuctionDuration
+		);
+		// 持续时间不能超过MaxAuctionDuration
+		assert_noop!(
+			Kitties::escalate_to_auction(Origin::signed(1), 0, 101),
+			Error::<Test>::InvalidAuctionDuration
+		);
+		// 结束区块溢出时拒绝，不会留下永远无法结算的拍卖
+		MaxAuctionDuration::set(&u64::MAX);
+		assert_noop!(
+			Kitties::escalate_to_auction(Origin::signed(1), 0, u64::MAX),
+			Error::<Test>::InvalidAuctionDuration
+		);
+		// 失败时保留原来的出售
+		assert_eq!(Kitties::kitties_list_for_sales(0), Some(1_000));
+		MaxAuctionDuration::set(&100);
+		assert_ok!(Kitties::escalate_to_auction(Origin::signed(1), 0, 100));
+		assert_has_event!(Event::<Test>::AuctionStarted(1, 0, 1_000, 101));
+		assert_noop!(
+			Kitties::escalate_to_auction(Origin::signed(1), 0, 10),
+			Error::<Test>::AlreadyInAuction
+		);
+	});
+}
+
+#[test]
+fn breed_failed_parent_listed() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::sell(Origin::signed(1), 1, Some(1_000)));
+		assert_noop!(Kitties::breed(Origin::signed(1), 0, 1), Error::<Test>::ParentListed);
+		assert_noop!(Kitties::breed(Origin::signed(1), 1, 0), Error::<Test>::ParentListed);
+		assert_noop!(
+			Kitties::breed_premium(Origin::signed(1), 0, 1, 1),
+			Error::<Test>::ParentListed
+		);
+		// 出售列表保持不变
+		assert_eq!(Kitties::kitties_list_for_sales(1), Some(1_000));
+		// 拍卖中的Kitty同样不能繁殖
+		assert_ok!(Kitties::escalate_to_auction(Origin::signed(1), 1, 10));
+		assert_noop!(Kitties::breed(Origin::signed(1), 0, 1), Error::<Test>::ParentListed);
+		run_to_block(11);
+		assert_ok!(Kitties::breed(Origin::signed(1), 0, 1));
+	});
+}
+
+#[test]
+fn kitties_by_ids_aligned_with_input() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KittyConsumer::mint_for(Origin::root(), 1, [1u8; 16]));
+		assert_ok!(KittyConsumer::mint_for(Origin::root(), 2, [2u8; 16]));
+		let kitty = |dna| Some(Kitty { dna, clone: false });
+		assert_eq!(
+			Kitties::kitties_by_ids(vec![1, 7, 0, 1, 2]),
+			vec![kitty([2u8; 16]), None, kitty([1u8; 16]), kitty([2u8; 16]), None]
+		);
+		assert_eq!(Kitties::kitties_by_ids(vec![]), vec![]);
+	});
+}
+
+#[test]
+fn kitties_by_ids_capped_at_max_query_results() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		// 超过上限的ID被忽略
+		let result = Kitties::kitties_by_ids(vec![0, 9, 0, 9, 0, 0, 0]);
+		assert_eq!(result.len(), 5);
+		assert!(result[0].is_some());
+		assert!(result[1].is_none());
+		assert!(result[4].is_some());
+	});
+}
+
+#[test]
+fn random_value_combines_both_beacons() {
+	new_test_ext().execute_with(|| {
+		let sender: u64 = 1;
+		let extrinsic_index = System::extrinsic_index();
+		let primary_only = (RandomnessCollectiveFlip::random_seed(), &sender, extrinsic_index)
+			.using_encoded(sp_io::hashing::blake2_128);
+		let secondary_only = (SecondaryBeacon::random_seed(), &sender, extrinsic_index)
+			.using_encoded(sp_io::hashing::blake2_128);
+		let combined = Kitties::random_value(&sender);
+		assert_ne!(combined, primary_only);
+		assert_ne!(combined, secondary_only);
+	});
+}
+
+// 创建资产并发放给买家
+fn setup_asset(asset_id: u32, holder: u64, amount: u128) {
+	assert_ok!(Assets::force_create(Origin::root(), asset_id, 1, true, 1));
+	assert_ok!(Assets::mint(Origin::signed(1), asset_id, holder, amount));
+}
+
+#[test]
+fn sell_in_allowed_asset_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::sell_in_asset(Origin::signed(1), 0, 1_000, 2));
+		assert_eq!(Kitties::kitties_list_for_sales(0), Some(1_000));
+		assert_eq!(Kitties::listing_asset(0), Some(2));
+		assert_has_event!(Event::<Test>::KittyListedInAsset(1, 0, 1_000, 2));
+		// 使用原生代币重新定价
+		assert_ok!(Kitties::sell(Origin::signed(1), 0, Some(500)));
+		assert_eq!(Kitties::listing_asset(0), None);
+		// 取消出售同时清除资产
+		assert_ok!(Kitties::sell_in_asset(Origin::signed(1), 0, 1_000, 1));
+		assert_ok!(Kitties::sell(Origin::signed(1), 0, None));
+		assert_eq!(Kitties::listing_asset(0), None);
+	});
+}
+
+#[test]
+fn sell_in_asset_failed_asset_not_allowed() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_noop!(
+			Kitties::sell_in_asset(Origin::signed(1), 0, 1_000, 3),
+			Error::<Test>::AssetNotAllowed
+		);
+		assert_eq!(Kitties::kitties_list_for_sales(0), None);
+	});
+}
+
+#[test]
+fn buy_settles_in_listing_asset() {
+	new_test_ext().execute_with(|| {
+		setup_asset(1, 2, 10_000);
+		setup_asset(2, 2, 10_000);
+		assert_ok!(KittyConsumer::mint_for(Origin::root(), 4, [3u8; 16]));
+		assert_ok!(Kitties::transfer(Origin::signed(4), 1, 0));
+		assert_ok!(Kitties::sell_in_asset(Origin::signed(1), 0, 1_000, 2));
+		let buyer_free = Balances::free_balance(2);
+		assert_ok!(Kitties::buy(Origin::signed(2), 0));
+		assert_eq!(Kitties::owner(0), Some(2));
+		// 使用资产2支付，创建者直接获得版税
+		assert_eq!(Assets::balance(2, 2), 9_000);
+		assert_eq!(Assets::balance(2, 1), 900);
+		assert_eq!(Assets::balance(2, 4), 100);
+		assert_eq!(Assets::balance(1, 2), 10_000);
+		// 原生代币只用于质押
+		assert_eq!(Balances::free_balance(2), buyer_free - 10_000);
+		assert_eq!(Kitties::proceeds(1), 0);
+		assert_eq!(Kitties::listing_asset(0), None);
+		assert_has_event!(Event::<Test>::KittySoldInAsset(2, 1, 0, 2, 1_000));
+		assert_has_event!(Event::<Test>::KittySold(2, 1, 0));
+	});
+}
+
+#[test]
+fn buy_in_asset_charges_marketplace_and_rarity_fees() {
+	new_test_ext().execute_with(|| {
+		MarketplaceFee::set(Permill::from_percent(5));
+		RarityFeeTiers::set(vec![(0, Permill::from_percent(2))]);
+		setup_asset(2, 2, 10_000);
+		assert_ok!(KittyConsumer::mint_for(Origin::root(), 4, [3u8; 16]));
+		assert_ok!(Kitties::transfer(Origin::signed(4), 1, 0));
+		assert_ok!(Kitties::sell_in_asset(Origin::signed(1), 0, 1_000, 2));
+		assert_ok!(Kitties::buy(Origin::signed(2), 0));
+		// 手续费5% + 2%归FeeAssetDestination，版税10%归创建者
+		assert_eq!(Assets::balance(2, 2), 9_000);
+		assert_eq!(Assets::balance(2, FeeAssetDestination::get()), 70);
+		assert_eq!(Assets::balance(2, Kitties::account_id()), 0);
+		assert_eq!(Assets::balance(2, 4), 100);
+		assert_eq!(Assets::balance(2, 1), 830);
+	});
+}
+
+#[test]
+fn buy_in_asset_failed_not_enough_asset() {
+	new_test_ext().execute_with(|| {
+		setup_asset(1, 2, 500);
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::sell_in_asset(Origin::signed(1), 0, 1_000, 1));
+		assert!(Kitties::buy(Origin::signed(2), 0).is_err());
+		assert_eq!(Kitties::owner(0), Some(1));
+		assert_eq!(Balances::reserved_balance(2), 0);
+	});
+}
+
+#[test]
+fn make_offer_reserves_and_accept_offer_sells() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_noop!(Kitties::make_offer(Origin::signed(1), 0, 1_000), Error::<Test>::BuyerIsOwner);
+		assert_noop!(
+			Kitties::make_offer(Origin::signed(2), 0, 0),
+			Error::<Test>::InvalidOfferAmount
+		);
+		assert_ok!(Kitties::make_offer(Origin::signed(2), 0, 1_000));
+		assert_ok!(Kitties::make_offer(Origin::signed(4), 0, 2_000));
+		assert_eq!(Balances::reserved_balance(2), 1_000);
+		// 重复报价替换之前的报价
+		assert_ok!(Kitties::make_offer(Origin::signed(2), 0, 1_500));
+		assert_eq!(Balances::reserved_balance(2), 1_500);
+		assert_eq!(Kitties::offer_count(0), 2);
+		assert_noop!(Kitties::accept_offer(Origin::signed(2), 0, 4), Error::<Test>::NotOwner);
+		assert_ok!(Kitties::accept_offer(Origin::signed(1), 0, 4));
+		assert_has_event!(Event::<Test>::OfferAccepted(1, 4, 0, 2_000));
+		assert_eq!(Kitties::owner(0), Some(4));
+		assert_eq!(Kitties::proceeds(1), 2_000);
+		assert_eq!(Balances::reserved_balance(4), 10_000);
+		// 拥有者改变后其他报价被取消
+		assert_has_event!(Event::<Test>::OfferCancelled(2, 0, 1_500));
+		assert_eq!(Balances::reserved_balance(2), 0);
+		assert_eq!(Kitties::offer(0, 2), None);
+		assert_eq!(Kitties::offer_count(0), 0);
+	});
+}
+
+#[test]
+fn make_offer_failed_too_many_offers() {
+	new_test_ext().execute_with(|| {
+		MaxOffersPerKitty::set(2);
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::make_offer(Origin::signed(2), 0, 1_000));
+		assert_ok!(Kitties::make_offer(Origin::signed(4), 0, 1_000));
+		assert_noop!(
+			Kitties::make_offer(Origin::signed(5), 0, 1_000),
+			Error::<Test>::TooManyOffers
+		);
+		// 达到上限时仍然可以修改已有的报价
+		assert_ok!(Kitties::make_offer(Origin::signed(4), 0, 3_000));
+		// 撤回报价后腾出空间
+		assert_ok!(Kitties::withdraw_offer(Origin::signed(2), 0));
+		assert_has_event!(Event::<Test>::OfferWithdrawn(2, 0, 1_000));
+		assert_eq!(Balances::reserved_balance(2), 0);
+		assert_eq!(Kitties::offer_count(0), 1);
+		assert_ok!(Kitties::make_offer(Origin::signed(5), 0, 1_000));
+		assert_noop!(Kitties::withdraw_offer(Origin::signed(2), 0), Error::<Test>::NoOffer);
+	});
+}
+
+#[test]
+fn make_offer_failed_too_many_active_offers() {
+	new_test_ext().execute_with(|| {
+		MaxOffersPerAccount::set(2);
+		for _ in 0..3 {
+			assert_ok!(Kitties::create(Origin::signed(1)));
+		}
+		assert_ok!(Kitties::make_offer(Origin::signed(2), 0, 1_000));
+		assert_ok!(Kitties::make_offer(Origin::signed(2), 1, 1_000));
+		assert_eq!(Kitties::account_offer_count(2), 2);
+		assert_noop!(
+			Kitties::make_offer(Origin::signed(2), 2, 1_000),
+			Error::<Test>::TooManyActiveOffers
+		);
+		// 达到上限时仍然可以修改已有的报价
+		assert_ok!(Kitties::make_offer(Origin::signed(2), 1, 2_000));
+		assert_eq!(Kitties::account_offer_count(2), 2);
+		// 其他账户不受影响
+		assert_ok!(Kitties::make_offer(Origin::signed(4), 2, 1_000));
+		// 撤回报价后腾出空间
+		assert_ok!(Kitties::withdraw_offer(Origin::signed(2), 0));
+		assert_eq!(Kitties::account_offer_count(2), 1);
+		assert_ok!(Kitties::make_offer(Origin::signed(2), 2, 1_000));
+		// 报价被接受或因转移被取消时同样腾出空间
+		assert_ok!(Kitties::accept_offer(Origin::signed(1), 1, 2));
+		assert_eq!(Kitties::account_offer_count(2), 1);
+		assert_ok!(Kitties::transfer(Origin::signed(1), 5, 2));
+		assert_eq!(Kitties::account_offer_count(2), 0);
+		assert_eq!(Kitties::account_offer_count(4), 0);
+	});
+}
+
+#[test]
+fn withdraw_all_offers_works_in_chunks() {
+	new_test_ext().execute_with(|| {
+		for _ in 0..3 {
+			assert_ok!(Kitties::create(Origin::signed(1)));
+		}
+		assert_ok!(Kitties::make_offer(Origin::signed(2), 0, 1_000));
+		assert_ok!(Kitties::make_offer(Origin::signed(2), 1, 2_000));
+		assert_ok!(Kitties::make_offer(Origin::signed(2), 2, 3_000));
+		assert_ok!(Kitties::make_offer(Origin::signed(4), 1, 500));
+		assert_eq!(Balances::reserved_balance(2), 6_000);
+
+		assert_ok!(Kitties::withdraw_all_offers(Origin::signed(2), 2));
+		assert_has_event!(Event::<Test>::OffersWithdrawn(2, 2));
+		assert_ok!(Kitties::withdraw_all_offers(Origin::signed(2), 2));
+		assert_has_event!(Event::<Test>::OffersWithdrawn(2, 1));
+		assert_eq!(Balances::reserved_balance(2), 0);
+		for kitty_id in 0..3 {
+			assert_eq!(Kitties::offer(kitty_id, 2), None);
+		}
+		// 其他报价者的报价不受影响
+		assert_eq!(Kitties::offer(1, 4), Some(500));
+		assert_eq!(Kitties::offer_count(1), 1);
+
+		assert_ok!(Kitties::withdraw_all_offers(Origin::signed(2), 2));
+		assert_has_event!(Event::<Test>::OffersWithdrawn(2, 0));
+	});
+}
+
+#[test]
+fn auction_tie_is_won_by_earliest_bid() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::sell(Origin::signed(1), 0, Some(1_000)));
+		assert_ok!(Kitties::escalate_to_auction(Origin::signed(1), 0, 10));
+		run_to_block(3);
+		assert_ok!(Kitties::bid(Origin::signed(2), 0, 2_000));
+		run_to_block(5);
+		assert_ok!(Kitties::bid(Origin::signed(4), 0, 2_000));
+		// 相同金额的出价不能重复提交
+		assert_noop!(Kitties::bid(Origin::signed(4), 0, 2_000), Error::<Test>::BidTooLow);
+		assert_noop!(Kitties::bid(Origin::signed(2), 0, 2_000), Error::<Test>::BidTooLow);
+		assert_eq!(Kitties::auction(0).unwrap().highest_bid, Some((2, 2_000, 3)));
+		assert_eq!(Kitties::tied_bid(0, 4), Some((2_000, 5)));
+		assert_eq!(Balances::reserved_balance(4), 2_000);
+		run_to_block(11);
+		// 较早的出价者获胜，较晚的出价者被退还
+		assert_has_event!(Event::<Test>::AuctionSettled(0, 2, 2_000));
+		assert_eq!(Kitties::owner(0), Some(2));
+		assert_eq!(Balances::reserved_balance(4), 0);
+		assert_eq!(Kitties::tied_bid(0, 4), None);
+	});
+}
+
+#[test]
+fn higher_bid_refunds_tied_bids() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::sell(Origin::signed(1), 0, Some(1_000)));
+		assert_ok!(Kitties::escalate_to_auction(Origin::signed(1), 0, 10));
+		assert_ok!(Kitties::bid(Origin::signed(2), 0, 2_000));
+		assert_ok!(Kitties::bid(Origin::signed(4), 0, 2_000));
+		assert_ok!(Kitties::bid(Origin::signed(5), 0, 3_000));
+		assert_eq!(Balances::reserved_balance(2), 0);
+		assert_eq!(Balances::reserved_balance(4), 0);
+		assert_eq!(Kitties::tied_bid(0, 4), None);
+		assert_eq!(Kitties::auction(0).unwrap().highest_bid, Some((5, 3_000, 1)));
+	});
+}
+
+#[test]
+fn burn_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_eq!(Kitties::created_at(0), Some(1));
+		assert_ok!(Kitties::sell(Origin::signed(1), 0, Some(1_000)));
+		assert_ok!(Kitties::make_offer(Origin::signed(2), 0, 1_000));
+		assert_noop!(Kitties::burn(Origin::signed(2), 0), Error::<Test>::NotOwner);
+		assert_ok!(Kitties::burn(Origin::signed(1), 0));
+		assert_has_event!(Event::<Test>::KittyBurned(1, 0));
+		assert_eq!(Kitties::kitties(0), None);
+		assert_eq!(Kitties::owner(0), None);
+		assert_eq!(Kitties::kitties_list_for_sales(0), None);
+		assert_eq!(Kitties::created_at(0), None);
+		// 质押、挂单押金和报价都已退还
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_eq!(Balances::reserved_balance(2), 0);
+		assert_noop!(Kitties::burn(Origin::signed(1), 0), Error::<Test>::NotOwner);
+	});
+}
+
+#[test]
+fn burn_failed_kitty_too_young() {
+	new_test_ext().execute_with(|| {
+		MinAgeBeforeBurn::set(5);
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		run_to_block(5);
+		assert_noop!(Kitties::burn(Origin::signed(1), 0), Error::<Test>::KittyTooYoungToBurn);
+		run_to_block(6);
+		assert_ok!(Kitties::burn(Origin::signed(1), 0));
+	});
+}
+
+#[test]
+fn every_error_has_a_stable_description() {
+	new_test_ext().execute_with(|| {
+		let descriptions = Kitties::error_descriptions();
+		for (index, (error, description)) in descriptions.into_iter().enumerate() {
+			assert!(!description.is_empty());
+			// 说明的下标与错误的编号一致
+			match DispatchError::from(error) {
+				DispatchError::Module { error, .. } => assert_eq!(error as usize, index),
+				_ => panic!("not a module error"),
+			}
+			assert_eq!(Kitties::describe_error(index as u8), Some(description.as_bytes().to_vec()));
+		}
+		assert_eq!(
+			Kitties::describe_error(0),
+			Some(b"The maximum number of kitties has been reached".to_vec())
+		);
+		assert_eq!(
+			Kitties::describe_error(45),
+			Some(b"The kitty is too young to be burned".to_vec())
+		);
+		assert_eq!(Kitties::describe_error(66), None);
+	});
+}
+
+#[test]
+fn create_bundle_works() {
+	new_test_ext().execute_with(|| {
+		for _ in 0..4 {
+			assert_ok!(Kitties::create(Origin::signed(1)));
+		}
+		assert_ok!(Kitties::create(Origin::signed(2)));
+		assert_noop!(
+			Kitties::create_bundle(Origin::signed(1), vec![0]),
+			Error::<Test>::InvalidBundleSize
+		);
+		assert_noop!(
+			Kitties::create_bundle(Origin::signed(1), vec![0, 1, 2, 3]),
+			Error::<Test>::InvalidBundleSize
+		);
+		assert_noop!(
+			Kitties::create_bundle(Origin::signed(1), vec![0, 4]),
+			Error::<Test>::NotOwner
+		);
+		assert_noop!(
+			Kitties::create_bundle(Origin::signed(1), vec![0, 0]),
+			Error::<Test>::KittyAlreadyBundled
+		);
+		assert_ok!(Kitties::sell(Origin::signed(1), 0, Some(1_000)));
+		assert_ok!(Kitties::create_bundle(Origin::signed(1), vec![0, 1]));
+		assert_has_event!(Event::<Test>::BundleCreated(1, 0, vec![0, 1]));
+		assert_eq!(Kitties::bundle_of(0), Some(0));
+		// 加入捆绑包后取消单独出售
+		assert_eq!(Kitties::kitties_list_for_sales(0), None);
+		// 一个Kitty不能在两个捆绑包中
+		assert_noop!(
+			Kitties::create_bundle(Origin::signed(1), vec![1, 2]),
+			Error::<Test>::KittyAlreadyBundled
+		);
+		// 捆绑包中的Kitty不能单独交易
+		assert_noop!(Kitties::transfer(Origin::signed(1), 2, 0), Error::<Test>::KittyLocked);
+		assert_noop!(Kitties::sell(Origin::signed(1), 1, Some(1_000)), Error::<Test>::KittyLocked);
+	});
+}
+
+#[test]
+fn transfer_bundle_moves_all_kitties() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::create_bundle(Origin::signed(1), vec![0, 1]));
+		assert_noop!(Kitties::transfer_bundle(Origin::signed(2), 4, 0), Error::<Test>::NotOwner);
+		assert_noop!(
+			Kitties::transfer_bundle(Origin::signed(1), 4, 1),
+			Error::<Test>::InvalidBundleId
+		);
+		assert_ok!(Kitties::transfer_bundle(Origin::signed(1), 4, 0));
+		assert_has_event!(Event::<Test>::BundleTransferred(1, 4, 0));
+		assert_eq!(Kitties::owner(0), Some(4));
+		assert_eq!(Kitties::owner(1), Some(4));
+		assert_eq!(Kitties::bundle(0).unwrap().0, 4);
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_eq!(Balances::reserved_balance(4), 20_000);
+		// 转移后仍然锁定在捆绑包中
+		assert_noop!(Kitties::transfer(Origin::signed(4), 1, 0), Error::<Test>::KittyLocked);
+	});
+}
+
+#[test]
+fn transfer_bundle_rejects_acceptance_required_recipient() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::create_bundle(Origin::signed(1), vec![0, 1]));
+		assert_ok!(Kitties::set_require_acceptance(Origin::signed(4), true));
+		assert_noop!(
+			Kitties::transfer_bundle(Origin::signed(1), 4, 0),
+			Error::<Test>::RecipientRequiresAcceptance
+		);
+		assert_ok!(Kitties::set_require_acceptance(Origin::signed(4), false));
+		assert_ok!(Kitties::transfer_bundle(Origin::signed(1), 4, 0));
+		assert_eq!(Kitties::owner(0), Some(4));
+	});
+}
+
+#[test]
+fn buy_bundle_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::create_bundle(Origin::signed(1), vec![0, 1]));
+		assert_noop!(Kitties::buy_bundle(Origin::signed(4), 0), Error::<Test>::BundleNotForSell);
+		assert_noop!(
+			Kitties::sell_bundle(Origin::signed(4), 0, Some(1_001)),
+			Error::<Test>::NotOwner
+		);
+		assert_ok!(Kitties::sell_bundle(Origin::signed(1), 0, Some(1_001)));
+		assert_noop!(Kitties::buy_bundle(Origin::signed(1), 0), Error::<Test>::BuyerIsOwner);
+		assert_ok!(Kitties::buy_bundle(Origin::signed(4), 0));
+		assert_has_event!(Event::<Test>::BundleSold(4, 1, 0, 1_001));
+		assert_has_event!(Event::<Test>::KittySold(4, 1, 0));
+		assert_has_event!(Event::<Test>::KittySold(4, 1, 1));
+		assert_eq!(Kitties::owner(0), Some(4));
+		assert_eq!(Kitties::owner(1), Some(4));
+		assert_eq!(Kitties::proceeds(1), 1_001);
+		assert_eq!(Kitties::bundle_price(0), None);
+		assert_eq!(Kitties::bundle(0).unwrap().0, 4);
+		// 售出后退还每个Kitty的挂单押金
+		assert_eq!(Balances::reserved_balance(1), 0);
+	});
+}
+
+#[test]
+fn sell_bundle_reserves_listing_deposit_per_kitty() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::create_bundle(Origin::signed(1), vec![0, 1]));
+		assert_ok!(Kitties::sell_bundle(Origin::signed(1), 0, Some(1_000)));
+		assert_eq!(Balances::reserved_balance(1), 20_200);
+		// 修改价格不重复质押
+		assert_ok!(Kitties::sell_bundle(Origin::signed(1), 0, Some(2_000)));
+		assert_eq!(Balances::reserved_balance(1), 20_200);
+		assert_ok!(Kitties::sell_bundle(Origin::signed(1), 0, None));
+		assert_eq!(Balances::reserved_balance(1), 20_000);
+		assert_ok!(Kitties::sell_bundle(Origin::signed(1), 0, Some(1_000)));
+		assert_ok!(Kitties::unbundle(Origin::signed(1), 0));
+		assert_eq!(Balances::reserved_balance(1), 20_000);
+	});
+}
+
+#[test]
+fn sell_bundle_respects_resale_cooldown() {
+	new_test_ext().execute_with(|| {
+		ResaleCooldown::set(5);
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::sell(Origin::signed(1), 0, Some(1_000)));
+		assert_ok!(Kitties::buy(Origin::signed(2), 0));
+		assert_ok!(Kitties::create(Origin::signed(2)));
+		assert_ok!(Kitties::create_bundle(Origin::signed(2), vec![0, 1]));
+		assert_noop!(
+			Kitties::sell_bundle(Origin::signed(2), 0, Some(2_000)),
+			Error::<Test>::ResaleCooldownActive
+		);
+		run_to_block(6);
+		assert_ok!(Kitties::sell_bundle(Origin::signed(2), 0, Some(2_000)));
+	});
+}
+
+#[test]
+fn sell_bundle_requires_sufficient_stake() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::create_bundle(Origin::signed(1), vec![0, 1]));
+		// 宽限期已过仍未补足质押
+		StakeShortfall::<Test>::insert(1, (5_000, 1));
+		assert_noop!(
+			Kitties::sell_bundle(Origin::signed(1), 0, Some(1_000)),
+			Error::<Test>::StakeTopUpRequired
+		);
+	});
+}
+
+#[test]
+fn buy_bundle_rejects_wash_trade() {
+	new_test_ext().execute_with(|| {
+		WashTradeCooldown::set(10);
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::create_bundle(Origin::signed(1), vec![0, 1]));
+		assert_ok!(Kitties::sell_bundle(Origin::signed(1), 0, Some(1_000)));
+		assert_ok!(Kitties::buy_bundle(Origin::signed(4), 0));
+		// 原拥有者不能在冷却期内买回
+		assert_ok!(Kitties::sell_bundle(Origin::signed(4), 0, Some(1_000)));
+		assert_noop!(Kitties::buy_bundle(Origin::signed(1), 0), Error::<Test>::WashTradeDetected);
+		assert_ok!(Kitties::buy_bundle(Origin::signed(5), 0));
+		assert_eq!(Kitties::bundle(0).unwrap().0, 5);
+	});
+}
+
+#[test]
+fn unbundle_releases_kitties() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::create_bundle(Origin::signed(1), vec![0, 1]));
+		assert_ok!(Kitties::sell_bundle(Origin::signed(1), 0, Some(1_000)));
+		assert_noop!(Kitties::unbundle(Origin::signed(2), 0), Error::<Test>::NotOwner);
+		assert_ok!(Kitties::unbundle(Origin::signed(1), 0));
+		assert_has_event!(Event::<Test>::Unbundled(1, 0));
+		assert_eq!(Kitties::bundle(0), None);
+		assert_eq!(Kitties::bundle_of(0), None);
+		assert_eq!(Kitties::bundle_price(0), None);
+		assert_eq!(Kitties::kitty_status(0), KittyStatus::Idle);
+		assert_ok!(Kitties::transfer(Origin::signed(1), 2, 0));
+		// Kitty可以重新加入新的捆绑包
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::create_bundle(Origin::signed(1), vec![1, 2]));
+		assert_has_event!(Event::<Test>::BundleCreated(1, 1, vec![1, 2]));
+	});
+}
+
+#[test]
+fn high_demand_raises_required_stake() {
+	new_test_ext().execute_with(|| {
+		HighDemandOfferThreshold::set(2);
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::make_offer(Origin::signed(2), 0, 1_000));
+		assert_eq!(Kitties::kitty_stake(0), Some(10_000));
+		// 报价数量达到阈值时质押要求提高50%
+		assert_ok!(Kitties::make_offer(Origin::signed(4), 0, 1_000));
+		assert_has_event!(Event::<Test>::StakeIncreaseRequired(1, 0, 15_000, 11));
+		assert_eq!(Kitties::kitty_stake(0), Some(15_000));
+		assert_eq!(Kitties::stake_shortfall(0), Some((5_000, 11)));
+		// 报价数量回落到阈值以下时撤销，再次达到阈值时重新提高
+		assert_ok!(Kitties::withdraw_offer(Origin::signed(4), 0));
+		assert_has_event!(Event::<Test>::StakeIncreaseLifted(0, 10_000));
+		assert_eq!(Kitties::kitty_stake(0), Some(10_000));
+		assert_eq!(Kitties::stake_shortfall(0), None);
+		assert_ok!(Kitties::make_offer(Origin::signed(4), 0, 1_000));
+		assert_eq!(Kitties::kitty_stake(0), Some(15_000));
+		// 宽限期内仍然可以出售
+		assert_ok!(Kitties::sell(Origin::signed(1), 0, Some(1_000)));
+		run_to_block(11);
+		assert_noop!(
+			Kitties::sell(Origin::signed(1), 0, Some(2_000)),
+			Error::<Test>::StakeTopUpRequired
+		);
+		assert_noop!(
+			Kitties::accept_offer(Origin::signed(1), 0, 2),
+			Error::<Test>::StakeTopUpRequired
+		);
+	});
+}
+
+#[test]
+fn top_up_stake_covers_shortfall() {
+	new_test_ext().execute_with(|| {
+		HighDemandOfferThreshold::set(1);
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::make_offer(Origin::signed(2), 0, 1_000));
+		assert_eq!(Kitties::stake_shortfall(0), Some((5_000, 11)));
+		run_to_block(11);
+		assert_ok!(Kitties::top_up_stake(Origin::signed(1), 0, 3_000));
+		assert_eq!(Kitties::stake_shortfall(0), Some((2_000, 11)));
+		assert_eq!(Kitties::kitty_stake(0), Some(15_000));
+		// 超出差额的部分增加质押
+		assert_ok!(Kitties::top_up_stake(Origin::signed(1), 0, 3_000));
+		assert_has_event!(Event::<Test>::StakeShortfallCovered(0));
+		assert_eq!(Kitties::stake_shortfall(0), None);
+		assert_eq!(Kitties::kitty_stake(0), Some(16_000));
+		assert_eq!(Balances::reserved_balance(1), 16_000);
+		assert_ok!(Kitties::accept_offer(Origin::signed(1), 0, 2));
+		// 售出后撤销提高的质押，买家只质押拥有者追加的部分，卖家取回全部质押
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_eq!(Balances::reserved_balance(2), 11_000);
+		assert_eq!(Kitties::kitty_stake(0), Some(11_000));
+	});
+}
+
+#[test]
+fn transfer_with_shortfall_releases_only_reserved_stake() {
+	new_test_ext().execute_with(|| {
+		HighDemandOfferThreshold::set(1);
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::sell(Origin::signed(1), 0, Some(1_000)));
+		assert_ok!(Kitties::make_offer(Origin::signed(2), 0, 1_000));
+		assert_eq!(Balances::reserved_balance(1), 10_000 + ListingDeposit::get());
+		assert_ok!(Kitties::transfer(Origin::signed(1), 4, 0));
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_eq!(Balances::reserved_balance(4), 10_000);
+		assert_eq!(Kitties::stake_shortfall(0), None);
+		assert_eq!(Kitties::demand_stake_raise(0), None);
+	});
+}
+
+#[test]
+fn quote_buy_excludes_demand_raise() {
+	new_test_ext().execute_with(|| {
+		HighDemandOfferThreshold::set(1);
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::make_offer(Origin::signed(4), 0, 1_000));
+		assert_eq!(Kitties::kitty_stake(0), Some(15_000));
+		assert_ok!(Kitties::sell(Origin::signed(1), 0, Some(1_000)));
+		// 买家只质押基础金额，报价与实际一致
+		let quote = assert_buy_matches_quote(5, 0);
+		assert_eq!(quote.stake, 10_000);
+		assert_eq!(Kitties::kitty_stake(0), Some(10_000));
+	});
+}
+
+#[test]
+fn high_demand_raise_does_not_compound() {
+	new_test_ext().execute_with(|| {
+		HighDemandOfferThreshold::set(1);
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::make_offer(Origin::signed(2), 0, 1_000));
+		assert_ok!(Kitties::top_up_stake(Origin::signed(1), 0, 5_000));
+		assert_eq!(Kitties::stake_shortfall(0), None);
+		assert_eq!(Kitties::kitty_stake(0), Some(15_000));
+		// 补足后反复撤回和重新报价不会在提高后的质押上继续提高
+		for _ in 0..3 {
+			assert_ok!(Kitties::withdraw_offer(Origin::signed(2), 0));
+			assert_eq!(Kitties::kitty_stake(0), Some(10_000));
+			assert_eq!(Balances::reserved_balance(1), 10_000);
+			assert_ok!(Kitties::make_offer(Origin::signed(2), 0, 1_000));
+			assert_eq!(Kitties::kitty_stake(0), Some(15_000));
+			assert_eq!(Kitties::stake_shortfall(0), Some((5_000, 11)));
+		}
+		// 销毁时退还拥有者的全部质押
+		assert_ok!(Kitties::burn(Origin::signed(1), 0));
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_eq!(Kitties::demand_stake_raise(0), None);
+	});
+}
+
+#[test]
+fn admin_can_call_privileged_extrinsics() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(Kitties::admin(), Some(ADMIN));
+		assert_ok!(Kitties::create_race(Origin::signed(ADMIN), 100, 10));
+		assert_ok!(Kitties::create_race(Origin::root(), 100, 10));
+		assert_noop!(Kitties::create_race(Origin::signed(1), 100, 10), BadOrigin);
+		assert_noop!(Kitties::transfer_admin(Origin::signed(1), 1), BadOrigin);
+	});
+}
+
+#[test]
+fn admin_privileged_calls_record_activity() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(3);
+		// Root调用时没有签名账户
+		assert_ok!(Kitties::create_race(Origin::root(), 100, 10));
+		assert_eq!(Kitties::last_activity(ADMIN), None);
+		assert_ok!(Kitties::create_race(Origin::signed(ADMIN), 100, 10));
+		assert_eq!(Kitties::last_activity(ADMIN), Some(3));
+		System::set_block_number(4);
+		assert_ok!(Kitties::seal_supply(Origin::signed(ADMIN)));
+		assert_eq!(Kitties::last_activity(ADMIN), Some(4));
+		System::set_block_number(5);
+		assert_ok!(Kitties::transfer_admin(Origin::signed(ADMIN), 7));
+		assert_eq!(Kitties::last_activity(ADMIN), Some(5));
+	});
+}
+
+#[test]
+fn transfer_admin_revokes_old_admin() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::transfer_admin(Origin::signed(ADMIN), 7));
+		assert_has_event!(Event::<Test>::AdminChanged(Some(ADMIN), 7));
+		assert_eq!(Kitties::admin(), Some(7));
+		// 旧管理员失去特权
+		assert_noop!(Kitties::create_race(Origin::signed(ADMIN), 100, 10), BadOrigin);
+		assert_noop!(Kitties::transfer_admin(Origin::signed(ADMIN), ADMIN), BadOrigin);
+		assert_ok!(Kitties::create_race(Origin::signed(7), 100, 10));
+		// ForceOrigin仍然可以更换管理员
+		assert_ok!(Kitties::transfer_admin(Origin::root(), 8));
+		assert_has_event!(Event::<Test>::AdminChanged(Some(7), 8));
+	});
+}
+
+#[test]
+fn rarest_kitty_tracks_leader() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(Kitties::rarest_kitty(), None);
+		assert_ok!(Kitties::do_mint(&1, [0x01; 16]));
+		assert_eq!(Kitties::rarest_kitty(), Some((0, 16)));
+		assert_ok!(Kitties::do_mint(&1, [0x03; 16]));
+		assert_eq!(Kitties::rarest_kitty(), Some((1, 32)));
+		// 稀有度相同时保留较早的Kitty
+		assert_ok!(Kitties::do_mint(&1, [0x30; 16]));
+		assert_eq!(Kitties::rarest_kitty(), Some((1, 32)));
+		// 稀有度较低的Kitty不改变最稀有的Kitty
+		assert_ok!(Kitties::do_mint(&1, [0x00; 16]));
+		assert_eq!(Kitties::rarest_kitty(), Some((1, 32)));
+		// 克隆体的稀有度为0
+		assert_ok!(Kitties::clone_kitty(Origin::signed(1), 1));
+		assert_eq!(Kitties::rarest_kitty(), Some((1, 32)));
+	});
+}
+
+#[test]
+fn burning_rarest_kitty_recomputes_leader() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::do_mint(&1, [0x01; 16]));
+		assert_ok!(Kitties::do_mint(&1, [0xff; 16]));
+		assert_ok!(Kitties::do_mint(&1, [0x03; 16]));
+		assert_ok!(Kitties::do_mint(&1, [0x30; 16]));
+		assert_eq!(Kitties::rarest_kitty(), Some((1, 128)));
+		// 销毁其他Kitty不改变最稀有的Kitty
+		assert_ok!(Kitties::burn(Origin::signed(1), 0));
+		assert_eq!(Kitties::rarest_kitty(), Some((1, 128)));
+		assert_ok!(Kitties::burn(Origin::signed(1), 1));
+		assert_eq!(Kitties::rarest_kitty(), Some((2, 32)));
+		assert_ok!(Kitties::burn(Origin::signed(1), 2));
+		assert_eq!(Kitties::rarest_kitty(), Some((3, 32)));
+		assert_ok!(Kitties::burn(Origin::signed(1), 3));
+		assert_eq!(Kitties::rarest_kitty(), None);
+	});
+}
+
+fn sponsor_signature(sponsor: u64, who: u64, kitty_id_1: u32, kitty_id_2: u32) -> TestSignature {
+	let nonce = Kitties::sponsor_nonce(sponsor);
+	TestSignature(sponsor, Kitties::sponsor_payload(&who, kitty_id_1, kitty_id_2, nonce))
+}
+
+#[test]
+fn sponsored_breed_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		let signature = sponsor_signature(2, 3, 0, 1);
+		assert_ok!(Kitties::sponsored_breed(Origin::signed(3), 2, 0, 1, signature.clone()));
+		assert_has_event!(Event::<Test>::SponsoredBreed(2, 3, 2, 10_000));
+		assert_eq!(Kitties::owner(2), Some(3));
+		// 赞助者支付质押，调用者的可用余额不变
+		assert_eq!(Balances::free_balance(2), 10_000_000_000 - 10_000);
+		assert_eq!(Balances::free_balance(3), 9_000);
+		assert_eq!(Balances::reserved_balance(3), 10_000);
+		assert_eq!(Kitties::sponsor_nonce(2), 1);
+		// 签名不能重复使用
+		assert_noop!(
+			Kitties::sponsored_breed(Origin::signed(3), 2, 0, 1, signature),
+			Error::<Test>::InvalidSponsorSignature
+		);
+	});
+}
+
+#[test]
+fn sponsored_breed_failed_invalid_signature() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		// 签名者不是赞助者
+		assert_noop!(
+			Kitties::sponsored_breed(Origin::signed(3), 2, 0, 1, sponsor_signature(4, 3, 0, 1)),
+			Error::<Test>::InvalidSponsorSignature
+		);
+		// 签名授权的是其他调用者
+		assert_noop!(
+			Kitties::sponsored_breed(Origin::signed(3), 2, 0, 1, sponsor_signature(2, 4, 0, 1)),
+			Error::<Test>::InvalidSponsorSignature
+		);
+		// 签名授权的是其他Kitty
+		assert_noop!(
+			Kitties::sponsored_breed(Origin::signed(3), 2, 1, 0, sponsor_signature(2, 3, 0, 1)),
+			Error::<Test>::InvalidSponsorSignature
+		);
+	});
+}
+
+#[test]
+fn sponsored_breed_failed_insolvent_sponsor() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		// 账户3的余额不足以支付质押
+		assert_noop!(
+			Kitties::sponsored_breed(Origin::signed(4), 3, 0, 1, sponsor_signature(3, 4, 0, 1)),
+			Error::<Test>::SponsorCannotPay
+		);
+		assert_eq!(Kitties::sponsor_nonce(3), 0);
+	});
+}
+
+#[test]
+fn last_activity_updates_on_each_extrinsic() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(Kitties::last_activity(1), None);
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_eq!(Kitties::last_activity(1), Some(1));
+		run_to_block(2);
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_eq!(Kitties::last_activity(1), Some(2));
+		run_to_block(3);
+		assert_ok!(Kitties::breed(Origin::signed(1), 0, 1));
+		assert_eq!(Kitties::last_activity(1), Some(3));
+		run_to_block(4);
+		assert_ok!(Kitties::transfer(Origin::signed(1), 2, 2));
+		assert_eq!(Kitties::last_activity(1), Some(4));
+		// 接收Kitty不算作活动
+		assert_eq!(Kitties::last_activity(2), None);
+		run_to_block(5);
+		assert_ok!(Kitties::sell(Origin::signed(1), 0, Some(1_000)));
+		assert_eq!(Kitties::last_activity(1), Some(5));
+		run_to_block(6);
+		assert_ok!(Kitties::buy(Origin::signed(2), 0));
+		assert_eq!(Kitties::last_activity(2), Some(6));
+		assert_eq!(Kitties::last_activity(1), Some(5));
+		// 失败的交易不更新活动
+		run_to_block(7);
+		assert_noop!(Kitties::transfer(Origin::signed(1), 4, 0), Error::<Test>::NotOwner);
+		assert_eq!(Kitties::last_activity(1), Some(5));
+	});
+}
+
+#[test]
+fn last_activity_updates_on_offers_votes_and_withdrawals() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		run_to_block(2);
+		assert_ok!(Kitties::make_offer(Origin::signed(2), 0, 1_000));
+		assert_eq!(Kitties::last_activity(2), Some(2));
+		run_to_block(3);
+		assert_ok!(Kitties::accept_offer(Origin::signed(1), 0, 2));
+		assert_eq!(Kitties::last_activity(1), Some(3));
+		run_to_block(4);
+		assert_ok!(Kitties::vote_featured(Origin::signed(4), 0));
+		assert_eq!(Kitties::last_activity(4), Some(4));
+		run_to_block(5);
+		assert_ok!(Kitties::withdraw_proceeds(Origin::signed(1)));
+		assert_eq!(Kitties::last_activity(1), Some(5));
+	});
+}
+
+#[test]
+fn gamble_won_mints_rarer_kitty() {
+	new_test_ext().execute_with(|| {
+		GambleWinChance::set(Permill::one());
+		assert_ok!(Kitties::do_mint(&1, [0x01; 16]));
+		let free = Balances::free_balance(1);
+		assert_ok!(Kitties::gamble(Origin::signed(1), 0));
+		assert_has_event!(Event::<Test>::GambleWon(1, 0, 1));
+		assert_eq!(Kitties::kitties(0), None);
+		let kitty = Kitties::kitties(1).unwrap();
+		assert!(Kitties::rarity(&kitty) > 16);
+		assert_eq!(Kitties::owner(1), Some(1));
+		// 支付费用，质押转到新的Kitty上
+		assert_eq!(Balances::free_balance(1), free - 1_000);
+		assert_eq!(Balances::reserved_balance(1), 10_000);
+	});
+}
+
+#[test]
+fn gamble_lost_burns_kitty() {
+	new_test_ext().execute_with(|| {
+		GambleWinChance::set(Permill::zero());
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		let free = Balances::free_balance(1);
+		assert_noop!(Kitties::gamble(Origin::signed(2), 0), Error::<Test>::NotOwner);
+		assert_ok!(Kitties::gamble(Origin::signed(1), 0));
+		assert_has_event!(Event::<Test>::GambleLost(1, 0));
+		assert_eq!(Kitties::kitties(0), None);
+		assert_eq!(Kitties::kitties_count(), Some(1));
+		// 质押退还，费用不退还
+		assert_eq!(Balances::free_balance(1), free + 10_000 - 1_000);
+		assert_eq!(Balances::reserved_balance(1), 0);
+	});
+}
+
+#[test]
+fn gamble_failed_clone() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::clone_kitty(Origin::signed(1), 0));
+		assert_noop!(Kitties::gamble(Origin::signed(1), 1), Error::<Test>::CloneCannotGamble);
+	});
+}
+
+#[test]
+fn gamble_helpers_work() {
+	let mut random = [0u8; 16];
+	assert!(Kitties::gamble_wins(&random, Permill::from_parts(1)));
+	assert!(!Kitties::gamble_wins(&random, Permill::zero()));
+	random[..4].copy_from_slice(&500_000u32.to_le_bytes());
+	assert!(!Kitties::gamble_wins(&random, Permill::from_percent(50)));
+	assert!(Kitties::gamble_wins(&random, Permill::from_parts(500_001)));
+	// 随机数没有增加基因位时设置最低的空位
+	assert_eq!(Kitties::rarer_dna([0x01; 16], &[0x01; 16])[0], 0x03);
+	assert_eq!(Kitties::rarer_dna([0x00; 16], &[0xf0; 16]), [0xf0; 16]);
+	assert_eq!(Kitties::rarer_dna([0xff; 16], &[0x00; 16]), [0xff; 16]);
+}
+
+#[test]
+fn schedule_transfer_fires_at_target_block() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_noop!(
+			Kitties::schedule_transfer(Origin::signed(2), 4, 0, 5),
+			Error::<Test>::NotOwner
+		);
+		assert_noop!(
+			Kitties::schedule_transfer(Origin::signed(1), 4, 0, 1),
+			Error::<Test>::DeadlineInPast
+		);
+		assert_ok!(Kitties::schedule_transfer(Origin::signed(1), 4, 0, 5));
+		assert_has_event!(Event::<Test>::TransferScheduled(1, 4, 0, 5));
+		assert_eq!(Kitties::kitty_status(0), KittyStatus::Locked);
+		run_to_block(4);
+		assert_eq!(Kitties::owner(0), Some(1));
+		run_to_block(5);
+		assert_has_event!(Event::<Test>::KittyTransfer(1, 4, 0));
+		assert_eq!(Kitties::owner(0), Some(4));
+		assert_eq!(Kitties::scheduled_transfer(0), None);
+		assert_eq!(Kitties::kitty_status(0), KittyStatus::Idle);
+		assert_eq!(Balances::reserved_balance(4), 10_000);
+	});
+}
+
+#[test]
+fn cancel_scheduled_transfer_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_noop!(
+			Kitties::cancel_scheduled_transfer(Origin::signed(1), 0),
+			Error::<Test>::NoScheduledTransfer
+		);
+		assert_ok!(Kitties::schedule_transfer(Origin::signed(1), 4, 0, 5));
+		assert_noop!(
+			Kitties::cancel_scheduled_transfer(Origin::signed(4), 0),
+			Error::<Test>::NotOwner
+		);
+		assert_ok!(Kitties::cancel_scheduled_transfer(Origin::signed(1), 0));
+		assert_has_event!(Event::<Test>::ScheduledTransferCancelled(1, 0));
+		run_to_block(5);
+		assert_eq!(Kitties::owner(0), Some(1));
+		assert_ok!(Kitties::transfer(Origin::signed(1), 2, 0));
+	});
+}
+
+#[test]
+fn scheduled_kitty_cannot_be_traded() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::sell(Origin::signed(1), 0, Some(1_000)));
+		assert_ok!(Kitties::schedule_transfer(Origin::signed(1), 4, 0, 5));
+		// 预定转移时取消出售
+		assert_eq!(Kitties::kitties_list_for_sales(0), None);
+		assert_noop!(Kitties::transfer(Origin::signed(1), 2, 0), Error::<Test>::KittyScheduled);
+		assert_noop!(
+			Kitties::sell(Origin::signed(1), 0, Some(1_000)),
+			Error::<Test>::KittyScheduled
+		);
+		assert_noop!(Kitties::burn(Origin::signed(1), 0), Error::<Test>::KittyScheduled);
+		assert_noop!(
+			Kitties::schedule_transfer(Origin::signed(1), 2, 0, 6),
+			Error::<Test>::KittyScheduled
+		);
+	});
+}
+
+#[test]
+fn scheduled_transfer_waits_for_acceptance() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::set_require_acceptance(Origin::signed(4), true));
+		assert_ok!(Kitties::schedule_transfer(Origin::signed(1), 4, 0, 5));
+		run_to_block(5);
+		// 到期时改为等待接收者确认
+		assert_has_event!(Event::<Test>::TransferPending(1, 4, 0, 15));
+		assert_eq!(Kitties::owner(0), Some(1));
+		assert_eq!(Kitties::pending_transfer(0), Some((1, 4, 15)));
+		assert_eq!(KittyLocks::<Test>::get(0), Some(KittyLock::PendingTransfer));
+		assert_ok!(Kitties::accept_incoming(Origin::signed(4), 0));
+		assert_eq!(Kitties::owner(0), Some(4));
+		assert_eq!(Balances::reserved_balance(4), 10_000);
+	});
+}
+
+#[test]
+fn scheduled_transfer_failure_unlocks_kitty() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		// 账户3的余额不足以质押
+		assert_ok!(Kitties::schedule_transfer(Origin::signed(1), 3, 0, 5));
+		run_to_block(5);
+		assert_has_event!(Event::<Test>::ScheduledTransferFailed(0));
+		assert_eq!(Kitties::owner(0), Some(1));
+		assert_eq!(Kitties::kitty_status(0), KittyStatus::Idle);
+	});
+}
+
+#[test]
+fn bind_makes_kitty_non_transferable() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::sell(Origin::signed(1), 0, Some(1_000)));
+		assert_ok!(Kitties::make_offer(Origin::signed(2), 0, 1_000));
+		assert_noop!(Kitties::bind(Origin::signed(2), 0), Error::<Test>::NotOwner);
+		assert_ok!(Kitties::bind(Origin::signed(1), 0));
+		assert_has_event!(Event::<Test>::KittyBound(1, 0));
+		assert!(Kitties::is_soulbound(0));
+		// 绑定时取消出售并退还报价
+		assert_eq!(Kitties::kitties_list_for_sales(0), None);
+		assert_eq!(Balances::reserved_balance(2), 0);
+		assert_noop!(Kitties::transfer(Origin::signed(1), 2, 0), Error::<Test>::KittySoulbound);
+		assert_noop!(
+			Kitties::sell(Origin::signed(1), 0, Some(1_000)),
+			Error::<Test>::KittySoulbound
+		);
+		assert_noop!(Kitties::buy(Origin::signed(2), 0), Error::<Test>::KittySoulbound);
+		assert_noop!(
+			Kitties::make_offer(Origin::signed(2), 0, 1_000),
+			Error::<Test>::KittySoulbound
+		);
+		assert_noop!(
+			Kitties::schedule_transfer(Origin::signed(1), 2, 0, 5),
+			Error::<Test>::KittySoulbound
+		);
+		assert_noop!(
+			Kitties::create_bundle(Origin::signed(1), vec![0, 1]),
+			Error::<Test>::KittySoulbound
+		);
+		// 绑定不能撤销
+		assert_noop!(Kitties::bind(Origin::signed(1), 0), Error::<Test>::KittySoulbound);
+		assert!(Kitties::is_soulbound(0));
+	});
+}
+
+#[test]
+fn soulbound_kitty_can_be_burned() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::bind(Origin::signed(1), 0));
+		assert_ok!(Kitties::burn(Origin::signed(1), 0));
+		assert_eq!(Kitties::owner(0), None);
+		assert!(!Kitties::is_soulbound(0));
+		assert_eq!(Balances::reserved_balance(1), 0);
+	});
+}
+
+#[test]
+fn soulbound_kitty_cannot_be_gambled_or_cloned() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::bind(Origin::signed(1), 0));
+		assert_noop!(Kitties::gamble(Origin::signed(1), 0), Error::<Test>::KittySoulbound);
+		assert_noop!(Kitties::clone_kitty(Origin::signed(1), 0), Error::<Test>::KittySoulbound);
+		assert_eq!(Kitties::owner(0), Some(1));
+		assert_eq!(Kitties::owner(1), None);
+		assert_eq!(Balances::reserved_balance(1), 10_000);
+	});
+}
+
+#[test]
+fn soulbound_kitty_cannot_be_abandoned() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::bind(Origin::signed(1), 0));
+		// 放弃后其他账户可以领养，灵魂绑定的Kitty不能放弃
+		assert_noop!(Kitties::abandon(Origin::signed(1), 0), Error::<Test>::KittySoulbound);
+		assert_noop!(Kitties::adopt(Origin::signed(2), 0), Error::<Test>::KittyNotOrphaned);
+		assert_eq!(Kitties::owner(0), Some(1));
+		assert_eq!(Balances::reserved_balance(1), 10_000);
+	});
+}
+
+#[test]
+fn supply_by_generation_counts_generations() {
+	new_test_ext().execute_with(|| {
+		assert!(Kitties::supply_by_generation().is_empty());
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_eq!(Kitties::supply_by_generation(), vec![(0, 2)]);
+		assert_ok!(Kitties::breed(Origin::signed(1), 0, 1));
+		assert_eq!(Kitties::generation_of(2), 1);
+		// 子代比父母中较高的一代多一代
+		assert_ok!(Kitties::breed(Origin::signed(1), 0, 2));
+		assert_eq!(Kitties::generation_of(3), 2);
+		assert_ok!(Kitties::breed(Origin::signed(1), 1, 2));
+		assert_eq!(Kitties::supply_by_generation(), vec![(0, 2), (1, 1), (2, 2)]);
+		// 克隆体与原Kitty同代
+		assert_ok!(Kitties::clone_kitty(Origin::signed(1), 3));
+		assert_eq!(Kitties::generation_of(5), 2);
+		assert_eq!(Kitties::supply_by_generation(), vec![(0, 2), (1, 1), (2, 3)]);
+	});
+}
+
+#[test]
+fn burn_decrements_generation_count() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::breed(Origin::signed(1), 0, 1));
+		assert_ok!(Kitties::burn(Origin::signed(1), 0));
+		assert_eq!(Kitties::supply_by_generation(), vec![(0, 1), (1, 1)]);
+		assert_ok!(Kitties::burn(Origin::signed(1), 2));
+		assert_eq!(Kitties::supply_by_generation(), vec![(0, 1)]);
+		assert_eq!(Kitties::generation_of(2), 0);
+	});
+}
+
+#[test]
+fn accept_offer_picks_any_bidder_and_refunds_others() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::make_offer(Origin::signed(2), 0, 1_000));
+		assert_ok!(Kitties::make_offer(Origin::signed(4), 0, 2_000));
+		assert_ok!(Kitties::make_offer(Origin::signed(5), 0, 3_000));
+		// 接受中间的报价
+		assert_ok!(Kitties::accept_offer(Origin::signed(1), 0, 4));
+		assert_has_event!(Event::<Test>::OfferAccepted(1, 4, 0, 2_000));
+		assert_eq!(Kitties::owner(0), Some(4));
+		assert_eq!(Kitties::proceeds(1), 2_000);
+		assert_eq!(Balances::free_balance(4), 10_000_000_000 - 2_000 - 10_000);
+		// 其他报价者的质押全部退还
+		assert_has_event!(Event::<Test>::OfferCancelled(2, 0, 1_000));
+		assert_has_event!(Event::<Test>::OfferCancelled(5, 0, 3_000));
+		assert_eq!(Balances::reserved_balance(2), 0);
+		assert_eq!(Balances::reserved_balance(5), 0);
+		assert_eq!(Balances::free_balance(2), 10_000_000_000);
+		assert_eq!(Balances::free_balance(5), 10_000_000_000);
+		assert_eq!(Kitties::offer_count(0), 0);
+		assert_eq!(Kitties::offer(0, 2), None);
+		assert_eq!(Kitties::offer(0, 5), None);
+	});
+}
+
+#[test]
+fn on_initialize_processes_at_most_limit_per_block() {
+	new_test_ext().execute_with(|| {
+		MaxScheduledPerBlock::set(2);
+		for kitty_id in 0..5 {
+			assert_ok!(Kitties::create(Origin::signed(1)));
+			assert_ok!(Kitties::schedule_transfer(Origin::signed(1), 4, kitty_id, 5));
+		}
+		let transferred = || (0..5).filter(|kitty_id| Kitties::owner(kitty_id) == Some(4)).count();
+		run_to_block(4);
+		assert_eq!(transferred(), 0);
+		System::set_block_number(5);
+		let weight = Kitties::on_initialize(5);
+		assert_eq!(weight, <() as WeightInfo>::on_initialize(2));
+		assert_eq!(transferred(), 2);
+		assert_eq!(Kitties::agenda_cursor(), Some(5));
+		// 未执行完的任务在之后的区块继续执行
+		run_to_block(6);
+		assert_eq!(transferred(), 4);
+		assert_eq!(Kitties::agenda_cursor(), Some(5));
+		run_to_block(7);
+		assert_eq!(transferred(), 5);
+		assert_eq!(Kitties::agenda_cursor(), None);
+	});
+}
+
+#[test]
+fn on_initialize_resumes_before_current_tasks() {
+	new_test_ext().execute_with(|| {
+		MaxScheduledPerBlock::set(2);
+		for kitty_id in 0..3 {
+			assert_ok!(Kitties::create(Origin::signed(1)));
+			assert_ok!(Kitties::schedule_transfer(Origin::signed(1), 4, kitty_id, 5));
+		}
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::schedule_transfer(Origin::signed(1), 4, 3, 6));
+		run_to_block(5);
+		// 先执行上个区块剩下的任务，再执行当前区块的任务
+		run_to_block(6);
+		assert_eq!(Kitties::owner(3), Some(4));
+		assert_eq!(Kitties::agenda_cursor(), None);
+		assert_eq!((0..4).filter(|kitty_id| Kitties::owner(kitty_id) == Some(4)).count(), 4);
+	});
+}
+
+#[test]
+fn breed_and_list_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::breed_and_list(Origin::signed(1), 0, 1, 5_000));
+		assert_has_event!(Event::<Test>::KittyCreate(1, 2));
+		assert_has_event!(Event::<Test>::KittyListed(1, 2, Some(5_000)));
+		assert_eq!(Kitties::owner(2), Some(1));
+		assert_eq!(Kitties::generation_of(2), 1);
+		assert_eq!(ListForSale::<Test>::get(2), Some(5_000));
+		// 子Kitty质押和挂单押金
+		assert_eq!(Balances::reserved_balance(1), 3 * 10_000 + 100);
+		assert_ok!(Kitties::buy(Origin::signed(2), 2));
+		assert_eq!(Kitties::owner(2), Some(2));
+	});
+}
+
+#[test]
+fn breed_and_list_failed_leaves_no_listing() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_noop!(
+			Kitties::breed_and_list(Origin::signed(1), 0, 0, 5_000),
+			Error::<Test>::SameParentIndex
+		);
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		// 交易尚未开始时挂单失败，繁殖一并回滚
+		TradingStartBlock::set(100);
+		assert_noop!(
+			Kitties::breed_and_list(Origin::signed(1), 0, 1, 5_000),
+			Error::<Test>::TradingNotStarted
+		);
+		assert_eq!(Kitties::kitties(2), None);
+		assert_eq!(ListForSale::<Test>::get(2), None);
+		assert_eq!(Balances::reserved_balance(1), 2 * 10_000);
+	});
+}
+
+#[test]
+fn create_failed_on_cooldown() {
+	new_test_ext().execute_with(|| {
+		CreateCooldown::set(3);
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_eq!(Kitties::last_create(1), Some(1));
+		assert_noop!(Kitties::create(Origin::signed(1)), Error::<Test>::CreateOnCooldown);
+		// 冷却时间只限制同一账户
+		assert_ok!(Kitties::create(Origin::signed(2)));
+		System::set_block_number(3);
+		assert_noop!(Kitties::create(Origin::signed(1)), Error::<Test>::CreateOnCooldown);
+		// 冷却时间结束后可以再次创建
+		System::set_block_number(4);
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_eq!(Kitties::last_create(1), Some(4));
+		assert_noop!(Kitties::create(Origin::signed(1)), Error::<Test>::CreateOnCooldown);
+	});
+}
+
+#[test]
+fn create_without_cooldown_allows_back_to_back() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_eq!(Kitties::owner(1), Some(1));
+		// 没有冷却时间时不记录创建区块
+		assert_eq!(Kitties::last_create(1), None);
+	});
+}
+
+#[test]
+fn owns_checks_current_owner() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert!(Kitties::owns(&1, 0));
+		// 其他账户不是拥有者
+		assert!(!Kitties::owns(&2, 0));
+		// 未知的Kitty
+		assert!(!Kitties::owns(&1, 1));
+		assert_ok!(Kitties::transfer(Origin::signed(1), 2, 0));
+		assert!(!Kitties::owns(&1, 0));
+		assert!(Kitties::owns(&2, 0));
+	});
+}
+
+#[test]
+fn transfer_to_acceptance_required_account_is_pending() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::set_require_acceptance(Origin::signed(2), true));
+		assert_has_event!(Event::<Test>::AcceptanceRequirementSet(2, true));
+		assert_ok!(Kitties::transfer(Origin::signed(1), 2, 0));
+		assert_has_event!(Event::<Test>::TransferPending(1, 2, 0, 11));
+		assert_eq!(Kitties::owner(0), Some(1));
+		assert_eq!(Kitties::pending_transfer(0), Some((1, 2, 11)));
+		// 等待确认期间Kitty被锁定
+		assert_noop!(Kitties::sell(Origin::signed(1), 0, Some(1_000)), Error::<Test>::KittyLocked);
+		assert_noop!(Kitties::transfer(Origin::signed(1), 4, 0), Error::<Test>::KittyLocked);
+		// 只有接收者可以确认
+		assert_noop!(
+			Kitties::accept_incoming(Origin::signed(4), 0),
+			Error::<Test>::NoPendingTransfer
+		);
+		assert_ok!(Kitties::accept_incoming(Origin::signed(2), 0));
+		assert_has_event!(Event::<Test>::IncomingTransferAccepted(2, 0));
+		assert_has_event!(Event::<Test>::KittyTransfer(1, 2, 0));
+		assert_eq!(Kitties::owner(0), Some(2));
+		assert_eq!(Kitties::pending_transfer(0), None);
+		assert_eq!(KittyLocks::<Test>::get(0), None);
+		assert_eq!(Balances::reserved_balance(2), 10_000);
+		// 确认后过期任务已取消
+		run_to_block(11);
+		assert_eq!(Kitties::owner(0), Some(2));
+		assert_noop!(
+			Kitties::accept_incoming(Origin::signed(2), 0),
+			Error::<Test>::NoPendingTransfer
+		);
+	});
+}
+
+#[test]
+fn pending_transfer_expires_back_to_sender() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::set_require_acceptance(Origin::signed(2), true));
+		assert_ok!(Kitties::transfer(Origin::signed(1), 2, 0));
+		run_to_block(10);
+		assert_eq!(Kitties::pending_transfer(0), Some((1, 2, 11)));
+		run_to_block(11);
+		assert_has_event!(Event::<Test>::IncomingTransferExpired(1, 0));
+		assert_eq!(Kitties::owner(0), Some(1));
+		assert_eq!(Kitties::pending_transfer(0), None);
+		assert_noop!(
+			Kitties::accept_incoming(Origin::signed(2), 0),
+			Error::<Test>::NoPendingTransfer
+		);
+		// 过期后Kitty解锁，可以再次转移
+		assert_ok!(Kitties::transfer(Origin::signed(1), 4, 0));
+		assert_eq!(Kitties::owner(0), Some(4));
+	});
+}
+
+#[test]
+fn transfer_to_unflagged_account_is_direct() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::transfer(Origin::signed(1), 2, 0));
+		assert_eq!(Kitties::owner(0), Some(2));
+		assert_eq!(Kitties::pending_transfer(0), None);
+		// 关闭确认后直接接收
+		assert_ok!(Kitties::set_require_acceptance(Origin::signed(4), true));
+		assert_ok!(Kitties::set_require_acceptance(Origin::signed(4), false));
+		assert!(!Kitties::requires_acceptance(4));
+		assert_ok!(Kitties::transfer(Origin::signed(2), 4, 0));
+		assert_eq!(Kitties::owner(0), Some(4));
+	});
+}
+
+#[test]
+fn seal_supply_blocks_minting() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_noop!(Kitties::seal_supply(Origin::signed(1)), BadOrigin);
+		// 管理员也可以封顶总量
+		assert_ok!(Kitties::seal_supply(Origin::signed(ADMIN)));
+		assert_has_event!(Event::<Test>::SupplySealed);
+		assert!(Kitties::is_supply_sealed());
+		assert_noop!(Kitties::seal_supply(Origin::root()), Error::<Test>::SupplySealed);
+		assert_noop!(Kitties::create(Origin::signed(1)), Error::<Test>::SupplySealed);
+		assert_noop!(Kitties::breed(Origin::signed(1), 0, 1), Error::<Test>::SupplySealed);
+		assert_noop!(Kitties::gamble(Origin::signed(1), 0), Error::<Test>::SupplySealed);
+		assert_noop!(Kitties::do_mint(&1, [1; 16]), Error::<Test>::SupplySealed);
+		assert_eq!(Kitties::kitties_count(), Some(2));
+	});
+}
+
+#[test]
+fn seal_supply_keeps_trading_and_burning() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::seal_supply(Origin::root()));
+		assert_ok!(Kitties::transfer(Origin::signed(1), 2, 0));
+		assert_ok!(Kitties::sell(Origin::signed(2), 0, Some(1_000)));
+		assert_ok!(Kitties::buy(Origin::signed(4), 0));
+		assert_eq!(Kitties::owner(0), Some(4));
+		assert_ok!(Kitties::burn(Origin::signed(1), 1));
+		assert_eq!(Kitties::owner(1), None);
+	});
+}
+
+#[test]
+fn order_book_merges_listings_and_auctions() {
+	new_test_ext().execute_with(|| {
+		for _ in 0..4 {
+			assert_ok!(Kitties::create(Origin::signed(1)));
+		}
+		assert_ok!(Kitties::sell(Origin::signed(1), 0, Some(1_000)));
+		assert_ok!(Kitties::sell(Origin::signed(1), 2, Some(2_000)));
+		assert_ok!(Kitties::escalate_to_auction(Origin::signed(1), 2, 10));
+		assert_ok!(Kitties::bid(Origin::signed(2), 2, 2_500));
+		assert_ok!(Kitties::sell(Origin::signed(1), 3, Some(3_000)));
+		assert_ok!(Kitties::escalate_to_auction(Origin::signed(1), 3, 5));
+		let page = Kitties::order_book(0, 10);
+		assert_eq!(
+			page.entries,
+			vec![
+				OrderBookEntry {
+					kitty_id: 0,
+					seller: 1,
+					kind: OrderKind::FixedPrice { price: 1_000, asset: None },
+				},
+				OrderBookEntry {
+					kitty_id: 2,
+					seller: 1,
+					kind: OrderKind::Auction {
+						reserve: 2_000,
+						end: 11,
+						highest_bid: Some((2, 2_500)),
+					},
+				},
+				OrderBookEntry {
+					kitty_id: 3,
+					seller: 1,
+					kind: OrderKind::Auction { reserve: 3_000, end: 6, highest_bid: None },
+				},
+			]
+		);
+		assert_eq!(page.next, None);
+		// 到达结束区块尚未结算的拍卖不包含在内
+		System::set_block_number(6);
+		let ids: Vec<u32> =
+			Kitties::order_book(0, 10).entries.iter().map(|entry| entry.kitty_id).collect();
+		assert_eq!(ids, vec![0, 2]);
+	});
+}
+
+#[test]
+fn order_book_pages_by_kitty_id() {
+	new_test_ext().execute_with(|| {
+		for kitty_id in 0..8 {
+			assert_ok!(Kitties::create(Origin::signed(1)));
+			if kitty_id % 2 == 0 {
+				assert_ok!(Kitties::sell(Origin::signed(1), kitty_id, Some(1_000)));
+			}
+		}
+		let ids = |page: &OrderBookPage<u64, u32, u128, u64, u32>| -> Vec<u32> {
+			page.entries.iter().map(|entry| entry.kitty_id).collect()
+		};
+		let page = Kitties::order_book(0, 2);
+		assert_eq!(ids(&page), vec![0, 2]);
+		assert_eq!(page.next, Some(3));
+		// limit为0时按1处理，下一页总会前进
+		let page = Kitties::order_book(0, 0);
+		assert_eq!(ids(&page), vec![0]);
+		assert_eq!(page.next, Some(1));
+		let page = Kitties::order_book(3, 2);
+		assert_eq!(ids(&page), vec![4, 6]);
+		assert_eq!(page.next, Some(7));
+		let page = Kitties::order_book(7, 2);
+		assert!(page.entries.is_empty());
+		assert_eq!(page.next, None);
+		// 每页数量不超过MaxQueryResults
+		assert_ok!(Kitties::sell(Origin::signed(1), 7, Some(1_000)));
+		for kitty_id in [1, 3, 5] {
+			assert_ok!(Kitties::sell(Origin::signed(1), kitty_id, Some(1_000)));
+		}
+		let page = Kitties::order_book(0, 100);
+		assert_eq!(ids(&page), vec![0, 1, 2, 3, 4]);
+		assert_eq!(page.next, Some(5));
+	});
+}
+
+#[test]
+fn order_book_scan_is_bounded() {
+	new_test_ext().execute_with(|| {
+		MaxOrderBookScan::set(&3);
+		for _ in 0..8 {
+			assert_ok!(Kitties::create(Origin::signed(1)));
+		}
+		assert_ok!(Kitties::sell(Origin::signed(1), 0, Some(1_000)));
+		assert_ok!(Kitties::sell(Origin::signed(1), 7, Some(1_000)));
+		// 只检查了3个ID，从下一个ID继续
+		let page = Kitties::order_book(0, 5);
+		assert_eq!(page.entries.len(), 1);
+		assert_eq!(page.next, Some(3));
+		let page = Kitties::order_book(3, 5);
+		assert!(page.entries.is_empty());
+		assert_eq!(page.next, Some(6));
+		let page = Kitties::order_book(6, 5);
+		assert_eq!(page.entries[0].kitty_id, 7);
+		assert_eq!(page.next, None);
+	});
+}
+
+#[test]
+fn pity_guarantees_rare_child_after_threshold() {
+	new_test_ext().execute_with(|| {
+		// 只有DNA全为1的Kitty是稀有的，随机繁殖几乎不可能得到
+		RareRarity::set(128);
+		PityThreshold::set(2);
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::breed(Origin::signed(1), 0, 1));
+		assert_eq!(Kitties::pity_counter(1), 1);
+		assert_ok!(Kitties::breed(Origin::signed(1), 0, 1));
+		assert_eq!(Kitties::pity_counter(1), 2);
+		// 计数达到阈值，下一次繁殖保证得到稀有Kitty并重置计数
+		assert_ok!(Kitties::breed(Origin::signed(1), 0, 1));
+		assert_has_event!(Event::<Test>::PityBreed(1, 4));
+		assert_eq!(Kitties::kitties(4).unwrap().dna, [0xff; 16]);
+		assert_eq!(Kitties::pity_counter(1), 0);
+		// 计数按账户记录
+		assert_ok!(Kitties::breed(Origin::signed(1), 0, 1));
+		assert_eq!(Kitties::pity_counter(1), 1);
+		assert_eq!(Kitties::pity_counter(2), 0);
+	});
+}
+
+#[test]
+fn pity_disabled_when_threshold_is_zero() {
+	new_test_ext().execute_with(|| {
+		RareRarity::set(128);
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		for _ in 0..3 {
+			assert_ok!(Kitties::breed(Origin::signed(1), 0, 1));
+		}
+		assert_eq!(Kitties::pity_counter(1), 0);
+	});
+}
+
+#[test]
+fn pity_dna_reaches_min_rarity() {
+	assert_eq!(Kitties::pity_dna([0; 16], 0), [0; 16]);
+	let mut expected = [0u8; 16];
+	expected[0] = 0xff;
+	expected[1] = 0b0000_0011;
+	assert_eq!(Kitties::pity_dna([0; 16], 10), expected);
+	// 已有的基因位计入稀有度
+	let mut dna = [0u8; 16];
+	dna[0] = 0b1000_0000;
+	assert_eq!(Kitties::pity_dna(dna, 2)[0], 0b1000_0001);
+	assert_eq!(Kitties::pity_dna([0; 16], 128), [0xff; 16]);
+}
+
+#[test]
+fn export_to_nft_locks_kitty() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::sell(Origin::signed(1), 0, Some(1_000)));
+		assert_ok!(Kitties::make_offer(Origin::signed(2), 0, 500));
+		assert_noop!(Kitties::export_to_nft(Origin::signed(2), 0), Error::<Test>::NotOwner);
+		assert_ok!(Kitties::export_to_nft(Origin::signed(1), 0));
+		assert_has_event!(Event::<Test>::KittyExported(1, 0, 0));
+		assert_eq!(Kitties::exported_nft(0), Some(0));
+		assert_eq!(MockNftBridge::holder(0), Some(1));
+		// 导出后取消出售和报价，Kitty不能在这里使用
+		assert_eq!(ListForSale::<Test>::get(0), None);
+		assert_eq!(Kitties::offer(0, 2), None);
+		assert_eq!(Balances::reserved_balance(2), 0);
+		assert_noop!(Kitties::transfer(Origin::signed(1), 2, 0), Error::<Test>::KittyLocked);
+		assert_noop!(Kitties::sell(Origin::signed(1), 0, Some(1_000)), Error::<Test>::KittyLocked);
+		assert_noop!(Kitties::burn(Origin::signed(1), 0), Error::<Test>::KittyLocked);
+		assert_noop!(Kitties::export_to_nft(Origin::signed(1), 0), Error::<Test>::KittyLocked);
+		assert_eq!(Kitties::owner(0), Some(1));
+	});
+}
+
+#[test]
+fn import_from_nft_restores_kitty() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_noop!(Kitties::import_from_nft(Origin::signed(1), 0), Error::<Test>::NotExported);
+		assert_ok!(Kitties::export_to_nft(Origin::signed(1), 0));
+		assert_ok!(Kitties::import_from_nft(Origin::signed(1), 0));
+		assert_has_event!(Event::<Test>::KittyImported(1, 0));
+		assert_eq!(Kitties::exported_nft(0), None);
+		assert_eq!(MockNftBridge::holder(0), None);
+		assert_ok!(Kitties::transfer(Origin::signed(1), 2, 0));
+		assert_eq!(Kitties::owner(0), Some(2));
+	});
+}
+
+#[test]
+fn import_from_nft_follows_external_holder() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::export_to_nft(Origin::signed(1), 0));
+		// NFT在外部集合中被转给账户2
+		MockNftBridge::transfer(0, 2);
+		assert_noop!(
+			Kitties::import_from_nft(Origin::signed(1), 0),
+			DispatchError::Other("not the NFT holder")
+		);
+		assert_ok!(Kitties::import_from_nft(Origin::signed(2), 0));
+		assert_has_event!(Event::<Test>::KittyTransfer(1, 2, 0));
+		assert_eq!(Kitties::owner(0), Some(2));
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_eq!(Balances::reserved_balance(2), 10_000);
+		assert_eq!(KittyLocks::<Test>::get(0), None);
+	});
+}
+
+#[test]
+fn image_descriptor_is_stable_for_dna() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(Kitties::image_descriptor(0), None);
+		let dna = [0, 1, 17, 33, 2, 64, 15, 31, 16, 32, 255, 255, 7, 8, 100, 200];
+		assert_ok!(Kitties::do_mint(&1, dna));
+		let layer = |kind, variant, palette| ImageLayer { kind, variant, palette };
+		assert_eq!(
+			Kitties::image_descriptor(0),
+			Some(ImageDescriptor {
+				layers: vec![
+					layer(LayerKind::Background, 0, 1),
+					layer(LayerKind::Body, 1, 1),
+					layer(LayerKind::Pattern, 2, 0),
+					layer(LayerKind::Belly, 15, 31),
+					layer(LayerKind::Ears, 0, 0),
+					layer(LayerKind::Eyes, 15, 31),
+					layer(LayerKind::Mouth, 7, 8),
+					layer(LayerKind::Accessory, 4, 8),
+				],
+				clone: false,
+			})
+		);
+		// 不同的DNA得到不同的形象
+		let mut other = dna;
+		other[5] = 65;
+		assert_ok!(Kitties::do_mint(&1, other));
+		assert_ne!(Kitties::image_descriptor(0), Kitties::image_descriptor(1));
+		assert_eq!(
+			Kitties::image_descriptor(1).unwrap().layers[2],
+			layer(LayerKind::Pattern, 2, 1)
+		);
+	});
+}
+
+#[test]
+fn burn_batch_refunds_all_stakes() {
+	new_test_ext().execute_with(|| {
+		for _ in 0..4 {
+			assert_ok!(Kitties::create(Origin::signed(1)));
+		}
+		assert_ok!(Kitties::sell(Origin::signed(1), 0, Some(1_000)));
+		assert_ok!(Kitties::make_offer(Origin::signed(2), 1, 500));
+		assert_eq!(Balances::reserved_balance(1), 4 * 10_000 + 100);
+		let ids: BoundedVec<u32, _> = vec![0, 1, 2].try_into().unwrap();
+		assert_ok!(Kitties::burn_batch(Origin::signed(1), ids));
+		for kitty_id in 0..3 {
+			assert_has_event!(Event::<Test>::KittyBurned(1, kitty_id));
+			assert_eq!(Kitties::owner(kitty_id), None);
+			assert_eq!(Kitties::kitties(kitty_id), None);
+		}
+		// 质押、挂单押金和报价全部退还
+		assert_eq!(Balances::reserved_balance(1), 10_000);
+		assert_eq!(Balances::reserved_balance(2), 0);
+		assert_eq!(ListForSale::<Test>::get(0), None);
+		assert_eq!(Kitties::offer(1, 2), None);
+		assert_eq!(Kitties::owner(3), Some(1));
+	});
+}
+
+#[test]
+fn burn_batch_reverts_on_non_owned_kitty() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::create(Origin::signed(2)));
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		let ids: BoundedVec<u32, _> = vec![0, 1, 2].try_into().unwrap();
+		assert_noop!(Kitties::burn_batch(Origin::signed(1), ids), Error::<Test>::NotOwner);
+		// 重复的ID在第二次时已不属于调用者
+		let ids: BoundedVec<u32, _> = vec![0, 0].try_into().unwrap();
+		assert_noop!(Kitties::burn_batch(Origin::signed(1), ids), Error::<Test>::NotOwner);
+		assert_eq!(Kitties::owner(0), Some(1));
+		assert_eq!(Kitties::owner(2), Some(1));
+		assert_eq!(Balances::reserved_balance(1), 2 * 10_000);
+	});
+}
+
+#[test]
+fn buy_back_by_recent_owner_is_wash_trade() {
+	new_test_ext().execute_with(|| {
+		WashTradeCooldown::set(10);
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::sell(Origin::signed(1), 0, Some(1_000)));
+		assert_ok!(Kitties::buy(Origin::signed(2), 0));
+		assert_eq!(Kitties::recent_owners(0).into_inner(), vec![(1, 1)]);
+		// 通过中间账户转手后立即买回
+		assert_ok!(Kitties::transfer(Origin::signed(2), 4, 0));
+		assert_ok!(Kitties::sell(Origin::signed(4), 0, Some(1_000)));
+		assert_noop!(Kitties::buy(Origin::signed(1), 0), Error::<Test>::WashTradeDetected);
+		assert_noop!(Kitties::buy(Origin::signed(2), 0), Error::<Test>::WashTradeDetected);
+		assert_eq!(Kitties::quote_buy(0, &1), None);
+		// 无关的买家可以购买
+		assert_ok!(Kitties::buy(Origin::signed(5), 0));
+		assert_eq!(Kitties::owner(0), Some(5));
+		// 只保留最近的MaxRecentOwners个拥有者
+		assert_eq!(Kitties::recent_owners(0).into_inner(), vec![(2, 1), (4, 1)]);
+	});
+}
+
+#[test]
+fn buy_back_allowed_after_wash_trade_cooldown() {
+	new_test_ext().execute_with(|| {
+		WashTradeCooldown::set(10);
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::sell(Origin::signed(1), 0, Some(1_000)));
+		assert_ok!(Kitties::buy(Origin::signed(2), 0));
+		assert_ok!(Kitties::sell(Origin::signed(2), 0, Some(1_000)));
+		System::set_block_number(10);
+		assert_noop!(Kitties::buy(Origin::signed(1), 0), Error::<Test>::WashTradeDetected);
+		System::set_block_number(11);
+		assert_ok!(Kitties::buy(Origin::signed(1), 0));
+		assert_eq!(Kitties::owner(0), Some(1));
+		// 冷却时间为0时不记录
+		WashTradeCooldown::set(0);
+		assert_ok!(Kitties::transfer(Origin::signed(1), 4, 0));
+		assert_eq!(Kitties::recent_owners(0).into_inner(), vec![(2, 11)]);
+	});
+}
+
+#[test]
+fn portfolio_value_sums_listed_and_sold_kitties() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(Kitties::portfolio_value(&2), (0, false));
+		for _ in 0..3 {
+			assert_ok!(Kitties::create(Origin::signed(1)));
+		}
+		// 曾经成交的Kitty按成交价格估值
+		assert_ok!(Kitties::sell(Origin::signed(1), 0, Some(1_500)));
+		assert_ok!(Kitties::buy(Origin::signed(2), 0));
+		assert_eq!(Kitties::last_sale_price(0), Some(1_500));
+		// 出售中的Kitty按出售价格估值
+		assert_ok!(Kitties::create(Origin::signed(2)));
+		assert_ok!(Kitties::sell(Origin::signed(2), 3, Some(4_000)));
+		// 没有价格信息的Kitty估值为0
+		assert_ok!(Kitties::create(Origin::signed(2)));
+		assert_eq!(Kitties::portfolio_value(&2), (1_500 + 4_000, false));
+		// 重新出售时使用出售价格
+		assert_ok!(Kitties::sell(Origin::signed(2), 0, Some(2_000)));
+		assert_eq!(Kitties::portfolio_value(&2), (2_000 + 4_000, false));
+		assert_ok!(Kitties::sell(Origin::signed(2), 0, None));
+		assert_eq!(Kitties::portfolio_value(&2), (1_500 + 4_000, false));
+		assert_eq!(Kitties::portfolio_value(&1), (0, false));
+	});
+}
+
+#[test]
+fn portfolio_value_is_truncated_at_max_query_results() {
+	new_test_ext().execute_with(|| {
+		for kitty_id in 0..6 {
+			assert_ok!(Kitties::create(Origin::signed(1)));
+			assert_ok!(Kitties::sell(Origin::signed(1), kitty_id, Some(100)));
+		}
+		assert_eq!(Kitties::portfolio_value(&1), (5 * 100, true));
+	});
+}
+
+#[test]
+fn breed_consumes_breeding_material() {
+	new_test_ext().execute_with(|| {
+		BreedingMaterialPerBreed::set(30);
+		setup_asset(BreedingMaterialAsset::get(), 1, 100);
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::breed(Origin::signed(1), 0, 1));
+		assert_eq!(Assets::balance(BreedingMaterialAsset::get(), 1), 70);
+		assert_ok!(Kitties::breed_premium(Origin::signed(1), 0, 1, 1));
+		assert_eq!(Assets::balance(BreedingMaterialAsset::get(), 1), 40);
+		// 创建不需要材料
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_eq!(Assets::balance(BreedingMaterialAsset::get(), 1), 40);
+	});
+}
+
+#[test]
+fn sponsored_breed_consumes_sponsor_material() {
+	new_test_ext().execute_with(|| {
+		BreedingMaterialPerBreed::set(30);
+		setup_asset(BreedingMaterialAsset::get(), 2, 100);
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		// 调用者没有繁殖材料，由赞助者支付
+		assert_eq!(Assets::balance(BreedingMaterialAsset::get(), 3), 0);
+		assert_ok!(Kitties::sponsored_breed(
+			Origin::signed(3),
+			2,
+			0,
+			1,
+			sponsor_signature(2, 3, 0, 1)
+		));
+		assert_eq!(Kitties::owner(2), Some(3));
+		assert_eq!(Assets::balance(BreedingMaterialAsset::get(), 2), 70);
+		assert_eq!(Assets::balance(BreedingMaterialAsset::get(), 3), 0);
+	});
+}
+
+#[test]
+fn breed_failed_not_enough_breeding_material() {
+	new_test_ext().execute_with(|| {
+		BreedingMaterialPerBreed::set(30);
+		setup_asset(BreedingMaterialAsset::get(), 1, 50);
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_ok!(Kitties::breed(Origin::signed(1), 0, 1));
+		assert_noop!(
+			Kitties::breed(Origin::signed(1), 0, 1),
+			Error::<Test>::NotEnoughBreedingMaterial
+		);
+		// 没有材料的账户不能繁殖
+		assert_ok!(Kitties::create(Origin::signed(2)));
+		assert_ok!(Kitties::create(Origin::signed(2)));
+		assert_noop!(
+			Kitties::breed(Origin::signed(2), 3, 4),
+			Error::<Test>::NotEnoughBreedingMaterial
+		);
+		assert_eq!(Assets::balance(BreedingMaterialAsset::get(), 1), 20);
+		assert_eq!(Kitties::kitties(5), None);
+	});
+}
+
+#[test]
+fn recount_restores_kitties_count() {
+	new_test_ext().execute_with(|| {
+		for _ in 0..4 {
+			assert_ok!(Kitties::create(Origin::signed(1)));
+		}
+		assert_ok!(Kitties::burn(Origin::signed(1), 1));
+		// 计数错误时新的Kitty会覆盖已有的Kitty
+		KittiesCount::<Test>::put(1);
+		assert_noop!(Kitties::recount(Origin::signed(1), 10), BadOrigin);
+		assert_noop!(Kitties::recount(Origin::root(), 0), Error::<Test>::InvalidRecountLimit);
+		// 每次最多遍历limit个Kitty，遍历完成前不修改计数，管理员也可以调用
+		assert_ok!(Kitties::recount(Origin::signed(ADMIN), 1));
+		assert!(RecountCursor::<Test>::get().is_some());
+		assert_eq!(Kitties::kitties_count(), Some(1));
+		for _ in 0..3 {
+			assert_ok!(Kitties::recount(Origin::root(), 1));
+		}
+		assert_has_event!(Event::<Test>::CountReconciled(4));
+		assert_eq!(Kitties::kitties_count(), Some(4));
+		assert_eq!(RecountCursor::<Test>::get(), None);
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_eq!(Kitties::owner(4), Some(1));
+		assert_eq!(Kitties::owner(3), Some(1));
+	});
+}
+
+#[test]
+fn recount_never_lowers_kitties_count() {
+	new_test_ext().execute_with(|| {
+		for _ in 0..3 {
+			assert_ok!(Kitties::create(Origin::signed(1)));
+		}
+		// 销毁最大ID的Kitty后计数保持不变，已销毁的ID不会被重新分配
+		assert_ok!(Kitties::burn(Origin::signed(1), 2));
+		assert_ok!(Kitties::recount(Origin::root(), 10));
+		assert_has_event!(Event::<Test>::CountReconciled(3));
+		assert_ok!(Kitties::create(Origin::signed(1)));
+		assert_eq!(Kitties::owner(3), Some(1));
+		assert_eq!(Kitties::kitties(2), None);
+		// 没有Kitty时也不降低计数
+		KittiesCount::<Test>::put(5);
+		for id in [0, 1, 3] {
+			assert_ok!(Kitties::burn(Origin::signed(1), id));
+		}
+		assert_ok!(Kitties::recount(Origin::root(), 10));
+		assert_eq!(Kitties::kitties_count(), Some(5));
+	});
+}
+
+#[test]
+fn recount_keeps_kitties_created_during_walk() {
+	new_test_ext().execute_with(|| {
+		for _ in 0..3 {
+			assert_ok!(Kitties::create(Origin::signed(1)));
+		}
+		assert_ok!(Kitties::recount(Origin::root(), 1));
+		// 遍历期间新建的Kitty可能落在已遍历的位置，计数仍然包含它
+		assert_ok!(Kitties::create(Origin::signed(2)));
+		assert_ok!(Kitties::recount(Origin::root(), 10));
+		assert_has_event!(Event::<Test>::CountReconciled(4));
+		assert_ok!(Kitties::create(Origin::signed(2)));
+		assert_eq!(Kitties::owner(3), Some(2));
+		assert_eq!(Kitties::owner(4), Some(2));
+	});
+}
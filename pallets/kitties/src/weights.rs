@@ -0,0 +1,55 @@
+//! Weights for pallet_kitties.
+//!
+//! THESE WEIGHTS ARE HAND-WRITTEN ESTIMATES, NOT BENCHMARK RESULTS.
+//!
+//! The `on_initialize` benchmark in `benchmarking.rs` only covers scheduled transfers. The
+//! per-task cost below must also cover the worst agenda task, resolving a race with
+//! `MaxRaceEntrants` entrants, which has no benchmark yet. Until that benchmark is added and
+//! this file is regenerated with
+//! `node-kitties benchmark --pallet pallet_kitties --extrinsic '*'` on reference hardware,
+//! the numbers are not measured.
+
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{
+	traits::Get,
+	weights::{constants::RocksDbWeight, Weight},
+};
+use sp_std::marker::PhantomData;
+
+/// Weight functions needed for pallet_kitties.
+pub trait WeightInfo {
+	fn on_initialize(n: u32) -> Weight;
+}
+
+/// Weights for pallet_kitties using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	// Storage: SubstrateKitties AgendaCursor (r:1 w:1)
+	// Storage: SubstrateKitties Agenda (r:1 w:1)
+	// Storage: SubstrateKitties ScheduledTransfers (r:1 w:1)
+	// Storage: SubstrateKitties KittyLocks (r:1 w:1)
+	// Storage: SubstrateKitties Owner (r:1 w:1)
+	// Storage: System Account (r:2 w:2)
+	fn on_initialize(n: u32) -> Weight {
+		(5_000_000 as Weight)
+			.saturating_add((60_000_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(T::DbWeight::get().reads(2 as Weight))
+			.saturating_add(T::DbWeight::get().reads((12 as Weight).saturating_mul(n as Weight)))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+			.saturating_add(T::DbWeight::get().writes((14 as Weight).saturating_mul(n as Weight)))
+	}
+}
+
+// For backwards compatibility and tests
+impl WeightInfo for () {
+	fn on_initialize(n: u32) -> Weight {
+		(5_000_000 as Weight)
+			.saturating_add((60_000_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(RocksDbWeight::get().reads(2 as Weight))
+			.saturating_add(RocksDbWeight::get().reads((12 as Weight).saturating_mul(n as Weight)))
+			.saturating_add(RocksDbWeight::get().writes(1 as Weight))
+			.saturating_add(RocksDbWeight::get().writes((14 as Weight).saturating_mul(n as Weight)))
+	}
+}
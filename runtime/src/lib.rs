@@ -14,7 +14,10 @@ use sp_consensus_aura::sr25519::AuthorityId as AuraId;
 use sp_core::{crypto::KeyTypeId, OpaqueMetadata};
 use sp_runtime::{
 	create_runtime_str, generic, impl_opaque_keys,
-	traits::{AccountIdLookup, BlakeTwo256, Block as BlockT, IdentifyAccount, NumberFor, Verify},
+	traits::{
+		AccountIdConversion, AccountIdLookup, BlakeTwo256, Block as BlockT, IdentifyAccount,
+		NumberFor, Verify,
+	},
 	transaction_validity::{TransactionSource, TransactionValidity},
 	ApplyExtrinsicResult, MultiSignature,
 };
@@ -31,7 +34,7 @@ pub use frame_support::{
 		constants::{BlockExecutionWeight, ExtrinsicBaseWeight, RocksDbWeight, WEIGHT_PER_SECOND},
 		IdentityFee, Weight,
 	},
-	StorageValue,
+	PalletId, StorageValue,
 };
 pub use pallet_balances::Call as BalancesCall;
 pub use pallet_timestamp::Call as TimestampCall;
@@ -56,6 +59,12 @@ pub type AccountId = <<Signature as Verify>::Signer as IdentifyAccount>::Account
 /// Balance of an account.
 pub type Balance = u128;
 
+/// Index of a kitty.
+pub type KittyIndex = u32;
+
+/// Identifier of an asset in pallet-assets.
+pub type AssetId = u32;
+
 /// Index of a transaction in the chain.
 pub type Index = u32;
 
@@ -260,6 +269,58 @@ parameter_types! {
 	pub const TransactionByteFee: Balance = 1;
 	pub OperationalFeeMultiplier: u8 = 5;
 	pub const StakeForEachKitty: u128 = 1_000;
+	pub const ListingDeposit: Balance = 100;
+	pub const PremiumBreedFee: Balance = 1_000;
+	pub const MaxPremiumTier: u8 = 3;
+	pub const EnableOffchainIndexing: bool = true;
+	pub TokenSymbol: Vec<u8> = b"UNIT".to_vec();
+	pub const TokenDecimals: u8 = 12;
+	pub const KittiesPalletId: PalletId = PalletId(*b"py/kitty");
+	pub const MaxRaceEntrants: u32 = 16;
+	pub RacePrizeSplit: Vec<Permill> =
+		vec![Permill::from_percent(60), Permill::from_percent(30), Permill::from_percent(10)];
+	pub const RoyaltyRate: Permill = Permill::from_percent(5);
+	pub const CloneFee: Balance = 50_000;
+	pub const TradingStartBlock: BlockNumber = 0;
+	pub const FeaturedPeriod: BlockNumber = 7 * DAYS;
+	pub const MarketplaceFee: Permill = Permill::from_percent(2);
+	// DNA共128位，随机Kitty的稀有度约为64
+	pub RarityFeeTiers: Vec<(u32, Permill)> =
+		vec![(80, Permill::from_percent(1)), (96, Permill::from_percent(3))];
+	pub const MaxInstallments: u32 = 12;
+	pub const InstallmentPeriod: BlockNumber = DAYS;
+	pub const InstallmentPenalty: Permill = Permill::from_percent(10);
+	pub const MaxNoteLength: u32 = 256;
+	pub const NoteDepositPerByte: Balance = 10;
+	pub const MaxQueryResults: u32 = 100;
+	pub const ResaleCooldown: BlockNumber = HOURS;
+	pub const SupplyWarningThreshold: Permill = Permill::from_percent(90);
+	pub AllowedAssets: Vec<AssetId> = Vec::new();
+	pub const MaxOffersPerKitty: u32 = 32;
+	pub const MaxOffersPerAccount: u32 = 64;
+	pub const MinAgeBeforeBurn: BlockNumber = 0;
+	pub const MaxBundleSize: u32 = 16;
+	pub const HighDemandOfferThreshold: u32 = 10;
+	pub const HighDemandStakeIncrease: Permill = Permill::from_percent(50);
+	pub const HighDemandGracePeriod: BlockNumber = 3 * DAYS;
+	pub const GambleFee: Balance = 10_000;
+	pub const GambleWinChance: Permill = Permill::from_percent(30);
+	pub const MaxScheduledPerBlock: u32 = 50;
+	pub const CreateCooldown: BlockNumber = 0;
+	pub const IncomingTransferExpiry: BlockNumber = 7 * DAYS;
+	pub const RareRarity: u32 = 80;
+	pub const PityThreshold: u32 = 20;
+	pub const MaxBatchSize: u32 = 32;
+	pub const WashTradeCooldown: BlockNumber = 7 * DAYS;
+	pub const MaxRecentOwners: u32 = 4;
+	pub const BreedingMaterialAsset: AssetId = 0;
+	// 默认不需要繁殖材料
+	pub const BreedingMaterialPerBreed: Balance = 0;
+	pub const MaxAuctionDuration: BlockNumber = 30 * DAYS;
+	pub const MaxFeaturedTallyPerBlock: u32 = 100;
+	pub const MaxOrderBookScan: u32 = 1_000;
+	pub KittiesRecoveryAccount: AccountId = KittiesPalletId::get().into_sub_account(b"recovery");
+	pub KittiesFeeAssetDestination: AccountId = KittiesPalletId::get().into_sub_account(b"assetfee");
 }
 
 impl pallet_transaction_payment::Config for Runtime {
@@ -270,6 +331,30 @@ impl pallet_transaction_payment::Config for Runtime {
 	type FeeMultiplierUpdate = ();
 }
 
+parameter_types! {
+	pub const AssetDeposit: Balance = 1;
+	pub const ApprovalDeposit: Balance = 1;
+	pub const StringLimit: u32 = 50;
+	pub const MetadataDepositBase: Balance = 1;
+	pub const MetadataDepositPerByte: Balance = 1;
+}
+
+impl pallet_assets::Config for Runtime {
+	type Event = Event;
+	type Balance = Balance;
+	type AssetId = AssetId;
+	type Currency = Balances;
+	type ForceOrigin = frame_system::EnsureRoot<AccountId>;
+	type AssetDeposit = AssetDeposit;
+	type MetadataDepositBase = MetadataDepositBase;
+	type MetadataDepositPerByte = MetadataDepositPerByte;
+	type ApprovalDeposit = ApprovalDeposit;
+	type StringLimit = StringLimit;
+	type Freezer = ();
+	type Extra = ();
+	type WeightInfo = pallet_assets::weights::SubstrateWeight<Runtime>;
+}
+
 impl pallet_sudo::Config for Runtime {
 	type Event = Event;
 	type Call = Call;
@@ -280,8 +365,67 @@ impl pallet_kitties::Config for Runtime {
 	type Event = Event;
 	type Currency = Balances;
 	type Randomness = RandomnessCollectiveFlip;
+	type SecondaryRandomness = RandomnessCollectiveFlip;
 	type StakeForEachKitty = StakeForEachKitty;
-	type KittyIndex = u32;
+	type KittyIndex = KittyIndex;
+	type ListingDeposit = ListingDeposit;
+	type ForceOrigin = frame_system::EnsureRoot<AccountId>;
+	type PremiumBreedFee = PremiumBreedFee;
+	type MaxPremiumTier = MaxPremiumTier;
+	type FeeDestination = ();
+	type EnableOffchainIndexing = EnableOffchainIndexing;
+	type TokenSymbol = TokenSymbol;
+	type TokenDecimals = TokenDecimals;
+	type PalletId = KittiesPalletId;
+	type MaxRaceEntrants = MaxRaceEntrants;
+	type RacePrizeSplit = RacePrizeSplit;
+	type RoyaltyRate = RoyaltyRate;
+	type CloneFee = CloneFee;
+	type TradingStartBlock = TradingStartBlock;
+	type FeaturedPeriod = FeaturedPeriod;
+	type MarketplaceFee = MarketplaceFee;
+	type RarityFeeTiers = RarityFeeTiers;
+	type MaxInstallments = MaxInstallments;
+	type InstallmentPeriod = InstallmentPeriod;
+	type InstallmentPenalty = InstallmentPenalty;
+	type MaxNoteLength = MaxNoteLength;
+	type NoteDepositPerByte = NoteDepositPerByte;
+	type MaxQueryResults = MaxQueryResults;
+	type ResaleCooldown = ResaleCooldown;
+	type RecoveryAccount = KittiesRecoveryAccount;
+	type SupplyWarningThreshold = SupplyWarningThreshold;
+	type AssetId = AssetId;
+	type Assets = Assets;
+	type AllowedAssets = AllowedAssets;
+	type FeeAssetDestination = KittiesFeeAssetDestination;
+	type MaxOffersPerKitty = MaxOffersPerKitty;
+	type MaxOffersPerAccount = MaxOffersPerAccount;
+	type MinAgeBeforeBurn = MinAgeBeforeBurn;
+	type MaxBundleSize = MaxBundleSize;
+	type HighDemandOfferThreshold = HighDemandOfferThreshold;
+	type HighDemandStakeIncrease = HighDemandStakeIncrease;
+	type HighDemandGracePeriod = HighDemandGracePeriod;
+	type SponsorSignature = Signature;
+	type SponsorPublic = <Signature as Verify>::Signer;
+	type GambleFee = GambleFee;
+	type GambleWinChance = GambleWinChance;
+	type MaxScheduledPerBlock = MaxScheduledPerBlock;
+	type CreateCooldown = CreateCooldown;
+	type IncomingTransferExpiry = IncomingTransferExpiry;
+	type RareRarity = RareRarity;
+	type PityThreshold = PityThreshold;
+	// 还没有外部NFT集合，导出会被拒绝
+	type NftBridge = ();
+	type MaxBatchSize = MaxBatchSize;
+	type WashTradeCooldown = WashTradeCooldown;
+	type MaxRecentOwners = MaxRecentOwners;
+	type BreedingMaterial = Assets;
+	type BreedingMaterialAsset = BreedingMaterialAsset;
+	type BreedingMaterialPerBreed = BreedingMaterialPerBreed;
+	type MaxAuctionDuration = MaxAuctionDuration;
+	type MaxFeaturedTallyPerBlock = MaxFeaturedTallyPerBlock;
+	type MaxOrderBookScan = MaxOrderBookScan;
+	type WeightInfo = pallet_kitties::weights::SubstrateWeight<Runtime>;
 }
 
 // Create the runtime by composing the FRAME pallets that were previously configured.
@@ -299,6 +443,7 @@ construct_runtime!(
 		Balances: pallet_balances,
 		TransactionPayment: pallet_transaction_payment,
 		Sudo: pallet_sudo,
+		Assets: pallet_assets,
 		// Include the custom logic from the pallet-template in the runtime.
 		SubstrateKitties: pallet_kitties,
 	}
@@ -462,6 +607,69 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl pallet_kitties_runtime_api::KittiesApi<Block, AccountId, KittyIndex, Balance, BlockNumber, AssetId>
+		for Runtime
+	{
+		fn kitty_stake(kitty_id: KittyIndex) -> Option<Balance> {
+			SubstrateKitties::kitty_stake(kitty_id)
+		}
+
+		fn price_info() -> pallet_kitties::PriceInfo<Balance> {
+			SubstrateKitties::price_info()
+		}
+
+		fn quote_buy(kitty_id: KittyIndex, buyer: AccountId) -> Option<pallet_kitties::BuyQuote<Balance>> {
+			SubstrateKitties::quote_buy(kitty_id, &buyer)
+		}
+
+		fn kitties_of(owner: AccountId) -> (Vec<KittyIndex>, bool) {
+			SubstrateKitties::kitties_of(&owner)
+		}
+
+		fn kitties_of_paged(owner: AccountId, start: u32, limit: u32) -> Vec<KittyIndex> {
+			SubstrateKitties::kitties_of_paged(&owner, start, limit)
+		}
+
+		fn kitties_by_ids(ids: Vec<KittyIndex>) -> Vec<Option<pallet_kitties::Kitty>> {
+			SubstrateKitties::kitties_by_ids(ids)
+		}
+
+		fn describe_error(index: u8) -> Option<Vec<u8>> {
+			SubstrateKitties::describe_error(index)
+		}
+
+		fn rarest_kitty() -> Option<(KittyIndex, u32)> {
+			SubstrateKitties::rarest_kitty()
+		}
+
+		fn last_activity(who: AccountId) -> Option<BlockNumber> {
+			SubstrateKitties::last_activity(who)
+		}
+
+		fn supply_by_generation() -> Vec<(u32, u32)> {
+			SubstrateKitties::supply_by_generation()
+		}
+
+		fn owns(who: AccountId, kitty_id: KittyIndex) -> bool {
+			SubstrateKitties::owns(&who, kitty_id)
+		}
+
+		fn order_book(
+			start: KittyIndex,
+			limit: u32,
+		) -> pallet_kitties::OrderBookPage<AccountId, KittyIndex, Balance, BlockNumber, AssetId> {
+			SubstrateKitties::order_book(start, limit)
+		}
+
+		fn image_descriptor(kitty_id: KittyIndex) -> Option<pallet_kitties::ImageDescriptor> {
+			SubstrateKitties::image_descriptor(kitty_id)
+		}
+
+		fn portfolio_value(who: AccountId) -> (Balance, bool) {
+			SubstrateKitties::portfolio_value(&who)
+		}
+	}
+
 	#[cfg(feature = "runtime-benchmarks")]
 	impl frame_benchmarking::Benchmark<Block> for Runtime {
 		fn benchmark_metadata(extra: bool) -> (
@@ -479,7 +687,7 @@ impl_runtime_apis! {
 			list_benchmark!(list, extra, frame_system, SystemBench::<Runtime>);
 			list_benchmark!(list, extra, pallet_balances, Balances);
 			list_benchmark!(list, extra, pallet_timestamp, Timestamp);
-			list_benchmark!(list, extra, pallet_template, TemplateModule);
+			list_benchmark!(list, extra, pallet_kitties, SubstrateKitties);
 
 			let storage_info = AllPalletsWithSystem::storage_info();
 
@@ -517,7 +725,7 @@ impl_runtime_apis! {
 			add_benchmark!(params, batches, frame_system, SystemBench::<Runtime>);
 			add_benchmark!(params, batches, pallet_balances, Balances);
 			add_benchmark!(params, batches, pallet_timestamp, Timestamp);
-			add_benchmark!(params, batches, pallet_kitties, TemplateModule);
+			add_benchmark!(params, batches, pallet_kitties, SubstrateKitties);
 
 			Ok(batches)
 		}